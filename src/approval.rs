@@ -0,0 +1,336 @@
+use crate::bacchus::Error;
+use crate::events::{EventData, EventStatus};
+use crate::manifest;
+use crate::notifications;
+use crate::participation;
+use crate::reliability;
+use crate::repository::Repository;
+use crate::store::StoreError;
+use poise::serenity_prelude::{
+    ButtonStyle, ChannelId, ComponentInteraction, CreateActionRow, CreateAllowedMentions,
+    CreateButton, CreateChannel, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateMessage, GuildId, Http, MessageId, PermissionOverwrite, PermissionOverwriteType,
+    Permissions, RoleId, UserId,
+};
+use tracing::{info, warn};
+
+/// Custom id of the buttons of a join request, followed by approve|deny:<event id>:<user id>
+pub const REQUEST_PREFIX: &str = "join_request:";
+const REQUESTS_CHANNEL_NAME: &str = "join-requests";
+
+/// Reads the custom id of a request's button: whether it approves, the event and the requester
+pub fn parse_custom_id(custom_id: &str) -> Option<(bool, i64, u64)> {
+    let mut parts = custom_id.strip_prefix(REQUEST_PREFIX)?.split(':');
+    let approve = match parts.next()? {
+        "approve" => true,
+        "deny" => false,
+        _ => return None,
+    };
+    let event_id = parts.next()?.parse().ok()?;
+    let user_id = parts.next()?.parse().ok()?;
+    match parts.next() {
+        None => Some((approve, event_id, user_id)),
+        Some(_) => None,
+    }
+}
+
+fn buttons(event_id: i64, user_id: u64) -> CreateActionRow {
+    CreateActionRow::Buttons(vec![
+        CreateButton::new(format!(
+            "{}approve:{}:{}",
+            REQUEST_PREFIX, event_id, user_id
+        ))
+        .label("Approve")
+        .style(ButtonStyle::Success),
+        CreateButton::new(format!("{}deny:{}:{}", REQUEST_PREFIX, event_id, user_id))
+            .label("Deny")
+            .style(ButtonStyle::Danger),
+    ])
+}
+
+/// The channel of [category] only the event's managers can see, where join requests are posted
+pub async fn create_requests_channel(
+    http: &Http,
+    guild_id: GuildId,
+    category: ChannelId,
+    manager_role: RoleId,
+    bot: UserId,
+) -> Result<ChannelId, Error> {
+    let channel = guild_id
+        .create_channel(
+            http,
+            CreateChannel::new(REQUESTS_CHANNEL_NAME)
+                .category(category)
+                .topic("Approve or deny who joins the event")
+                .permissions(vec![
+                    PermissionOverwrite {
+                        allow: Permissions::empty(),
+                        deny: Permissions::VIEW_CHANNEL,
+                        kind: PermissionOverwriteType::Role(guild_id.everyone_role()),
+                    },
+                    PermissionOverwrite {
+                        allow: Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES,
+                        deny: Permissions::empty(),
+                        kind: PermissionOverwriteType::Role(manager_role),
+                    },
+                    PermissionOverwrite {
+                        allow: Permissions::VIEW_CHANNEL
+                            | Permissions::MANAGE_CHANNELS
+                            | Permissions::SEND_MESSAGES,
+                        deny: Permissions::empty(),
+                        kind: PermissionOverwriteType::Member(bot),
+                    },
+                ]),
+        )
+        .await?;
+
+    Ok(channel.id)
+}
+
+async fn tell(http: &Http, user_id: UserId, text: String) {
+    if let Err(e) = notifications::dm(http, user_id, &text).await {
+        warn!("Couldn't DM {} about their join request: {}", user_id, e);
+    }
+}
+
+/// Asks the managers of an invite-only event to let [user_id] in.
+/// Asking while a request is pending does nothing
+pub async fn request(
+    http: &Http,
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+    user_id: UserId,
+) -> Result<(), Error> {
+    let channel_id = match event.requests_channel_id {
+        Some(channel_id) => ChannelId::new(channel_id),
+        None => return Err(Error::from("The event doesn't take join requests")),
+    };
+    match db.get_join_request(event_id, u64::from(user_id)).await {
+        Ok(_) => return Ok(()),
+        Err(StoreError::NotFound) => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    // Managers may rather let in those who show up
+    let mut content = format!("<@{}> asks to join {}", user_id, event.name);
+    let record = db.get_reliability(event.server_id, u64::from(user_id)).await?;
+    if reliability::has_misses(&record) {
+        content += &format!("\nTheir record: {}", reliability::describe(&record));
+    }
+    let message = channel_id
+        .send_message(
+            http,
+            CreateMessage::new()
+                .content(content)
+                .allowed_mentions(CreateAllowedMentions::new())
+                .components(vec![buttons(event_id, u64::from(user_id))]),
+        )
+        .await?;
+    db.insert_join_request(event_id, u64::from(user_id), u64::from(message.id))
+        .await?;
+
+    info!(
+        "{} asked to join event {}(id {})",
+        user_id, event.name, event_id
+    );
+    tell(
+        http,
+        user_id,
+        format!(
+            "Your request to join {} was sent to its managers, you'll be told when they answer",
+            event.name
+        ),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Drops the pending request of [user_id], and its message.
+/// Returns whether there was one
+pub async fn withdraw(
+    http: &Http,
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+    user_id: UserId,
+) -> Result<bool, Error> {
+    let message_id = match db.get_join_request(event_id, u64::from(user_id)).await {
+        Ok(message_id) => message_id,
+        Err(StoreError::NotFound) => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    db.delete_join_request(event_id, u64::from(user_id)).await?;
+
+    if let Some(channel_id) = event.requests_channel_id {
+        if let Err(e) = ChannelId::new(channel_id)
+            .delete_message(http, MessageId::new(message_id))
+            .await
+        {
+            warn!(
+                "Couldn't delete the join request of {} for event {}: {}",
+                user_id, event.name, e
+            );
+        }
+    }
+
+    Ok(true)
+}
+
+/// Approves or denies the request whose button was clicked, and tells the requester
+pub async fn decide(
+    http: &Http,
+    db: &Repository,
+    interaction: &ComponentInteraction,
+) -> Result<(), Error> {
+    let (approve, event_id, user_id) = match parse_custom_id(&interaction.data.custom_id) {
+        Some(parsed) => parsed,
+        None => return Ok(()),
+    };
+    let user_id = UserId::new(user_id);
+
+    let refuse = |text: String| {
+        CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(text)
+                .ephemeral(true),
+        )
+    };
+
+    let (_, event) = match db.get_event_by_id(event_id).await {
+        Ok(found) => found,
+        Err(StoreError::NotFound) => {
+            interaction
+                .create_response(
+                    http,
+                    refuse(String::from("This event doesn't exist anymore")),
+                )
+                .await?;
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let is_manager = interaction
+        .member
+        .as_ref()
+        .is_some_and(|m| m.roles.contains(&RoleId::new(event.manager_role_id)));
+    if !is_manager {
+        interaction
+            .create_response(
+                http,
+                refuse(format!("Only the managers of {} can do that", event.name)),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    match db.get_join_request(event_id, u64::from(user_id)).await {
+        Ok(_) => {}
+        Err(StoreError::NotFound) => {
+            interaction
+                .create_response(
+                    http,
+                    refuse(String::from("This request was already answered")),
+                )
+                .await?;
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    }
+    if approve && (event.status != EventStatus::Published || event.archived_at.is_some()) {
+        interaction
+            .create_response(
+                http,
+                refuse(format!("{} can't be joined anymore", event.name)),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    let guild_id = GuildId::new(event.server_id);
+    if approve {
+        // The rules may have changed since they asked
+        if let Err(reason) =
+            participation::check_eligibility(http, db, guild_id, event_id, user_id).await?
+        {
+            interaction
+                .create_response(
+                    http,
+                    refuse(format!(
+                        "<@{}> can't join {}: {}",
+                        user_id, event.name, reason
+                    )),
+                )
+                .await?;
+            return Ok(());
+        }
+    }
+
+    db.delete_join_request(event_id, u64::from(user_id)).await?;
+    let verdict = if approve {
+        participation::join(http, db, guild_id, event_id, &event, user_id).await?;
+        "approved"
+    } else {
+        // Lets them ask again later
+        manifest::unreact(http, &event, user_id).await;
+        "denied"
+    };
+
+    interaction
+        .create_response(
+            http,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(format!(
+                        "<@{}> asked to join {}: {} by <@{}>",
+                        user_id, event.name, verdict, interaction.user.id
+                    ))
+                    .allowed_mentions(CreateAllowedMentions::new())
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+
+    info!(
+        "{} {} the request of {} to join event {}(id {})",
+        interaction.user.id, verdict, user_id, event.name, event_id
+    );
+    tell(
+        http,
+        user_id,
+        if approve {
+            format!(
+                "You're in ! Your request to join {} was approved",
+                event.name
+            )
+        } else {
+            format!("Your request to join {} was denied", event.name)
+        },
+    )
+    .await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_button_ids() {
+        assert_eq!(
+            parse_custom_id("join_request:approve:12:34"),
+            Some((true, 12, 34))
+        );
+        assert_eq!(
+            parse_custom_id("join_request:deny:12:34"),
+            Some((false, 12, 34))
+        );
+        assert_eq!(parse_custom_id("join_request:maybe:12:34"), None);
+        assert_eq!(parse_custom_id("join_request:deny:12"), None);
+        assert_eq!(parse_custom_id("join_request:deny:12:34:56"), None);
+        assert_eq!(parse_custom_id("date_poll:12"), None);
+    }
+}
@@ -0,0 +1,277 @@
+use crate::bacchus::{remove_event, Error};
+use crate::events::{EventData, EventMode};
+use crate::layout::MAX_CATEGORY_CHANNELS;
+use crate::repository::Repository;
+use crate::scheduler;
+use crate::store::StoreError;
+use poise::serenity_prelude::{
+    ChannelId, ChannelType, CreateChannel, EditChannel, EditThread, GuildChannel, GuildId, Http,
+    PermissionOverwrite, PermissionOverwriteType, Permissions, RoleId, UserId,
+};
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+const ARCHIVE_CATEGORY_NAME: &str = "Archive";
+const DAY: i64 = 24 * 60 * 60;
+
+/// Whether an archive made at [archived_at] has outlived a retention of [retention_days] at [now].
+/// Archives are kept forever without a retention
+pub fn expired(archived_at: i64, retention_days: Option<u32>, now: i64) -> bool {
+    retention_days.is_some_and(|days| now >= archived_at + i64::from(days) * DAY)
+}
+
+/// Name of a new archive category, after the [existing] categories of the server:
+/// Archive, then Archive 2, Archive 3...
+pub fn archive_category_name<'a>(existing: impl Iterator<Item = &'a str>) -> String {
+    let last = existing
+        .filter_map(|name| match name.strip_prefix(ARCHIVE_CATEGORY_NAME)?.trim() {
+            "" => Some(1),
+            number => number.parse::<u32>().ok(),
+        })
+        .max();
+    match last {
+        None => String::from(ARCHIVE_CATEGORY_NAME),
+        Some(last) => format!("{} {}", ARCHIVE_CATEGORY_NAME, last + 1),
+    }
+}
+
+/// Overwrites of an archived channel: the event's participants and managers may still read it,
+/// nobody but the bot may write in it anymore
+pub fn read_only_overwrites(
+    guild_id: GuildId,
+    event: &EventData,
+    bot: UserId,
+) -> Vec<PermissionOverwrite> {
+    let read = Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY;
+    let write = Permissions::SEND_MESSAGES
+        | Permissions::SEND_MESSAGES_IN_THREADS
+        | Permissions::CREATE_PUBLIC_THREADS
+        | Permissions::CREATE_PRIVATE_THREADS
+        | Permissions::ADD_REACTIONS
+        | Permissions::CONNECT;
+
+    vec![
+        PermissionOverwrite {
+            allow: Permissions::empty(),
+            deny: Permissions::VIEW_CHANNEL,
+            kind: PermissionOverwriteType::Role(guild_id.everyone_role()),
+        },
+        PermissionOverwrite {
+            allow: read,
+            deny: write,
+            kind: PermissionOverwriteType::Role(RoleId::new(event.participant_role_id)),
+        },
+        PermissionOverwrite {
+            allow: read,
+            deny: write,
+            kind: PermissionOverwriteType::Role(RoleId::new(event.manager_role_id)),
+        },
+        PermissionOverwrite {
+            allow: read | Permissions::MANAGE_CHANNELS | Permissions::SEND_MESSAGES,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Member(bot),
+        },
+    ]
+}
+
+/// The server's archive category, with room for [needed] more channels among [guild_channels].
+/// A new one is created the first time an event is archived,
+/// when the configured one is gone, and when it is full
+async fn archive_category(
+    http: &Http,
+    db: &Repository,
+    guild_id: GuildId,
+    guild_channels: &HashMap<ChannelId, GuildChannel>,
+    needed: usize,
+    bot: UserId,
+) -> Result<ChannelId, Error> {
+    let mut settings = db.get_guild_settings(u64::from(guild_id)).await?;
+
+    if let Some(category_id) = settings.archive_category_id {
+        let category_id = ChannelId::new(category_id);
+        if let Some(category) = guild_channels.get(&category_id) {
+            let used = guild_channels
+                .values()
+                .filter(|c| c.parent_id == Some(category_id))
+                .count();
+            if category.kind == ChannelType::Category && used + needed <= MAX_CATEGORY_CHANNELS {
+                return Ok(category_id);
+            }
+        }
+    }
+
+    let name = archive_category_name(
+        guild_channels
+            .values()
+            .filter(|c| c.kind == ChannelType::Category)
+            .map(|c| c.name.as_str()),
+    );
+    let category = guild_id
+        .create_channel(
+            http,
+            CreateChannel::new(&name)
+                .kind(ChannelType::Category)
+                .permissions(vec![
+                    PermissionOverwrite {
+                        allow: Permissions::empty(),
+                        deny: Permissions::VIEW_CHANNEL,
+                        kind: PermissionOverwriteType::Role(guild_id.everyone_role()),
+                    },
+                    PermissionOverwrite {
+                        allow: Permissions::VIEW_CHANNEL | Permissions::MANAGE_CHANNELS,
+                        deny: Permissions::empty(),
+                        kind: PermissionOverwriteType::Member(bot),
+                    },
+                ]),
+        )
+        .await?;
+    settings.archive_category_id = Some(u64::from(category.id));
+    db.set_guild_settings(u64::from(guild_id), settings).await?;
+
+    info!("Created archive category {} on server {}", name, guild_id);
+
+    Ok(category.id)
+}
+
+/// Makes an event read-only and marks it archived.
+/// The channels of category events move to the server's archive category and their category is deleted,
+/// threads are locked. Roles are kept, so that former participants can still read the channels
+pub async fn archive(
+    http: &Http,
+    db: &Repository,
+    bot: UserId,
+    id: i64,
+    event: &EventData,
+) -> Result<(), Error> {
+    let guild_id = GuildId::new(event.server_id);
+    let mut channel_ids = db.get_channels_by_event_id(id).await?;
+
+    if event.mode == EventMode::Category {
+        // Channels created by hand in the category belong to the event as well,
+        // register them so that deleting the archive deletes them too
+        let guild_channels = guild_id.channels(http).await?;
+        let extra: Vec<u64> = guild_channels
+            .values()
+            .filter(|c| c.parent_id == Some(ChannelId::new(event.category_id)))
+            .map(|c| u64::from(c.id))
+            .filter(|c| !channel_ids.contains(c))
+            .collect();
+        if !extra.is_empty() {
+            db.insert_channels(id, extra.clone()).await?;
+            channel_ids.extend(extra);
+        }
+
+        // Threads aren't listed with the server's channels, and follow their parent
+        let moved: Vec<ChannelId> = channel_ids
+            .iter()
+            .map(|c| ChannelId::new(*c))
+            .filter(|c| guild_channels.contains_key(c))
+            .collect();
+        // Checked before moving anything, a full category would leave the event half archived
+        let archive_id =
+            archive_category(http, db, guild_id, &guild_channels, moved.len(), bot).await?;
+        let overwrites = read_only_overwrites(guild_id, event, bot);
+
+        for channel_id in moved {
+            // Join requests stay between managers
+            let edit = if Some(u64::from(channel_id)) == event.requests_channel_id {
+                EditChannel::new().category(archive_id)
+            } else {
+                EditChannel::new()
+                    .category(archive_id)
+                    .permissions(overwrites.clone())
+            };
+            channel_id.edit(http, edit).await?;
+        }
+
+        if let Err(e) = ChannelId::new(event.category_id).delete(http).await {
+            warn!(
+                "Couldn't delete the category of archived event {}: {}",
+                event.name, e
+            );
+        }
+    }
+
+    // Threads can still be reopened by a message, lock them
+    for channel_id in &channel_ids {
+        let channel = ChannelId::new(*channel_id).to_channel(http).await;
+        if let Some(thread) = channel.ok().and_then(|c| c.guild()) {
+            if thread.thread_metadata.is_some() {
+                if let Err(e) = thread
+                    .id
+                    .edit_thread(http, EditThread::new().locked(true).archived(true))
+                    .await
+                {
+                    warn!(
+                        "Couldn't lock thread {} of event {}: {}",
+                        thread.id, event.name, e
+                    );
+                }
+            }
+        }
+    }
+
+    db.archive_event(id, scheduler::now()).await?;
+    info!(
+        "Archived event {}(id {}) on server {}",
+        event.name, id, guild_id
+    );
+
+    Ok(())
+}
+
+/// Deletes the archives that outlived their server's retention
+pub async fn purge_expired(http: &Http, db: &Repository) -> Result<(), Error> {
+    let now = scheduler::now();
+    let mut retentions: HashMap<u64, Option<u32>> = HashMap::new();
+
+    for (id, event) in db.get_archived_events().await? {
+        let retention = match retentions.get(&event.server_id) {
+            Some(retention) => *retention,
+            None => {
+                let retention = match db.get_guild_settings(event.server_id).await {
+                    Ok(settings) => settings.archive_retention_days,
+                    Err(StoreError::NotFound) => None,
+                    Err(e) => return Err(e.into()),
+                };
+                retentions.insert(event.server_id, retention);
+                retention
+            }
+        };
+
+        let archived_at = event.archived_at.unwrap_or(now);
+        if expired(archived_at, retention, now) {
+            if let Err(e) = remove_event(http, db, id, &event, None).await {
+                warn!(
+                    "Couldn't delete expired archive {}(id {}): {}",
+                    event.name, id, e
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_archive_categories() {
+        assert_eq!(archive_category_name(["Events", "General"].into_iter()), "Archive");
+        assert_eq!(archive_category_name(["Archive"].into_iter()), "Archive 2");
+        assert_eq!(
+            archive_category_name(["Archive", "Archive 3", "Archive 2", "Archives"].into_iter()),
+            "Archive 4"
+        );
+    }
+
+    #[test]
+    fn retention() {
+        assert!(!expired(0, None, i64::MAX));
+        assert!(!expired(1_000, Some(1), 1_000 + DAY - 1));
+        assert!(expired(1_000, Some(1), 1_000 + DAY));
+        assert!(expired(1_000, Some(30), 1_000 + 31 * DAY));
+    }
+}
@@ -1,19 +1,42 @@
 #![allow(dead_code)]
 
+use crate::approval;
+use crate::archive;
+use crate::bracket::{self, EntrantSource, MatchId, Seeding};
+use crate::checkin;
+use crate::composition;
+use crate::config::Config;
 use crate::events::{
-    delete_event, delete_server_manager_role, get_all_events, get_channels_by_event_id,
-    get_event_by_channel, get_server_manager_role_id, insert_channels, insert_event,
-    insert_server_manager_role, DatabasePool, EventData,
+    Ban, BracketFormat, CheckIn, DatePoll, Eligibility, Entrant, EventChange, EventData, EventMode,
+    EventStatus, MatchReport, TeamSlot,
 };
-use futures::future::try_join_all;
+use crate::manifest;
+use crate::layout::{parse_layout, ChannelKind, ChannelSpec, Template};
+use crate::notifications;
+use crate::scheduler::{self, format_time, parse_time, voice_open_at, voice_overwrite};
+use crate::participation;
+use crate::polls;
+use crate::reliability;
+use crate::repository::Repository;
+use crate::settings::{GuildSettings, SettingKey};
+use crate::store::{StoreError, StoreResult};
+use crate::teams::{self, TeamSplit};
+use crate::transcript;
+use poise::ChoiceParameter;
+use futures::future::{join_all, try_join_all};
+use std::sync::Arc;
+use tracing::{info, warn};
 use poise::serenity_prelude::ChannelId;
 use poise::serenity_prelude::{
-    Attachment, ChannelType, CreateChannel, CreateEmbed, CreateEmbedFooter, CreateMessage,
-    EditRole, PermissionOverwrite, PermissionOverwriteType, Permissions, Role, RoleId, User,
+    Attachment, ChannelType, CreateAllowedMentions, CreateAttachment, CreateChannel, CreateForumPost, CreateMessage,
+    CreateThread, EditMessage, GuildId, Http, MessageId,
+    EditRole, GuildChannel, PermissionOverwrite, PermissionOverwriteType, Permissions, Role, RoleId,
+    User, UserId,
 };
 
 pub struct Data {
-    pub(crate) conn: DatabasePool,
+    pub(crate) db: Repository,
+    pub(crate) config: Arc<Config>,
 } // User data, which is stored and accessible in all command invocations
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Context<'a> = poise::Context<'a, Data, Error>;
@@ -21,20 +44,154 @@ pub type Context<'a> = poise::Context<'a, Data, Error>;
 #[poise::command(
     slash_command,
     prefix_command,
-    subcommands("create", "delete", "list", "member")
+    subcommands(
+        "create",
+        "publish",
+        "cancel",
+        "complete",
+        "reschedule",
+        "poll_dates",
+        "poll_lock",
+        "history",
+        "delete",
+        "archive",
+        "list",
+        "member",
+        "rules",
+        "slots",
+        "teams",
+        "bracket",
+        "checkin",
+        "attendance",
+        "member_reliability",
+        "channel"
+    )
 )]
 pub async fn event(ctx: Context<'_>) -> Result<(), Error> {
     ctx.say("I am a prefix command").await?;
     Ok(())
 }
 
+/// Returns the event owning the channel the command was ran in.
+/// Channels created by hand inside an event's category belong to that event as well
+async fn current_event(ctx: Context<'_>) -> StoreResult<(i64, EventData)> {
+    match ctx
+        .data()
+        .db
+        .get_event_by_channel(u64::from(ctx.channel_id()))
+        .await
+    {
+        Err(StoreError::NotFound) => {}
+        found => return found,
+    }
+
+    match ctx.guild_channel().await.and_then(|c| c.parent_id) {
+        Some(category_id) => {
+            ctx.data()
+                .db
+                .get_event_by_category(u64::from(category_id))
+                .await
+        }
+        None => Err(StoreError::NotFound),
+    }
+}
+
+/// What /event create takes besides the event's name
+#[derive(Default)]
+struct CreateOptions {
+    short_description: Option<String>,
+    description: Option<String>,
+    thumbnail: Option<Attachment>,
+    picture: Option<Attachment>,
+    template: Option<String>,
+    announce_in: Option<GuildChannel>,
+    voice: Option<bool>,
+    stage: Option<bool>,
+    starts_at: Option<String>,
+    ends_at: Option<String>,
+    lock_voice: Option<bool>,
+    mode: Option<EventMode>,
+    draft: Option<bool>,
+    approval: Option<bool>,
+}
+
+/// Creates a new event, and sends a poll for people to enlist.
+/// The slash command takes every option, the prefix command the ones it always took
+fn create() -> poise::Command<Data, Error> {
+    let mut command = create_slash();
+    command.prefix_action = create_prefix().prefix_action;
+    command
+}
+
 /// Creates a new event, and sends a poll for people to enlist
-#[poise::command(
-    prefix_command,
-    slash_command,
-    required_permissions = "MANAGE_CHANNELS"
-)]
-async fn create(
+// poise parses optional prefix arguments by trying every combination,
+// which doesn't scale to this many options: the prefix command has its own
+#[poise::command(slash_command, rename = "create", required_permissions = "MANAGE_CHANNELS")]
+#[allow(clippy::too_many_arguments)] // Each argument is a slash command option
+async fn create_slash(
+    ctx: Context<'_>,
+    #[description = "How shall this event be named ?"] name: String,
+
+    #[description = "(Optional) Provide a short description of the event"]
+    short_description: Option<String>,
+
+    #[description = "Describe the proceedings"] description: Option<String>,
+
+    #[description = "A thumbnail for your event."] thumbnail: Option<Attachment>,
+
+    #[description = "A picture for your event."] picture: Option<Attachment>,
+
+    #[description = "(Optional) The channel layout to use, see /template list"]
+    #[autocomplete = "autocomplete_template"]
+    template: Option<String>,
+
+    #[description = "(Optional) Where to post the event, instead of the server's announcement channel"]
+    #[channel_types("Text", "News")]
+    announce_in: Option<GuildChannel>,
+
+    #[description = "(Optional) Also create a voice channel"] voice: Option<bool>,
+
+    #[description = "(Optional) Also create a stage channel"] stage: Option<bool>,
+
+    #[description = "(Optional) When the event starts, e.g. 2024-12-31 20:00 (UTC) or a Discord timestamp"]
+    starts_at: Option<String>,
+
+    #[description = "(Optional) When the event ends"] ends_at: Option<String>,
+
+    #[description = "(Optional) Only let participants into voice channels between starts_at and ends_at"]
+    lock_voice: Option<bool>,
+
+    #[description = "(Optional) A category with channels (default), or a single thread, which uses no channel slots"]
+    mode: Option<EventMode>,
+
+    #[description = "(Optional) Prepare the event in private, announce it later with /event publish"]
+    draft: Option<bool>,
+
+    #[description = "(Optional) Joining asks the event's managers, who approve or deny each request"]
+    approval: Option<bool>,
+) -> Result<(), Error> {
+    let options = CreateOptions {
+        short_description,
+        description,
+        thumbnail,
+        picture,
+        template,
+        announce_in,
+        voice,
+        stage,
+        starts_at,
+        ends_at,
+        lock_voice,
+        mode,
+        draft,
+        approval,
+    };
+    create_event(ctx, name, options).await
+}
+
+/// Creates a new event, and sends a poll for people to enlist
+#[poise::command(prefix_command, rename = "create", required_permissions = "MANAGE_CHANNELS")]
+async fn create_prefix(
     ctx: Context<'_>,
     #[description = "How shall this event be named ?"] name: String,
 
@@ -47,13 +204,43 @@ async fn create(
 
     #[description = "A picture for your event."] picture: Option<Attachment>,
 ) -> Result<(), Error> {
+    let options = CreateOptions {
+        short_description,
+        description,
+        thumbnail,
+        picture,
+        ..CreateOptions::default()
+    };
+    create_event(ctx, name, options).await
+}
+
+async fn create_event(ctx: Context<'_>, name: String, options: CreateOptions) -> Result<(), Error> {
+    let CreateOptions {
+        short_description,
+        description,
+        thumbnail,
+        picture,
+        template,
+        announce_in,
+        voice,
+        stage,
+        starts_at,
+        ends_at,
+        lock_voice,
+        mode,
+        draft,
+        approval,
+    } = options;
     let guild_id = ctx
         .guild_id()
         .ok_or(Error::from("That command can only be ran in a server"))?;
     let http = ctx.http();
 
     let creator_role_id = RoleId::from(
-        get_server_manager_role_id(&ctx.data().conn.get().unwrap(), u64::from(guild_id))
+        ctx.data()
+            .db
+            .get_server_manager_role_id(u64::from(guild_id))
+            .await
             .expect("Server doesn't have a role for creating events.\nPlease call /init"),
     );
 
@@ -70,12 +257,136 @@ async fn create(
         return Ok(());
     }
 
+    let settings = ctx
+        .data()
+        .db
+        .get_guild_settings(u64::from(guild_id))
+        .await
+        .unwrap_or_default();
+
+    let status = match draft {
+        Some(true) => EventStatus::Draft,
+        _ => EventStatus::Published,
+    };
+    // Checked before creating anything so a bad channel doesn't leave roles behind.
+    // Drafts are announced by /event publish
+    let announcement_channel = match status {
+        EventStatus::Draft if announce_in.is_some() => {
+            ctx.reply("Drafts are announced with /event publish, give announce_in to it instead")
+                .await?;
+            return Ok(());
+        }
+        EventStatus::Draft => None,
+        _ => match announcement_channel(ctx, &settings, announce_in, Some(ctx.channel_id())).await? {
+            Some(channel) => Some(channel),
+            None => return Ok(()),
+        },
+    };
+
+    let mode = mode.unwrap_or_default();
+    // Thread events open in the server's thread channel, or here
+    let thread_parent = match mode {
+        EventMode::Category => None,
+        EventMode::Thread => {
+            if template.is_some() || voice.is_some() || stage.is_some() || lock_voice.is_some() {
+                ctx.reply("Thread events are a single thread, they can't have a template, voice or stage channels")
+                    .await?;
+                return Ok(());
+            }
+            if approval.unwrap_or(false) {
+                ctx.reply("Thread events have no channel for their managers only, they can't require approval")
+                    .await?;
+                return Ok(());
+            }
+            let channel_id = settings
+                .thread_channel_id
+                .map(ChannelId::new)
+                .unwrap_or(ctx.channel_id());
+            match channel_id.to_channel(http).await.ok().and_then(|c| c.guild()) {
+                Some(channel)
+                    if channel.guild_id == guild_id
+                        && matches!(channel.kind, ChannelType::Text | ChannelType::Forum) =>
+                {
+                    Some(channel)
+                }
+                _ => {
+                    ctx.reply("Thread events must be opened in a text or forum channel, please run this in one or change it with /config set thread_channel")
+                        .await?;
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    let layout = match template {
+        Some(template) => {
+            match ctx
+                .data()
+                .db
+                .get_template(u64::from(guild_id), template.clone())
+                .await
+            {
+                Ok(template) => parse_layout(&template.layout)?,
+                Err(StoreError::NotFound) => {
+                    ctx.reply(format!(
+                        "There is no template named {} on this server, see /template list",
+                        template
+                    ))
+                    .await?;
+                    return Ok(());
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        None => settings.channel_layout(&ctx.data().config),
+    };
+    let mut layout = layout;
+    if voice.unwrap_or(false) {
+        layout.push(ChannelSpec {
+            name: String::from("voice"),
+            kind: ChannelKind::Voice,
+        });
+    }
+    if stage.unwrap_or(false) {
+        layout.push(ChannelSpec {
+            name: String::from("stage"),
+            kind: ChannelKind::Stage,
+        });
+    }
+
+    let (starts_at, ends_at) = match (
+        starts_at.as_deref().map(parse_time).transpose(),
+        ends_at.as_deref().map(parse_time).transpose(),
+    ) {
+        (Ok(starts_at), Ok(ends_at)) => (starts_at, ends_at),
+        (Err(e), _) | (_, Err(e)) => {
+            ctx.reply(format!("Invalid date: {}", e)).await?;
+            return Ok(());
+        }
+    };
+    if let (Some(start), Some(end)) = (starts_at, ends_at) {
+        if end <= start {
+            ctx.reply("The event must end after it starts").await?;
+            return Ok(());
+        }
+    }
+    let timed_voice = lock_voice.unwrap_or(false);
+    if timed_voice && (starts_at.is_none() || ends_at.is_none()) {
+        ctx.reply("lock_voice needs both starts_at and ends_at").await?;
+        return Ok(());
+    }
+    // Untimed voice channels are always open
+    let voice_open = !timed_voice || voice_open_at(starts_at, ends_at, scheduler::now());
+
     let created_roles: Vec<Role> = try_join_all(vec![
         guild_id.create_role(
             ctx.http(),
-            EditRole::new().name(format!("{}-manager", name)),
+            EditRole::new().name(settings.manager_role_name(&name)),
+        ),
+        guild_id.create_role(
+            ctx.http(),
+            EditRole::new().name(settings.player_role_name(&name)),
         ),
-        guild_id.create_role(ctx.http(), EditRole::new().name(format!("{}-player", name))),
     ])
     .await
     .map_err(Error::from)?
@@ -84,7 +395,7 @@ async fn create(
 
     let (manager, player) = (&created_roles[0], &created_roles[1]);
 
-    println!(
+    info!(
         "Created two roles for new {} event on server {}",
         name, guild_id
     );
@@ -92,6 +403,168 @@ async fn create(
     let member = guild_id.member(&http, ctx.author().id).await?;
     member.add_role(&http, manager).await?;
 
+    // Category events get their own channels, thread events a single thread
+    let (category_id, mut channels) = match &thread_parent {
+        None => {
+            create_category(ctx, guild_id, &name, &layout, manager, player, voice_open).await?
+        }
+        Some(parent) => {
+            let thread = open_event_thread(ctx, parent, &name, description.as_deref()).await?;
+            (thread, vec![u64::from(thread)])
+        }
+    };
+
+    // Created last so that it doesn't shift the channels of the layout
+    let requests_channel_id = if approval.unwrap_or(false) {
+        let bot = ctx.cache().current_user().id;
+        let channel = approval::create_requests_channel(
+            http,
+            guild_id,
+            category_id,
+            manager.id,
+            bot,
+        )
+        .await?;
+        channels.push(u64::from(channel));
+        Some(u64::from(channel))
+    } else {
+        None
+    };
+
+    // Drafts are previewed in the event's first text channel, which only its members can see
+    let manifest_channel = match announcement_channel {
+        Some(channel) => channel,
+        None => {
+            let preview = match mode {
+                EventMode::Category => layout
+                    .iter()
+                    .position(|spec| spec.kind == ChannelKind::Text)
+                    .unwrap_or(0),
+                EventMode::Thread => 0,
+            };
+            ChannelId::new(channels[preview])
+                .to_channel(http)
+                .await?
+                .guild()
+                .ok_or_else(|| Error::from("The event's channel vanished"))?
+        }
+    };
+
+    let mut data = EventData {
+        name: name.clone(),
+        short_description,
+        description,
+        thumbnail: thumbnail.map(|x| x.url),
+        picture: picture.map(|x| x.url),
+        max_participants: None,
+
+        server_id: u64::from(guild_id),
+        manager_role_id: u64::from(manager.id),
+        participant_role_id: u64::from(player.id),
+        manifest_id: 0,
+        manifest_channel_id: u64::from(manifest_channel.id),
+        category_id: u64::from(category_id),
+        rsvp_emoji: settings.rsvp_emoji(&ctx.data().config),
+        starts_at,
+        ends_at,
+        timed_voice,
+        voice_open,
+        mode,
+        archived_at: None,
+        status,
+        requests_channel_id,
+        transcript_exported_at: None,
+    };
+
+    let builder = CreateMessage::new()
+        .embed(manifest::manifest_embed(&data, &ctx.author().name))
+        .content(manifest::banner(status, &settings.announcement_text()));
+    let answer = manifest::post(http, &manifest_channel, &data, builder).await?;
+    data.manifest_id = u64::from(answer.id);
+
+    let event_id = ctx.data().db.insert_event(data).await?;
+
+    info!(
+        "Inserted new event {} from server {} in database",
+        name.clone(),
+        guild_id
+    );
+
+    ctx.data()
+        .db
+        .insert_channels(event_id, channels)
+        .await?;
+
+    info!(
+        "Inserted new channels related to event {} from server {} in database",
+        name, guild_id
+    );
+
+    if status == EventStatus::Draft {
+        ctx.reply(format!(
+            "Drafted {} in <#{}>, announce it with /event publish",
+            name, manifest_channel.id
+        ))
+        .await?;
+    } else if manifest_channel.id != ctx.channel_id() {
+        ctx.reply(format!("Announced {} in <#{}>", name, manifest_channel.id))
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Where to announce an event: [announce_in], then the server's announcement channel, then [fallback].
+/// Replies to the author and returns [None] when there is no valid channel
+async fn announcement_channel(
+    ctx: Context<'_>,
+    settings: &GuildSettings,
+    announce_in: Option<GuildChannel>,
+    fallback: Option<ChannelId>,
+) -> Result<Option<GuildChannel>, Error> {
+    let channel = match announce_in {
+        Some(channel) => channel,
+        None => {
+            let channel_id = match settings.announcement_channel_id.map(ChannelId::new).or(fallback) {
+                Some(channel_id) => channel_id,
+                None => {
+                    ctx.reply("Where should it be announced ? Give announce_in, or set the server's announcement_channel with /config set")
+                        .await?;
+                    return Ok(None);
+                }
+            };
+            match channel_id.to_channel(ctx.http()).await.ok().and_then(|c| c.guild()) {
+                Some(channel) => channel,
+                None => {
+                    ctx.reply("The announcement channel of this server doesn't exist anymore, please change it with /config set")
+                        .await?;
+                    return Ok(None);
+                }
+            }
+        }
+    };
+    if Some(channel.guild_id) != ctx.guild_id() {
+        ctx.reply("The event must be announced in a channel of this server")
+            .await?;
+        return Ok(None);
+    }
+
+    Ok(Some(channel))
+}
+
+/// Creates an event's category and the channels of [layout] inside it.
+/// Returns the category and the channels
+async fn create_category(
+    ctx: Context<'_>,
+    guild_id: GuildId,
+    name: &str,
+    layout: &[ChannelSpec],
+    manager: &Role,
+    player: &Role,
+    voice_open: bool,
+) -> Result<(ChannelId, Vec<u64>), Error> {
+    let http = ctx.http();
+
     let everyone_role = guild_id
         .roles(http)
         .await?
@@ -114,7 +587,8 @@ async fn create(
         PermissionOverwrite {
             allow: Permissions::MANAGE_CHANNELS
                 | Permissions::VIEW_CHANNEL
-                | Permissions::SEND_MESSAGES,
+                | Permissions::SEND_MESSAGES
+                | Permissions::CONNECT,
             deny: Permissions::empty(),
             kind: PermissionOverwriteType::Role(manager.id),
         },
@@ -127,124 +601,147 @@ async fn create(
         },
     ];
 
+    // Same as the text channels, except participants may be kept out until the event starts
+    let mut voice_permissions = channel_permissions.clone();
+    voice_permissions[1] = voice_overwrite(player.id, voice_open);
+
     let category = guild_id
         .create_channel(
             http,
-            CreateChannel::new(&name)
+            CreateChannel::new(name)
                 .permissions(channel_permissions.clone())
                 .kind(ChannelType::Category),
         )
         .await?;
 
-    println!(
+    info!(
         "Created category for new event {} on server {}",
         name, guild_id
     );
 
-    // Create channel
-    let general_channel = guild_id
-        .create_channel(
-            http,
-            CreateChannel::new("general")
-                .permissions(channel_permissions)
-                .category(category.id),
-        )
-        .await?;
-
-    println!(
-        "Created new general text channel for event {} on server {}",
-        name, guild_id
-    );
-
-    let mut embed = CreateEmbed::new()
-        .title(&name)
-        .description(description.clone().unwrap_or_default())
-        .field("Creator", &ctx.author().name, true)
-        .footer(CreateEmbedFooter::new("React with ✅ to join the event"));
-
-    if let Some(pic) = &picture {
-        embed = embed.image(pic.clone().url);
-    }
-
-    if let Some(thumb) = &thumbnail {
-        embed = embed.thumbnail(thumb.clone().url)
-    }
+    // Create channels
+    let mut channels = Vec::new();
+    let mut thread_parent = None;
+    for spec in layout {
+        let channel_id = match spec.kind {
+            ChannelKind::Thread => {
+                // parse_layout makes sure a text channel comes before every thread
+                let parent: ChannelId = thread_parent
+                    .ok_or_else(|| Error::from("A thread must come after a text channel"))?;
+                parent
+                    .create_thread(
+                        http,
+                        CreateThread::new(&spec.name).kind(spec.kind.channel_type()),
+                    )
+                    .await?
+                    .id
+            }
+            kind => {
+                let channel = guild_id
+                    .create_channel(
+                        http,
+                        CreateChannel::new(&spec.name)
+                            .kind(kind.channel_type())
+                            .permissions(match kind {
+                                ChannelKind::Voice | ChannelKind::Stage => {
+                                    voice_permissions.clone()
+                                }
+                                _ => channel_permissions.clone(),
+                            })
+                            .category(category.id),
+                    )
+                    .await?;
+                if kind == ChannelKind::Text {
+                    thread_parent = Some(channel.id);
+                }
+                channel.id
+            }
+        };
+        channels.push(u64::from(channel_id));
 
-    if let Some(decr) = &short_description {
-        embed = embed.field("Summary", decr, false);
+        info!(
+            "Created new {} channel for event {} on server {}",
+            spec.name, name, guild_id
+        );
     }
 
-    let builder = CreateMessage::new()
-        .embed(embed)
-        .content(":trumpet: :trumpet: :trumpet: NEW EVENT :trumpet: :trumpet: :trumpet:");
-    let answer = ctx.channel_id().send_message(ctx.http(), builder).await?;
-
-    println!(
-        "Posted embed regarding new event {} on server {}",
-        name, guild_id
-    );
-
-    answer.react(ctx.http(), '✅').await?;
-
-    println!(
-        "Reacted to embed regarding new event {} on server {}",
-        name, guild_id
-    );
-
-    let event_id = insert_event(
-        &ctx.data().conn.get().unwrap(),
-        EventData {
-            name: name.clone(),
-            short_description,
-            description,
-            thumbnail: thumbnail.map(|x| x.url),
-            picture: picture.map(|x| x.url),
-            max_participants: None,
-
-            server_id: u64::from(guild_id),
-            manager_role_id: u64::from(manager.id),
-            participant_role_id: u64::from(player.id),
-            manifest_id: u64::from(answer.id),
-            manifest_channel_id: u64::from(ctx.channel_id()),
-            category_id: u64::from(category.id),
-        },
-    )?;
+    Ok((category.id, channels))
+}
 
-    println!(
-        "Inserted new event {} from server {} in database",
-        name.clone(),
-        guild_id
-    );
+/// Opens a thread event in [parent]: a private thread in a text channel, or a post in a forum
+async fn open_event_thread(
+    ctx: Context<'_>,
+    parent: &GuildChannel,
+    name: &str,
+    description: Option<&str>,
+) -> Result<ChannelId, Error> {
+    let http = ctx.http();
 
-    insert_channels(
-        &ctx.data().conn.get().unwrap(),
-        event_id,
-        vec![u64::from(general_channel.id)],
-    )?;
+    let thread = if parent.kind == ChannelType::Forum {
+        parent
+            .id
+            .create_forum_post(
+                http,
+                CreateForumPost::new(
+                    name,
+                    CreateMessage::new().content(description.unwrap_or(name)),
+                ),
+            )
+            .await?
+    } else {
+        parent
+            .id
+            .create_thread(
+                http,
+                CreateThread::new(name)
+                    .kind(ChannelType::PrivateThread)
+                    .invitable(false),
+            )
+            .await?
+    };
+    thread.id.add_thread_member(http, ctx.author().id).await?;
 
-    println!(
-        "Inserted new channels related to event {} from server {} in database",
-        name, guild_id
+    info!(
+        "Opened thread for new event {} on server {}",
+        name, parent.guild_id
     );
 
-    Ok(())
+    Ok(thread.id)
 }
 
-/// Deletes the event whose channel you're currently in
+/// Deletes the event whose channel you're currently in, or the event with the given id
 #[poise::command(
     prefix_command,
     slash_command,
     required_permissions = "MANAGE_CHANNELS"
 )]
-async fn delete(ctx: Context<'_>) -> Result<(), Error> {
-    let (id, event) =
-        get_event_by_channel(&ctx.data().conn.get().unwrap(), u64::from(ctx.channel_id())).expect(
-            "Failed to get related event (are you running this in a managed event channel ?)",
-        );
+async fn delete(
+    ctx: Context<'_>,
+    #[description = "(Optional) The event to delete, as shown by /event list"] id: Option<i64>,
+) -> Result<(), Error> {
     let http = ctx.http();
     let guild_id = ctx
         .guild_id()
-        .expect("This command can only be ran in a server");
+        .ok_or(Error::from("That command can only be ran in a server"))?;
+
+    let (id, event) = match id {
+        Some(id) => match ctx.data().db.get_event_by_id(id).await {
+            Ok((id, event)) if event.server_id == u64::from(guild_id) => (id, event),
+            _ => {
+                ctx.reply(format!("There is no event #{} on this server", id))
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => match current_event(ctx).await {
+            Ok(found) => found,
+            Err(StoreError::NotFound) => {
+                ctx.reply("Failed to get related event (are you running this command in a managed event channel ?)").await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        },
+    };
 
     // REQUIRE MANAGER ROLE
     /*
@@ -257,35 +754,70 @@ async fn delete(ctx: Context<'_>) -> Result<(), Error> {
     ctx.reply(format!("Deleting event {}. Goodbye !", event.name))
         .await?;
 
-    try_join_all(vec![
-        guild_id.delete_role(http, RoleId::from(event.manager_role_id)),
-        guild_id.delete_role(http, RoleId::from(event.participant_role_id)),
-    ])
-    .await?;
+    remove_event(http, &ctx.data().db, id, &event, Some(ctx.author().id)).await
+}
+
+/// Deletes the roles, channels and manifest of an event, then forgets it.
+/// The conversations of its channels are exported first, to the server's log channel or [recipient]'s DMs
+pub(crate) async fn remove_event(
+    http: &Http,
+    db: &Repository,
+    id: i64,
+    event: &EventData,
+    recipient: Option<UserId>,
+) -> Result<(), Error> {
+    let guild_id = GuildId::new(event.server_id);
+
+    let log_channel = match db.get_guild_settings(event.server_id).await {
+        Ok(settings) => settings.log_channel_id,
+        Err(StoreError::NotFound) => None,
+        Err(e) => return Err(e.into()),
+    };
+    let mut channels_ids = db.get_channels_by_event_id(id).await?;
+    // A transcript that can't be sent, like to closed DMs, must not keep the event alive.
+    // A deletion retried after a later step failed already sent it
+    if event.transcript_exported_at.is_none() {
+        match transcript::export(http, event, &channels_ids, log_channel, recipient).await {
+            Ok(()) => db.set_transcript_exported(id, scheduler::now()).await?,
+            Err(e) => warn!("Couldn't export the transcript of event {}: {}", event.name, e),
+        }
+    }
+
+    // Roles may have been deleted by hand
+    let slot_roles = db.get_slots(id).await?.into_iter().filter_map(|slot| slot.role_id);
+    let team_roles = db.get_teams(id).await?.into_iter().map(|team| team.role_id);
+    let roles = [event.manager_role_id, event.participant_role_id]
+        .into_iter()
+        .chain(slot_roles)
+        .chain(team_roles);
+    for role_id in roles {
+        if let Err(e) = guild_id.delete_role(http, RoleId::new(role_id)).await {
+            warn!("Couldn't delete role {} of event {}: {}", role_id, event.name, e);
+        }
+    }
 
-    println!(
+    info!(
         "Deleted event roles for {} on server {}",
         event.name,
         u64::from(guild_id)
     );
 
     //Delete owned channels + category
-    let channels_ids = get_channels_by_event_id(
-        &ctx.data()
-            .conn
-            .get()
-            .expect("Couldnt get a reference to database"),
-        id,
-    )?;
-    try_join_all(
-        channels_ids
-            .iter()
-            .chain(vec![event.category_id].iter())
-            .map(|x| ChannelId::new(*x).delete(http)),
-    )
-    .await?;
+    // Threads go away with their channel, and people may have deleted a channel by hand:
+    // a channel that is already gone must not keep the event alive
+    // The "category" of a thread event is its thread, which is already one of its channels,
+    // and archives have already lost theirs
+    if event.mode == EventMode::Category && event.archived_at.is_none() {
+        channels_ids.push(event.category_id);
+    }
+    let deletions = join_all(channels_ids.iter().map(|x| ChannelId::new(*x).delete(http))).await;
+    for (channel_id, result) in channels_ids.iter().zip(deletions) {
+        if let Err(e) = result {
+            warn!("Couldn't delete channel {} of event {}: {}", channel_id, event.name, e);
+        }
+    }
 
-    println!(
+    info!(
         "Deleted channels related to event {} on server {}",
         event.name,
         u64::from(guild_id)
@@ -297,59 +829,680 @@ async fn delete(ctx: Context<'_>) -> Result<(), Error> {
         .delete_message(http, event.manifest_id)
         .await;
 
-    println!("Deleted event {} from server {}", event.name, guild_id);
+    info!("Deleted event {} from server {}", event.name, guild_id);
 
-    delete_event(&ctx.data().conn.get().unwrap(), id)?;
+    db.delete_event(id).await?;
 
-    println!("Wiped event {}, id {} from database", event.name, id);
+    info!("Wiped event {}, id {} from database", event.name, id);
 
     Ok(())
 }
 
-/// List all managed events
-#[poise::command(prefix_command, slash_command)]
-async fn list(ctx: Context<'_>) -> Result<(), Error> {
-    let db = ctx.data().conn.get()?;
-    let event_store = get_all_events(&db)?;
-
-    if event_store.len() == 0 {
-        ctx.reply("No events registered. Use `event create` to register one !")
-            .await?;
-    } else {
-        let body = event_store
-            .iter()
-            .map(|(_id, event)| format!("{}", event.name).to_string())
-            .collect::<Vec<String>>()
-            .join("\n");
-        ctx.reply(body).await?;
+/// Announces the draft event whose channel you're in, so that people can join it
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn publish(
+    ctx: Context<'_>,
+    #[description = "(Optional) Where to post the event, instead of the server's announcement channel"]
+    #[channel_types("Text", "News")]
+    announce_in: Option<GuildChannel>,
+) -> Result<(), Error> {
+    let http = ctx.http();
+    let (id, mut event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    if !event.status.can_become(EventStatus::Published) {
+        ctx.reply(format!(
+            "{} is {}, only drafts can be published",
+            event.name,
+            event.status.as_str()
+        ))
+        .await?;
+        return Ok(());
     }
 
+    let settings = ctx
+        .data()
+        .db
+        .get_guild_settings(event.server_id)
+        .await
+        .unwrap_or_default();
+    // The current channel is one of the event's, people can't see it
+    let channel = match announcement_channel(ctx, &settings, announce_in, None).await? {
+        Some(channel) => channel,
+        None => return Ok(()),
+    };
+
+    // The announcement reuses the draft's embed, the preview goes away
+    let preview_channel = ChannelId::new(event.manifest_channel_id);
+    let preview_id = MessageId::new(event.manifest_id);
+    event.status = EventStatus::Published;
+    let embed = match preview_channel.message(http, preview_id).await {
+        Ok(preview) if !preview.embeds.is_empty() => {
+            manifest::rebuild(preview.embeds[0].clone(), &event)
+        }
+        _ => manifest::manifest_embed(&event, &ctx.author().name),
+    };
+    let slots = ctx.data().db.get_slots(id).await?;
+    let members = ctx.data().db.get_slot_members(id).await?;
+    let builder = CreateMessage::new()
+        .embed(embed)
+        .content(manifest::banner(event.status, &settings.announcement_text()))
+        .components(composition::components(id, &event, &slots, &members));
+    let answer = manifest::post(http, &channel, &event, builder).await?;
+    let _ = preview_channel.delete_message(http, preview_id).await;
+
+    ctx.data()
+        .db
+        .set_manifest(id, u64::from(channel.id), u64::from(answer.id))
+        .await?;
+    ctx.data().db.set_status(id, EventStatus::Published).await?;
+    ctx.data()
+        .db
+        .insert_event_change(
+            id,
+            EventChange {
+                changed_at: scheduler::now(),
+                author_id: u64::from(ctx.author().id),
+                field: String::from("status"),
+                old_value: Some(EventStatus::Draft.as_str().to_string()),
+                new_value: Some(EventStatus::Published.as_str().to_string()),
+                reason: None,
+            },
+        )
+        .await?;
+
+    info!(
+        "Published event {}(id {}) on server {}",
+        event.name, id, event.server_id
+    );
+    ctx.reply(format!("Announced {} in <#{}>", event.name, channel.id))
+        .await?;
+
     Ok(())
 }
 
-#[poise::command(
-    prefix_command,
-    slash_command,
-    subcommands("add", "remove", "add_manager")
-)]
-async fn member(ctx: Context<'_>) -> Result<(), Error> {
-    Ok(())
+/// Cancels the event whose channel you're in and tells its participants, its channels are kept
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn cancel(
+    ctx: Context<'_>,
+    #[description = "(Optional) Why, shown to the participants"]
+    #[rest]
+    reason: Option<String>,
+) -> Result<(), Error> {
+    change_status(ctx, EventStatus::Cancelled, reason).await
 }
 
-/// Adds a participant to the event whose channel you're in right now
-#[poise::command(prefix_command, slash_command)]
-async fn add(ctx: Context<'_>, user: User) -> Result<(), Error> {
-    let http = ctx.http();
-    let guild_id = ctx
-        .guild_id()
-        .expect("This command can only be ran in a server");
+/// Marks the event whose channel you're in as over
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn complete(ctx: Context<'_>) -> Result<(), Error> {
+    change_status(ctx, EventStatus::Completed, None).await
+}
 
-    ctx.defer_ephemeral().await?;
+/// Moves the managed event the command was ran from to [status], and restyles its manifest.
+/// Participants are told when it is cancelled
+async fn change_status(
+    ctx: Context<'_>,
+    status: EventStatus,
+    reason: Option<String>,
+) -> Result<(), Error> {
+    let (id, mut event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    if !event.status.can_become(status) {
+        ctx.reply(format!(
+            "{} is {}, it can't be {} anymore",
+            event.name,
+            event.status.as_str(),
+            status.as_str()
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    ctx.data().db.set_status(id, status).await?;
+    ctx.data()
+        .db
+        .insert_event_change(
+            id,
+            EventChange {
+                changed_at: scheduler::now(),
+                author_id: u64::from(ctx.author().id),
+                field: String::from("status"),
+                old_value: Some(event.status.as_str().to_string()),
+                new_value: Some(status.as_str().to_string()),
+                reason: reason.clone(),
+            },
+        )
+        .await?;
+    event.status = status;
+    info!(
+        "Event {}(id {}) on server {} is now {}",
+        event.name,
+        id,
+        event.server_id,
+        status.as_str()
+    );
+
+    let settings = ctx
+        .data()
+        .db
+        .get_guild_settings(event.server_id)
+        .await
+        .unwrap_or_default();
+    if let Err(e) = manifest::refresh(ctx.http(), &event, &settings.announcement_text()).await {
+        warn!("Couldn't update the manifest of event {}: {}", event.name, e);
+    }
+
+    if status == EventStatus::Completed {
+        if let Err(e) = reliability::settle(ctx.cache(), &ctx.data().db, id, &event).await {
+            warn!("Couldn't record the no-shows of event {}: {}", event.name, e);
+        }
+    }
+
+    if status == EventStatus::Cancelled {
+        let text = match &reason {
+            Some(reason) => format!("{} has been cancelled: {}", event.name, reason),
+            None => format!("{} has been cancelled", event.name),
+        };
+        notifications::notify(
+            ctx.http(),
+            &ctx.data().db,
+            id,
+            &event,
+            ctx.channel_id(),
+            &text,
+        )
+        .await?;
+    }
+
+    ctx.reply(format!("{} is now {}", event.name, status.as_str()))
+        .await?;
+
+    Ok(())
+}
+
+/// Moves the event whose channel you're in and tells its participants
+#[poise::command(slash_command, guild_only)]
+async fn reschedule(
+    ctx: Context<'_>,
+    #[description = "(Optional) When the event now starts, e.g. 2024-12-31 20:00 (UTC) or a Discord timestamp"]
+    starts_at: Option<String>,
+    #[description = "(Optional) When the event now ends"] ends_at: Option<String>,
+    #[description = "(Optional) Why, shown to the participants"] reason: Option<String>,
+) -> Result<(), Error> {
+    let (id, mut event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    if !movable(ctx, &event).await? {
+        return Ok(());
+    }
+    if starts_at.is_none() && ends_at.is_none() {
+        ctx.reply("Give the new starts_at, ends_at, or both").await?;
+        return Ok(());
+    }
 
-    let (_id, event) = match get_event_by_channel(
-        &ctx.data().conn.get().unwrap(),
-        u64::from(ctx.channel_id()),
+    let (new_start, new_end) = match (
+        starts_at.as_deref().map(parse_time).transpose(),
+        ends_at.as_deref().map(parse_time).transpose(),
     ) {
+        (Ok(new_start), Ok(new_end)) => (
+            new_start.or(event.starts_at),
+            new_end.or(event.ends_at),
+        ),
+        (Err(e), _) | (_, Err(e)) => {
+            ctx.reply(format!("Invalid date: {}", e)).await?;
+            return Ok(());
+        }
+    };
+    if let (Some(start), Some(end)) = (new_start, new_end) {
+        if end <= start {
+            ctx.reply("The event must end after it starts").await?;
+            return Ok(());
+        }
+    }
+
+    move_event(ctx, id, &mut event, new_start, new_end, reason).await?;
+
+    ctx.reply(format!("Moved {}", event.name)).await?;
+
+    Ok(())
+}
+
+/// Asks the event's members when they're available, the picked time becomes its start
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn poll_dates(
+    ctx: Context<'_>,
+    #[description = "Candidate times separated by commas, e.g. 2024-12-31 20:00, 2025-01-01 18:00"]
+    #[rest]
+    slots: String,
+) -> Result<(), Error> {
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    if !movable(ctx, &event).await? {
+        return Ok(());
+    }
+    let slots = match polls::parse_slots(&slots) {
+        Ok(slots) => slots,
+        Err(e) => {
+            ctx.reply(format!("Invalid times: {}", e)).await?;
+            return Ok(());
+        }
+    };
+
+    let previous = match ctx.data().db.get_poll(id).await {
+        Ok(poll) => Some(poll),
+        Err(StoreError::NotFound) => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut poll = DatePoll {
+        channel_id: u64::from(ctx.channel_id()),
+        message_id: 0,
+        slots,
+    };
+    let message = ctx
+        .channel_id()
+        .send_message(
+            ctx.http(),
+            CreateMessage::new()
+                .embed(polls::poll_embed(&event, &poll, &[], None))
+                .components(vec![polls::poll_menu(id, &poll.slots)]),
+        )
+        .await?;
+    poll.message_id = u64::from(message.id);
+    ctx.data().db.create_poll(id, poll).await?;
+    info!(
+        "Opened a date poll for event {}(id {}) on server {}",
+        event.name, id, event.server_id
+    );
+
+    // Votes on the previous poll are gone, don't let people use its menu anymore
+    if let Some(previous) = previous {
+        if let Err(e) = ChannelId::new(previous.channel_id)
+            .delete_message(ctx.http(), MessageId::new(previous.message_id))
+            .await
+        {
+            warn!(
+                "Couldn't delete the previous date poll of event {}: {}",
+                event.name, e
+            );
+        }
+    }
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content("Poll posted, close it with /event poll_lock")
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Closes the date poll of the event whose channel you're in and moves the event to the picked time
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn poll_lock(
+    ctx: Context<'_>,
+    #[description = "(Optional) Number of the time to pick. Defaults to the one most people can make"]
+    slot: Option<usize>,
+) -> Result<(), Error> {
+    let (id, mut event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    if !movable(ctx, &event).await? {
+        return Ok(());
+    }
+    let poll = match ctx.data().db.get_poll(id).await {
+        Ok(poll) => poll,
+        Err(StoreError::NotFound) => {
+            ctx.reply(format!("{} has no date poll, open one with /event poll_dates", event.name))
+                .await?;
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let votes = ctx.data().db.get_poll_votes(id).await?;
+
+    let picked = match slot {
+        Some(n) => match poll.slots.get(n.wrapping_sub(1)) {
+            Some(picked) => *picked,
+            None => {
+                ctx.reply(format!("Pick a time between 1 and {}", poll.slots.len()))
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => match polls::winner(&polls::tally(&poll.slots, &votes)) {
+            Some(picked) => picked,
+            None => {
+                ctx.reply("Nobody voted yet, give the number of the time to pick")
+                    .await?;
+                return Ok(());
+            }
+        },
+    };
+
+    // The event keeps its length
+    let new_end = match (event.starts_at, event.ends_at) {
+        (Some(start), Some(end)) => Some(picked + (end - start)),
+        (None, Some(end)) if end > picked => Some(end),
+        _ => None,
+    };
+    move_event(
+        ctx,
+        id,
+        &mut event,
+        Some(picked),
+        new_end,
+        Some(String::from("picked by date poll")),
+    )
+    .await?;
+
+    if let Err(e) = ChannelId::new(poll.channel_id)
+        .edit_message(
+            ctx.http(),
+            MessageId::new(poll.message_id),
+            EditMessage::new()
+                .embed(polls::poll_embed(&event, &poll, &votes, Some(picked)))
+                .components(vec![]),
+        )
+        .await
+    {
+        warn!("Couldn't close the date poll of event {}: {}", event.name, e);
+    }
+    ctx.data().db.delete_poll(id).await?;
+
+    ctx.reply(format!(
+        "{} now starts {}",
+        event.name,
+        format_time(picked)
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Whether [event] may still get new times, replies otherwise
+async fn movable(ctx: Context<'_>, event: &EventData) -> Result<bool, Error> {
+    if !matches!(event.status, EventStatus::Draft | EventStatus::Published) {
+        ctx.reply(format!(
+            "{} is {}, it can't be moved anymore",
+            event.name,
+            event.status.as_str()
+        ))
+        .await?;
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+/// Saves the new times of an event and records them in its history,
+/// then updates its manifest and tells its participants
+async fn move_event(
+    ctx: Context<'_>,
+    id: i64,
+    event: &mut EventData,
+    new_start: Option<i64>,
+    new_end: Option<i64>,
+    reason: Option<String>,
+) -> Result<(), Error> {
+    ctx.data().db.set_event_times(id, new_start, new_end).await?;
+
+    // One entry per moved bound, with the old and new timestamps
+    let now = scheduler::now();
+    for (field, old, new) in [
+        ("starts_at", event.starts_at, new_start),
+        ("ends_at", event.ends_at, new_end),
+    ] {
+        if old != new {
+            ctx.data()
+                .db
+                .insert_event_change(
+                    id,
+                    EventChange {
+                        changed_at: now,
+                        author_id: u64::from(ctx.author().id),
+                        field: field.to_string(),
+                        old_value: old.map(|t| t.to_string()),
+                        new_value: new.map(|t| t.to_string()),
+                        reason: reason.clone(),
+                    },
+                )
+                .await?;
+        }
+    }
+    event.starts_at = new_start;
+    event.ends_at = new_end;
+    info!(
+        "Rescheduled event {}(id {}) on server {}",
+        event.name, id, event.server_id
+    );
+
+    let settings = ctx
+        .data()
+        .db
+        .get_guild_settings(event.server_id)
+        .await
+        .unwrap_or_default();
+    if let Err(e) = manifest::refresh(ctx.http(), event, &settings.announcement_text()).await {
+        warn!("Couldn't update the manifest of event {}: {}", event.name, e);
+    }
+
+    let mut text = format!("{} has been moved", event.name);
+    if let Some(start) = new_start {
+        text += &format!(", it starts {}", format_time(start));
+    }
+    if let Some(end) = new_end {
+        text += &format!(" and ends {}", format_time(end));
+    }
+    if let Some(reason) = &reason {
+        text += &format!(": {}", reason);
+    }
+    notifications::notify(
+        ctx.http(),
+        &ctx.data().db,
+        id,
+        event,
+        ctx.channel_id(),
+        &text,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Shows the changes made to the event whose channel you're in
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn history(ctx: Context<'_>) -> Result<(), Error> {
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+
+    let changes = ctx.data().db.get_event_changes(id).await?;
+    if changes.is_empty() {
+        ctx.reply(format!("{} hasn't changed since it was created", event.name))
+            .await?;
+        return Ok(());
+    }
+
+    let value = |field: &str, value: &Option<String>| match value {
+        None => String::from("nothing"),
+        // Times are stored as unix seconds
+        Some(value) if field.ends_with("_at") => value
+            .parse()
+            .map(format_time)
+            .unwrap_or_else(|_| value.clone()),
+        Some(value) => value.clone(),
+    };
+    let body = changes
+        .iter()
+        .map(|change| {
+            let mut line = format!(
+                "<t:{}:f> <@{}> {}: {} → {}",
+                change.changed_at,
+                change.author_id,
+                change.field,
+                value(&change.field, &change.old_value),
+                value(&change.field, &change.new_value)
+            );
+            if let Some(reason) = &change.reason {
+                line += &format!(" ({})", reason);
+            }
+            line
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    ctx.send(
+        poise::CreateReply::default()
+            .content(format!("Changes to {}:\n{}", event.name, body))
+            .allowed_mentions(CreateAllowedMentions::new()),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Choose whether cancellations and changes of the events you joined are sent to your DMs
+#[poise::command(prefix_command, slash_command, rename = "notifications")]
+pub async fn dm_notifications(
+    ctx: Context<'_>,
+    #[description = "Send them to your DMs ?"] dms: bool,
+) -> Result<(), Error> {
+    ctx.data()
+        .db
+        .set_dm_notifications(u64::from(ctx.author().id), dms)
+        .await?;
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content(if dms {
+                "Changes to the events you joined will be sent to your DMs"
+            } else {
+                "Changes to the events you joined won't be sent to your DMs anymore"
+            })
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Makes an event read-only, its participants can still read its channels
+#[poise::command(
+    prefix_command,
+    slash_command,
+    required_permissions = "MANAGE_CHANNELS"
+)]
+async fn archive(
+    ctx: Context<'_>,
+    #[description = "(Optional) The event to archive, as shown by /event list"] id: Option<i64>,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(Error::from("That command can only be ran in a server"))?;
+
+    let (id, event) = match id {
+        Some(id) => match ctx.data().db.get_event_by_id(id).await {
+            Ok((id, event)) if event.server_id == u64::from(guild_id) => (id, event),
+            _ => {
+                ctx.reply(format!("There is no event #{} on this server", id))
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => match current_event(ctx).await {
+            Ok(found) => found,
+            Err(StoreError::NotFound) => {
+                ctx.reply("Failed to get related event (are you running this command in a managed event channel ?)").await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        },
+    };
+
+    if event.archived_at.is_some() {
+        ctx.reply(format!("{} is already archived", event.name))
+            .await?;
+        return Ok(());
+    }
+
+    ctx.defer().await?;
+    let bot = ctx.cache().current_user().id;
+    archive::archive(ctx.http(), &ctx.data().db, bot, id, &event).await?;
+
+    ctx.reply(format!(
+        "Archived {}, its channels are now read-only",
+        event.name
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// List all managed events
+#[poise::command(prefix_command, slash_command)]
+async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(Error::from("That command can only be ran in a server"))?;
+    let event_store = ctx
+        .data()
+        .db
+        .get_events_by_guild(u64::from(guild_id))
+        .await?;
+
+    if event_store.is_empty() {
+        ctx.reply("No events registered. Use `event create` to register one !")
+            .await?;
+    } else {
+        let body = event_store
+            .iter()
+            .map(|(id, event)| {
+                let mut line = format!("#{} {}", id, event.name);
+                if event.status != EventStatus::Published {
+                    line += &format!(" ({})", event.status.as_str());
+                }
+                if event.archived_at.is_some() {
+                    line += " (archived)";
+                }
+                line
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        ctx.reply(body).await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("add", "remove", "add_manager", "participants", "ban", "unban", "bans")
+)]
+async fn member(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Adds a participant to the event whose channel you're in right now
+#[poise::command(prefix_command, slash_command)]
+async fn add(ctx: Context<'_>, user: User) -> Result<(), Error> {
+    let http = ctx.http();
+    let guild_id = ctx
+        .guild_id()
+        .expect("This command can only be ran in a server");
+
+    ctx.defer_ephemeral().await?;
+
+    let (id, event) = match current_event(ctx).await {
         Ok((i, e)) => (i, e),
         Err(_) => {
             ctx.reply("Failed to get related event (are you running this command in a manager event channel ?)").await?;
@@ -357,16 +1510,27 @@ async fn add(ctx: Context<'_>, user: User) -> Result<(), Error> {
         }
     };
 
-    let player_role = RoleId::from(event.participant_role_id);
-    guild_id
-        .member(http, user.id)
-        .await?
-        .add_role(http, player_role)
-        .await?;
+    if event.archived_at.is_some() {
+        ctx.reply(format!("{} is archived, nobody can join it anymore", event.name))
+            .await?;
+        return Ok(());
+    }
+
+    if let Err(reason) =
+        participation::check_eligibility(http, &ctx.data().db, guild_id, id, user.id).await?
+    {
+        ctx.reply(format!("{} can't join {}: {}", user.name, event.name, reason))
+            .await?;
+        return Ok(());
+    }
+
+    participation::join(http, &ctx.data().db, guild_id, id, &event, user.id).await?;
+    // Adding someone answers their pending request
+    approval::withdraw(http, &ctx.data().db, id, &event, user.id).await?;
 
     ctx.reply(format!("Granted participation rights to {}", user.name))
         .await?;
-    println!("Granted participation rights to {}", user.name);
+    info!("Granted participation rights to {}", user.name);
 
     Ok(())
 }
@@ -381,10 +1545,7 @@ async fn remove(ctx: Context<'_>, user: User) -> Result<(), Error> {
 
     ctx.defer_ephemeral().await?;
 
-    let (_id, event) = match get_event_by_channel(
-        &ctx.data().conn.get().unwrap(),
-        u64::from(ctx.channel_id()),
-    ) {
+    let (id, event) = match current_event(ctx).await {
         Ok((i, e)) => (i, e),
         Err(_) => {
             ctx.reply("Failed to get related event (are you running this command in a manager event channel ?)").await?;
@@ -392,16 +1553,11 @@ async fn remove(ctx: Context<'_>, user: User) -> Result<(), Error> {
         }
     };
 
-    let player_role = RoleId::from(event.participant_role_id);
-    guild_id
-        .member(http, user.id)
-        .await?
-        .remove_role(http, player_role)
-        .await?;
+    participation::leave(http, &ctx.data().db, guild_id, id, &event, user.id).await?;
 
     ctx.reply(format!("Stripped participation rights from {}", user.name))
         .await?;
-    println!("Stripped participation rights from {}", user.name);
+    info!("Stripped participation rights from {}", user.name);
 
     Ok(())
 }
@@ -414,10 +1570,7 @@ async fn add_manager(ctx: Context<'_>, user: User) -> Result<(), Error> {
         .guild_id()
         .expect("This command can only be ran in a server");
 
-    let (_id, event) = match get_event_by_channel(
-        &ctx.data().conn.get().unwrap(),
-        u64::from(ctx.channel_id()),
-    ) {
+    let (_id, event) = match current_event(ctx).await {
         Ok((i, e)) => (i, e),
         Err(_) => {
             ctx.reply("Failed to get related event (are you running this command in a manager event channel ?)").await?;
@@ -431,13 +1584,18 @@ async fn add_manager(ctx: Context<'_>, user: User) -> Result<(), Error> {
         .await?
         .add_role(http, player_role)
         .await?;
+    if event.mode == EventMode::Thread {
+        ChannelId::new(event.category_id)
+            .add_thread_member(http, user.id)
+            .await?;
+    }
 
     ctx.reply(format!(
         "Granted admin rights to {} (for this event only)",
         user.name
     ))
     .await?;
-    println!(
+    info!(
         "Granted admin rights to {} (for this event only)",
         user.name
     );
@@ -445,59 +1603,1838 @@ async fn add_manager(ctx: Context<'_>, user: User) -> Result<(), Error> {
     Ok(())
 }
 
-/// Creates the relevant role and server data for this server. Call this once before using the bot
+/// Lists the participants of the event whose channel you're currently in
 #[poise::command(prefix_command, slash_command)]
-pub async fn init(ctx: Context<'_>) -> Result<(), Error> {
-    let http = ctx.http();
-    let guild_id = ctx
-        .guild_id()
-        .expect("This command can only be ran in a server");
-
-    match get_server_manager_role_id(&ctx.data().conn.get().unwrap(), u64::from(guild_id)) {
-        Ok(id) => {
-            let role_id = RoleId::from(id);
-            let role_set = guild_id.roles(ctx.http()).await.unwrap();
+async fn participants(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
 
-            match role_set.get(&role_id) {
-                Some(role) => {
-                    let _ = ctx.reply(format!("Server is already initialized.\nGrant someone the [{}] role to allow them to create events",
-                    role.name)).await;
-                    return Ok(());
+    let (id, event) = match current_event(ctx).await {
+        Ok((i, e)) => (i, e),
+        Err(_) => {
+            ctx.reply("Failed to get related event (are you running this command in a manager event channel ?)").await?;
+            return Ok(());
+        }
+    };
+
+    let participants = ctx.data().db.get_participants(id).await?;
+    if participants.is_empty() {
+        ctx.reply(format!("Nobody has joined {} yet", event.name))
+            .await?;
+    } else {
+        let body = participants
+            .iter()
+            .map(|user_id| format!("<@{}>", user_id))
+            .collect::<Vec<String>>()
+            .join("\n");
+        ctx.reply(format!("Participants of {}:\n{}", event.name, body))
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Removes a member from the event whose channel you're in, and keeps them from joining it again
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn ban(
+    ctx: Context<'_>,
+    #[description = "Who to ban"] user: User,
+    #[description = "(Optional) Why, shown to them when they try to join"]
+    #[rest]
+    reason: Option<String>,
+) -> Result<(), Error> {
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    let guild_id = GuildId::new(event.server_id);
+    if user
+        .has_role(ctx.http(), guild_id, RoleId::new(event.manager_role_id))
+        .await?
+    {
+        ctx.reply("Managers can't be banned from their event").await?;
+        return Ok(());
+    }
+
+    ctx.data()
+        .db
+        .insert_event_ban(
+            id,
+            Ban {
+                user_id: u64::from(user.id),
+                banned_by: u64::from(ctx.author().id),
+                banned_at: scheduler::now(),
+                reason,
+            },
+        )
+        .await?;
+    participation::expel(ctx.http(), &ctx.data().db, id, &event, user.id).await?;
+    info!(
+        "Banned {} from event {}(id {}) on server {}",
+        user.id, event.name, id, guild_id
+    );
+
+    ctx.reply(format!("Banned {} from {}", user.name, event.name))
+        .await?;
+
+    Ok(())
+}
+
+/// Lets a banned member join the event whose channel you're in again
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn unban(
+    ctx: Context<'_>,
+    #[description = "Who to unban"] user: User,
+) -> Result<(), Error> {
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+
+    if ctx
+        .data()
+        .db
+        .delete_event_ban(id, u64::from(user.id))
+        .await?
+        == 0
+    {
+        ctx.reply(format!("{} isn't banned from {}", user.name, event.name))
+            .await?;
+        return Ok(());
+    }
+    info!(
+        "Unbanned {} from event {}(id {}) on server {}",
+        user.id, event.name, id, event.server_id
+    );
+
+    ctx.reply(format!("{} may join {} again", user.name, event.name))
+        .await?;
+
+    Ok(())
+}
+
+/// Lists who is banned from the event whose channel you're in
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn bans(ctx: Context<'_>) -> Result<(), Error> {
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+
+    let bans = ctx.data().db.get_event_bans(id).await?;
+    let text = if bans.is_empty() {
+        format!("Nobody is banned from {}", event.name)
+    } else {
+        format!("Banned from {}:\n{}", event.name, ban_list(&bans))
+    };
+    ctx.send(
+        poise::CreateReply::default()
+            .content(text)
+            .allowed_mentions(CreateAllowedMentions::new())
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// One line per ban: who, by whom, when and why
+fn ban_list(bans: &[Ban]) -> String {
+    bans.iter()
+        .map(|ban| {
+            let mut line = format!(
+                "<@{}> by <@{}> <t:{}:R>",
+                ban.user_id, ban.banned_by, ban.banned_at
+            );
+            if let Some(reason) = &ban.reason {
+                line += &format!(": {}", reason);
+            }
+            line
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Bans from every event of the server, for moderators
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "BAN_MEMBERS",
+    subcommands("server_ban", "server_unban", "server_bans")
+)]
+pub async fn eventban(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Keeps a member out of every event of this server, and removes them from the current ones
+#[poise::command(prefix_command, slash_command, rename = "add")]
+async fn server_ban(
+    ctx: Context<'_>,
+    #[description = "Who to ban"] user: User,
+    #[description = "(Optional) Why, shown to them when they try to join"]
+    #[rest]
+    reason: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(Error::from("That command can only be ran in a server"))?;
+    ctx.defer_ephemeral().await?;
+
+    ctx.data()
+        .db
+        .insert_server_ban(
+            u64::from(guild_id),
+            Ban {
+                user_id: u64::from(user.id),
+                banned_by: u64::from(ctx.author().id),
+                banned_at: scheduler::now(),
+                reason,
+            },
+        )
+        .await?;
+
+    let events = ctx.data().db.get_events_by_guild(u64::from(guild_id)).await?;
+    for (id, event) in events.iter().filter(|(_, e)| e.archived_at.is_none()) {
+        if let Err(e) =
+            participation::expel(ctx.http(), &ctx.data().db, *id, event, user.id).await
+        {
+            warn!(
+                "Couldn't take {} out of event {}(id {}): {}",
+                user.id, event.name, id, e
+            );
+        }
+    }
+    info!("Banned {} from every event of server {}", user.id, guild_id);
+
+    ctx.reply(format!("Banned {} from every event of this server", user.name))
+        .await?;
+
+    Ok(())
+}
+
+/// Lifts a server-wide event ban. Bans from single events are kept
+#[poise::command(prefix_command, slash_command, rename = "remove")]
+async fn server_unban(
+    ctx: Context<'_>,
+    #[description = "Who to unban"] user: User,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(Error::from("That command can only be ran in a server"))?;
+
+    if ctx
+        .data()
+        .db
+        .delete_server_ban(u64::from(guild_id), u64::from(user.id))
+        .await?
+        == 0
+    {
+        ctx.reply(format!("{} isn't banned from this server's events", user.name))
+            .await?;
+        return Ok(());
+    }
+    info!("Unbanned {} from the events of server {}", user.id, guild_id);
+
+    ctx.reply(format!("{} may join this server's events again", user.name))
+        .await?;
+
+    Ok(())
+}
+
+/// Lists who is banned from this server's events, everywhere or from single events
+#[poise::command(prefix_command, slash_command, rename = "list")]
+async fn server_bans(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(Error::from("That command can only be ran in a server"))?;
+
+    let mut sections = Vec::new();
+    let everywhere = ctx.data().db.get_server_bans(u64::from(guild_id)).await?;
+    if !everywhere.is_empty() {
+        sections.push(format!("Every event:\n{}", ban_list(&everywhere)));
+    }
+    for (id, event) in ctx.data().db.get_events_by_guild(u64::from(guild_id)).await? {
+        let bans = ctx.data().db.get_event_bans(id).await?;
+        if !bans.is_empty() {
+            sections.push(format!("{} (id {}):\n{}", event.name, id, ban_list(&bans)));
+        }
+    }
+
+    let text = if sections.is_empty() {
+        String::from("Nobody is banned from this server's events")
+    } else {
+        sections.join("\n\n")
+    };
+    ctx.send(
+        poise::CreateReply::default()
+            .content(text)
+            .allowed_mentions(CreateAllowedMentions::new())
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    subcommands("add_channel", "remove_channel")
+)]
+async fn channel(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    subcommands("show_rules", "require", "forbid", "unset_role", "age")
+)]
+async fn rules(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Shows who may join the event whose channel you're in
+#[poise::command(prefix_command, slash_command, rename = "show")]
+async fn show_rules(ctx: Context<'_>) -> Result<(), Error> {
+    let (id, event) = match current_event(ctx).await {
+        Ok(found) => found,
+        Err(StoreError::NotFound) => {
+            ctx.reply("Failed to get related event (are you running this command in a managed event channel ?)").await?;
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let rules = ctx.data().db.get_eligibility(id).await?;
+    if rules == Eligibility::default() {
+        ctx.reply(format!("Everyone may join {}", event.name)).await?;
+        return Ok(());
+    }
+
+    let roles = |roles: &[u64]| {
+        roles
+            .iter()
+            .map(|role| format!("<@&{}>", role))
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+    let mut lines = vec![format!("To join {}:", event.name)];
+    if !rules.required_roles.is_empty() {
+        lines.push(format!("- have {}", roles(&rules.required_roles)));
+    }
+    if !rules.forbidden_roles.is_empty() {
+        lines.push(format!("- not have {}", roles(&rules.forbidden_roles)));
+    }
+    if let Some(days) = rules.min_member_days {
+        lines.push(format!("- have been on the server for {} days", days));
+    }
+    if let Some(days) = rules.min_account_days {
+        lines.push(format!("- have a Discord account at least {} days old", days));
+    }
+    ctx.send(
+        poise::CreateReply::default()
+            .content(lines.join("\n"))
+            .allowed_mentions(CreateAllowedMentions::new()),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Only lets members with [role] join the event whose channel you're in
+#[poise::command(prefix_command, slash_command)]
+async fn require(
+    ctx: Context<'_>,
+    #[description = "Role people need to join"] role: Role,
+) -> Result<(), Error> {
+    change_rules(ctx, |rules| {
+        rules.forbidden_roles.retain(|r| *r != u64::from(role.id));
+        if !rules.required_roles.contains(&u64::from(role.id)) {
+            rules.required_roles.push(u64::from(role.id));
+            rules.required_roles.sort_unstable();
+        }
+        format!("Joining now requires the {} role", role.name)
+    })
+    .await
+}
+
+/// Keeps members with [role] out of the event whose channel you're in
+#[poise::command(prefix_command, slash_command)]
+async fn forbid(
+    ctx: Context<'_>,
+    #[description = "Role that keeps people out"] role: Role,
+) -> Result<(), Error> {
+    change_rules(ctx, |rules| {
+        rules.required_roles.retain(|r| *r != u64::from(role.id));
+        if !rules.forbidden_roles.contains(&u64::from(role.id)) {
+            rules.forbidden_roles.push(u64::from(role.id));
+            rules.forbidden_roles.sort_unstable();
+        }
+        format!("Members with the {} role can't join anymore", role.name)
+    })
+    .await
+}
+
+/// Stops requiring or forbidding [role]
+#[poise::command(prefix_command, slash_command, rename = "unset")]
+async fn unset_role(
+    ctx: Context<'_>,
+    #[description = "Role to drop from the rules"] role: Role,
+) -> Result<(), Error> {
+    change_rules(ctx, |rules| {
+        rules.required_roles.retain(|r| *r != u64::from(role.id));
+        rules.forbidden_roles.retain(|r| *r != u64::from(role.id));
+        format!("The {} role doesn't matter to join anymore", role.name)
+    })
+    .await
+}
+
+/// Sets how long people must have been on the server, and had their account, to join
+#[poise::command(prefix_command, slash_command)]
+async fn age(
+    ctx: Context<'_>,
+    #[description = "(Optional) Days on the server, 0 for none"] member_days: Option<u32>,
+    #[description = "(Optional) Days since the account was created, 0 for none"]
+    account_days: Option<u32>,
+) -> Result<(), Error> {
+    if member_days.is_none() && account_days.is_none() {
+        ctx.reply("Give member_days, account_days, or both").await?;
+        return Ok(());
+    }
+    change_rules(ctx, |rules| {
+        if let Some(days) = member_days {
+            rules.min_member_days = Some(days).filter(|days| *days > 0);
+        }
+        if let Some(days) = account_days {
+            rules.min_account_days = Some(days).filter(|days| *days > 0);
+        }
+        String::from("Minimum ages updated")
+    })
+    .await
+}
+
+/// Applies [change] to the rules of the managed event the command was ran from.
+/// People who already joined are kept
+async fn change_rules(
+    ctx: Context<'_>,
+    change: impl FnOnce(&mut Eligibility) -> String,
+) -> Result<(), Error> {
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    let mut rules = ctx.data().db.get_eligibility(id).await?;
+    let answer = change(&mut rules);
+    ctx.data().db.set_eligibility(id, rules).await?;
+    info!(
+        "Changed who may join event {}(id {}) on server {}",
+        event.name, id, event.server_id
+    );
+
+    ctx.reply(answer).await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    subcommands("show_slots", "add_slot", "remove_slot")
+)]
+async fn slots(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Shows the composition of the event whose channel you're in
+#[poise::command(prefix_command, slash_command, rename = "show")]
+async fn show_slots(ctx: Context<'_>) -> Result<(), Error> {
+    let (id, event) = match current_event(ctx).await {
+        Ok(found) => found,
+        Err(StoreError::NotFound) => {
+            ctx.reply("Failed to get related event (are you running this command in a managed event channel ?)").await?;
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let slots = ctx.data().db.get_slots(id).await?;
+    let members = ctx.data().db.get_slot_members(id).await?;
+    let text = match composition::composition(&slots, &members) {
+        Some(composition) => format!("Composition of {}:\n{}", event.name, composition),
+        None => format!("{} has no slots, add some with /event slots add", event.name),
+    };
+    ctx.send(
+        poise::CreateReply::default()
+            .content(text)
+            .allowed_mentions(CreateAllowedMentions::new()),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Adds a slot to the composition of the event whose channel you're in, or changes one
+#[poise::command(prefix_command, slash_command, rename = "add")]
+async fn add_slot(
+    ctx: Context<'_>,
+    #[description = "Name of the slot, like Tank"] name: String,
+    #[description = "How many people it takes"]
+    #[min = 1]
+    #[max = 100]
+    capacity: u32,
+    #[description = "(Optional) Whether the people in the slot get a role of their own"]
+    role: Option<bool>,
+) -> Result<(), Error> {
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > composition::MAX_NAME_LENGTH {
+        ctx.reply(format!(
+            "Slot names are 1 to {} characters long",
+            composition::MAX_NAME_LENGTH
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let slots = ctx.data().db.get_slots(id).await?;
+    let existing = slots.iter().find(|slot| slot.name == name);
+    if existing.is_none() && slots.len() >= composition::MAX_SLOTS {
+        ctx.reply(format!(
+            "{} already has {} slots, remove one first",
+            event.name,
+            composition::MAX_SLOTS
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let guild_id = GuildId::new(event.server_id);
+    let previous_role = existing.and_then(|slot| slot.role_id);
+    let role_id = match (role, previous_role) {
+        (Some(true), None) => {
+            let created = guild_id
+                .create_role(
+                    ctx.http(),
+                    EditRole::new().name(format!("{} {}", event.name, name)),
+                )
+                .await?;
+            // People already in the slot get it as well
+            let members = ctx.data().db.get_slot_members(id).await?;
+            for user_id in composition::members_of(&name, &members) {
+                if let Err(e) = ctx
+                    .http()
+                    .add_member_role(guild_id, UserId::new(user_id), created.id, None)
+                    .await
+                {
+                    warn!("Couldn't give slot role {} to {}: {}", created.id, user_id, e);
+                }
+            }
+            Some(u64::from(created.id))
+        }
+        (Some(false), Some(role_id)) => {
+            if let Err(e) = guild_id.delete_role(ctx.http(), RoleId::new(role_id)).await {
+                warn!("Couldn't delete slot role {} of event {}: {}", role_id, event.name, e);
+            }
+            None
+        }
+        (_, previous) => previous,
+    };
+
+    ctx.data()
+        .db
+        .insert_slot(
+            id,
+            TeamSlot {
+                name: name.clone(),
+                capacity,
+                role_id,
+            },
+        )
+        .await?;
+    info!(
+        "Set slot {} of event {}(id {}) on server {} to {} people",
+        name, event.name, id, event.server_id, capacity
+    );
+    if let Err(e) = composition::update_manifest(ctx.http(), &ctx.data().db, id, &event).await {
+        warn!("Couldn't update the composition of event {}: {}", event.name, e);
+    }
+
+    let verb = if existing.is_some() { "now takes" } else { "added, it takes" };
+    ctx.reply(format!("{} {} {} people", name, verb, capacity))
+        .await?;
+
+    Ok(())
+}
+
+/// Removes a slot from the composition of the event whose channel you're in
+#[poise::command(prefix_command, slash_command, rename = "remove")]
+async fn remove_slot(
+    ctx: Context<'_>,
+    #[description = "Name of the slot"] name: String,
+) -> Result<(), Error> {
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    let name = name.trim().to_string();
+    let slot = match ctx
+        .data()
+        .db
+        .get_slots(id)
+        .await?
+        .into_iter()
+        .find(|slot| slot.name == name)
+    {
+        Some(slot) => slot,
+        None => {
+            ctx.reply(format!("{} has no {} slot", event.name, name))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    ctx.data().db.delete_slot(id, name.clone()).await?;
+    if let Some(role_id) = slot.role_id {
+        if let Err(e) = GuildId::new(event.server_id)
+            .delete_role(ctx.http(), RoleId::new(role_id))
+            .await
+        {
+            warn!("Couldn't delete slot role {} of event {}: {}", role_id, event.name, e);
+        }
+    }
+    info!(
+        "Removed slot {} of event {}(id {}) on server {}",
+        name, event.name, id, event.server_id
+    );
+    if let Err(e) = composition::update_manifest(ctx.http(), &ctx.data().db, id, &event).await {
+        warn!("Couldn't update the composition of event {}: {}", event.name, e);
+    }
+
+    ctx.reply(format!(
+        "Removed {}, the people in it can pick another slot",
+        name
+    ))
+    .await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    subcommands("generate", "show_teams", "clear_teams", "skill")
+)]
+async fn teams(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Splits the participants of the event whose channel you're in into teams, replacing the previous ones
+#[poise::command(prefix_command, slash_command)]
+async fn generate(
+    ctx: Context<'_>,
+    #[description = "How many teams"]
+    #[min = 2]
+    #[max = 20]
+    count: u32,
+    #[description = "(Optional) random (default), or balanced by the skills set with /event teams skill"]
+    by: Option<TeamSplit>,
+) -> Result<(), Error> {
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    if event.mode == EventMode::Thread {
+        ctx.reply(format!("{} is a thread event, it has no category for team channels", event.name))
+            .await?;
+        return Ok(());
+    }
+    if event.archived_at.is_some() {
+        ctx.reply(format!("{} is archived, its channels can't change anymore", event.name))
+            .await?;
+        return Ok(());
+    }
+    let count = (count as usize).clamp(2, teams::MAX_TEAMS);
+    let participants = participation::players(ctx.cache(), &ctx.data().db, id).await?;
+    if participants.len() < count {
+        ctx.reply(format!(
+            "{} teams need at least {} participants, {} has {}",
+            count,
+            count,
+            event.name,
+            participants.len()
+        ))
+        .await?;
+        return Ok(());
+    }
+    ctx.defer().await?;
+    // Checked before anything is created, a full category would leave half the teams behind
+    let room = teams::room(ctx.http(), &ctx.data().db, id, &event).await?;
+    if count > room {
+        ctx.reply(format!(
+            "The category of {} only has room for the channels of {} teams",
+            event.name, room
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let by = by.unwrap_or_default();
+    let skills = ctx.data().db.get_skills(id).await?;
+    teams::clear(ctx.http(), &ctx.data().db, id, &event).await?;
+    let split = teams::split(&participants, &skills, count, by);
+    let bot = ctx.cache().current_user().id;
+    let mut generated = vec![];
+    for (i, members) in split.into_iter().enumerate() {
+        match teams::create_team(ctx.http(), &ctx.data().db, id, &event, i as u32 + 1, members, bot)
+            .await
+        {
+            Ok(team) => generated.push(team),
+            Err(e) => {
+                // Half a split is no use, take back the teams already made
+                teams::clear(ctx.http(), &ctx.data().db, id, &event).await?;
+                return Err(e);
+            }
+        }
+    }
+    info!(
+        "Generated {} {} teams for event {}(id {}) on server {}",
+        count,
+        by.name(),
+        event.name,
+        id,
+        event.server_id
+    );
+
+    let shown_skills = if by == TeamSplit::Skill { skills } else { vec![] };
+    ctx.send(
+        poise::CreateReply::default()
+            .content(format!(
+                "Teams of {}:\n{}",
+                event.name,
+                teams::describe(&generated, &shown_skills)
+            ))
+            .allowed_mentions(CreateAllowedMentions::new()),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Shows the teams of the event whose channel you're in
+#[poise::command(prefix_command, slash_command, rename = "show")]
+async fn show_teams(ctx: Context<'_>) -> Result<(), Error> {
+    let (id, event) = match current_event(ctx).await {
+        Ok(found) => found,
+        Err(StoreError::NotFound) => {
+            ctx.reply("Failed to get related event (are you running this command in a managed event channel ?)").await?;
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let generated = ctx.data().db.get_teams(id).await?;
+    if generated.is_empty() {
+        ctx.reply(format!("{} has no teams, see /event teams generate", event.name))
+            .await?;
+        return Ok(());
+    }
+
+    let skills = ctx.data().db.get_skills(id).await?;
+    ctx.send(
+        poise::CreateReply::default()
+            .content(format!(
+                "Teams of {}:\n{}",
+                event.name,
+                teams::describe(&generated, &skills)
+            ))
+            .allowed_mentions(CreateAllowedMentions::new()),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Deletes the teams of the event whose channel you're in, with their roles and channels
+#[poise::command(prefix_command, slash_command, rename = "clear")]
+async fn clear_teams(ctx: Context<'_>) -> Result<(), Error> {
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    ctx.defer().await?;
+
+    match teams::clear(ctx.http(), &ctx.data().db, id, &event).await? {
+        0 => ctx.reply(format!("{} has no teams", event.name)).await?,
+        cleared => {
+            ctx.reply(format!("Deleted the {} teams of {}", cleared, event.name))
+                .await?
+        }
+    };
+
+    Ok(())
+}
+
+/// Sets the skill a participant counts for when balancing teams
+#[poise::command(prefix_command, slash_command)]
+async fn skill(
+    ctx: Context<'_>,
+    #[description = "Whose skill"] user: User,
+    #[description = "(Optional) Any number, like a rating. Leave out to forget it"] value: Option<i64>,
+) -> Result<(), Error> {
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+
+    ctx.data()
+        .db
+        .set_skill(id, u64::from(user.id), value)
+        .await?;
+    info!(
+        "Set the skill of {} in event {}(id {}) to {:?}",
+        user.id, event.name, id, value
+    );
+
+    ctx.reply(match value {
+        Some(value) => format!("{} counts for {} in {}", user.name, value, event.name),
+        None => format!("{} has no skill in {} anymore", user.name, event.name),
+    })
+    .await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    subcommands("create_bracket", "report", "show_bracket", "delete_bracket")
+)]
+async fn bracket(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Posts a tournament bracket for the event whose channel you're in, replacing its previous one
+#[poise::command(prefix_command, slash_command, rename = "create")]
+async fn create_bracket(
+    ctx: Context<'_>,
+    #[description = "Single or double elimination"] format: BracketFormat,
+    #[description = "(Optional) The participants (default), or the teams of /event teams generate"]
+    from: Option<EntrantSource>,
+    #[description = "(Optional) random (default), by skill, or in join or team order"]
+    seeding: Option<Seeding>,
+) -> Result<(), Error> {
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    if event.archived_at.is_some() {
+        ctx.reply(format!("{} is archived, it can't start a tournament anymore", event.name))
+            .await?;
+        return Ok(());
+    }
+
+    let guild_id = GuildId::new(event.server_id);
+    let entrants: Vec<Entrant> = match from.unwrap_or_default() {
+        EntrantSource::Participants => {
+            let mut entrants = vec![];
+            for user_id in participation::players(ctx.cache(), &ctx.data().db, id).await? {
+                // Members who left the server keep their id as a name
+                let name = match guild_id.member(ctx.http(), UserId::new(user_id)).await {
+                    Ok(member) => member.display_name().to_string(),
+                    Err(_) => user_id.to_string(),
+                };
+                entrants.push(Entrant { seed: 0, name, members: vec![user_id] });
+            }
+            entrants
+        }
+        EntrantSource::Teams => ctx
+            .data()
+            .db
+            .get_teams(id)
+            .await?
+            .into_iter()
+            .map(|team| Entrant {
+                seed: 0,
+                name: format!("Team {}", team.number),
+                members: team.members,
+            })
+            .collect(),
+    };
+    if entrants.len() < 2 || entrants.len() > bracket::MAX_ENTRANTS {
+        ctx.reply(format!(
+            "A bracket takes 2 to {} entrants, {} has {}",
+            bracket::MAX_ENTRANTS,
+            event.name,
+            entrants.len()
+        ))
+        .await?;
+        return Ok(());
+    }
+    ctx.defer().await?;
+
+    let skills = ctx.data().db.get_skills(id).await?;
+    let entrants = bracket::seed(entrants, &skills, seeding.unwrap_or_default());
+    let posted = bracket::post(
+        ctx.http(),
+        &ctx.data().db,
+        id,
+        &event,
+        ctx.channel_id(),
+        format,
+        entrants,
+    )
+    .await?;
+
+    ctx.reply(format!(
+        "Posted the {} bracket of {}, with {} entrants",
+        format.name(),
+        event.name,
+        posted.entrants.len()
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Reports who won a match of the bracket of the event whose channel you're in
+#[poise::command(prefix_command, slash_command)]
+async fn report(
+    ctx: Context<'_>,
+    #[description = "The match, like W1-2, L3-1 or GF"]
+    #[rename = "match"]
+    match_id: String,
+    #[description = "A player of the winning side"] winner: User,
+) -> Result<(), Error> {
+    let (id, event) = match current_event(ctx).await {
+        Ok(found) => found,
+        Err(StoreError::NotFound) => {
+            ctx.reply("Failed to get related event (are you running this command in a managed event channel ?)").await?;
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let tournament = match ctx.data().db.get_bracket(id).await {
+        Ok(found) => found,
+        Err(StoreError::NotFound) => {
+            ctx.reply(format!("{} has no bracket, see /event bracket create", event.name))
+                .await?;
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let match_id: MatchId = match match_id.parse() {
+        Ok(match_id) => match_id,
+        Err(e) => {
+            ctx.reply(e).await?;
+            return Ok(());
+        }
+    };
+
+    let reports = ctx.data().db.get_results(id).await?;
+    let states = bracket::play(
+        &bracket::structure(tournament.format, tournament.entrants.len()),
+        tournament.entrants.len(),
+        &reports,
+    );
+    let state = match states.iter().find(|state| state.id == match_id) {
+        Some(state) => state,
+        None => {
+            ctx.reply(format!("The bracket of {} has no match `{}`", event.name, match_id))
+                .await?;
+            return Ok(());
+        }
+    };
+    if !state.is_playable() {
+        ctx.reply(format!("`{}` isn't played, its sides aren't both known", match_id))
+            .await?;
+        return Ok(());
+    }
+    let seed = match bracket::side_of(&tournament, state, u64::from(winner.id)) {
+        Some(seed) => seed,
+        None => {
+            ctx.reply(format!("{} doesn't play in `{}`", winner.name, match_id))
+                .await?;
+            return Ok(());
+        }
+    };
+    let name = bracket::entrant_name(&tournament, seed).to_string();
+
+    let author = u64::from(ctx.author().id);
+    let is_manager = ctx
+        .author()
+        .has_role(ctx.http(), event.server_id, RoleId::new(event.manager_role_id))
+        .await?;
+    if is_manager {
+        ctx.data()
+            .db
+            .report_result(
+                id,
+                MatchReport {
+                    match_id: match_id.to_string(),
+                    winner: seed,
+                    reported_by: author,
+                    confirmed: true,
+                },
+            )
+            .await?;
+        ctx.reply(format!("{} won `{}`", name, match_id)).await?;
+    } else {
+        let own_side = bracket::side_of(&tournament, state, author);
+        if own_side.is_none() {
+            ctx.reply(format!(
+                "Only the players of `{}` and the managers of {} can report it",
+                match_id, event.name
+            ))
+            .await?;
+            return Ok(());
+        }
+        if state.winner.is_some() {
+            ctx.reply(format!("`{}` is decided, ask a manager to change it", match_id))
+                .await?;
+            return Ok(());
+        }
+
+        ctx.data()
+            .db
+            .report_result(
+                id,
+                MatchReport {
+                    match_id: match_id.to_string(),
+                    winner: seed,
+                    reported_by: author,
+                    confirmed: false,
+                },
+            )
+            .await?;
+        // The other side confirms, or a manager
+        let opponents: Vec<UserId> = tournament
+            .entrants
+            .iter()
+            .filter(|entrant| {
+                state.seeds().contains(&entrant.seed) && Some(entrant.seed) != own_side
+            })
+            .flat_map(|entrant| entrant.members.iter().map(|member| UserId::new(*member)))
+            .collect();
+        ctx.send(
+            poise::CreateReply::default()
+                .content(format!(
+                    "<@{}> reported that {} won `{}`. {}, press Confirm if that's right",
+                    author,
+                    name,
+                    match_id,
+                    opponents
+                        .iter()
+                        .map(|user_id| format!("<@{}>", user_id))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ))
+                .allowed_mentions(CreateAllowedMentions::new().users(opponents))
+                .components(vec![bracket::confirm_button(id, &match_id, seed)]),
+        )
+        .await?;
+    }
+    info!(
+        "{} reported that seed {} won {} of event {}(id {})",
+        author, seed, match_id, event.name, id
+    );
+
+    if let Err(e) = bracket::refresh(ctx.http(), &ctx.data().db, id, &event).await {
+        warn!("Couldn't update the bracket of event {}(id {}): {}", event.name, id, e);
+    }
+
+    Ok(())
+}
+
+/// Shows the bracket of the event whose channel you're in, only to you
+#[poise::command(prefix_command, slash_command, rename = "show")]
+async fn show_bracket(ctx: Context<'_>) -> Result<(), Error> {
+    let (id, event) = match current_event(ctx).await {
+        Ok(found) => found,
+        Err(StoreError::NotFound) => {
+            ctx.reply("Failed to get related event (are you running this command in a managed event channel ?)").await?;
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let (embed, image) = match bracket::show(&ctx.data().db, id, &event).await {
+        Ok(shown) => shown,
+        Err(e) if matches!(e.downcast_ref::<StoreError>(), Some(StoreError::NotFound)) => {
+            ctx.reply(format!("{} has no bracket, see /event bracket create", event.name))
+                .await?;
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(embed)
+            .attachment(image)
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Deletes the bracket of the event whose channel you're in, with its results
+#[poise::command(prefix_command, slash_command, rename = "delete")]
+async fn delete_bracket(ctx: Context<'_>) -> Result<(), Error> {
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+
+    match bracket::remove(ctx.http(), &ctx.data().db, id, &event).await? {
+        0 => ctx.reply(format!("{} has no bracket", event.name)).await?,
+        _ => {
+            ctx.reply(format!("Deleted the bracket of {}", event.name))
+                .await?
+        }
+    };
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    subcommands("open_checkin", "close_checkin")
+)]
+async fn checkin(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Posts a check-in button in this channel for the event's participants, replacing the previous one
+#[poise::command(prefix_command, slash_command, rename = "open")]
+async fn open_checkin(
+    ctx: Context<'_>,
+    #[description = "(Optional) How long check-in stays open. Defaults to 30 minutes"]
+    #[min = 1]
+    #[max = 1440]
+    minutes: Option<u32>,
+    #[description = "(Optional) Ask for a code you show at the venue, so that only those there check in"]
+    code: Option<bool>,
+) -> Result<(), Error> {
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    if event.status != EventStatus::Published || event.archived_at.is_some() {
+        ctx.reply(format!("{} isn't under way, nobody can check in", event.name))
+            .await?;
+        return Ok(());
+    }
+
+    checkin::close(ctx.http(), &ctx.data().db, id, &event).await?;
+    let opened_at = scheduler::now();
+    let mut window = CheckIn {
+        channel_id: u64::from(ctx.channel_id()),
+        message_id: 0,
+        code: code.unwrap_or(false).then(checkin::generate_code),
+        opened_at,
+        closes_at: opened_at + 60 * i64::from(minutes.unwrap_or(30).clamp(1, 1440)),
+    };
+    let message = ctx
+        .channel_id()
+        .send_message(
+            ctx.http(),
+            CreateMessage::new()
+                .embed(checkin::checkin_embed(&event, &window, true))
+                .components(checkin::components(id, true)),
+        )
+        .await?;
+    window.message_id = u64::from(message.id);
+    ctx.data().db.open_checkin(id, window.clone()).await?;
+    info!(
+        "Opened check-in for event {}(id {}) until {}",
+        event.name, id, window.closes_at
+    );
+
+    let mut answer = format!(
+        "Check-in is open until {}, close it early with /event checkin close",
+        scheduler::format_time(window.closes_at)
+    );
+    if let Some(code) = &window.code {
+        answer += &format!("\nShow this code at the venue: **{}**", code);
+    }
+    ctx.send(
+        poise::CreateReply::default()
+            .content(answer)
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Closes the check-in of the event whose channel you're in
+#[poise::command(prefix_command, slash_command, rename = "close")]
+async fn close_checkin(ctx: Context<'_>) -> Result<(), Error> {
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+
+    match checkin::close(ctx.http(), &ctx.data().db, id, &event).await? {
+        0 => {
+            ctx.reply(format!("{} has no check-in open", event.name))
+                .await?
+        }
+        _ => {
+            let came = ctx.data().db.get_attendance(id).await?.len();
+            ctx.reply(format!(
+                "Closed the check-in of {}, {} people checked in",
+                event.name, came
+            ))
+            .await?
+        }
+    };
+
+    Ok(())
+}
+
+/// Shows who came to the event whose channel you're in and who didn't
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn attendance(
+    ctx: Context<'_>,
+    #[description = "(Optional) Attach the list as a CSV file"] export: Option<bool>,
+) -> Result<(), Error> {
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    let participants = participation::players(ctx.cache(), &ctx.data().db, id).await?;
+    let (came, missing) = checkin::split(&participants, &ctx.data().db.get_attendance(id).await?);
+
+    let mut reply = poise::CreateReply::default()
+        .content(format!(
+            "Attendance of {}:\n{}",
+            event.name,
+            checkin::describe(&came, &missing)
+        ))
+        .allowed_mentions(CreateAllowedMentions::new());
+    if export.unwrap_or(false) {
+        ctx.defer().await?;
+        let guild_id = GuildId::new(event.server_id);
+        let mut names = vec![];
+        for user_id in came.iter().map(|(user, _)| *user).chain(missing.iter().copied()) {
+            // Members who left the server have no name anymore
+            if let Ok(member) = guild_id.member(ctx.http(), UserId::new(user_id)).await {
+                names.push((user_id, member.display_name().to_string()));
+            }
+        }
+        reply = reply.attachment(CreateAttachment::bytes(
+            checkin::to_csv(&came, &missing, &names),
+            "attendance.csv",
+        ));
+    }
+    ctx.send(reply).await?;
+
+    Ok(())
+}
+
+/// Shows how often a member came to the server's events they joined, only to you
+#[poise::command(prefix_command, slash_command, guild_only, rename = "reliability")]
+async fn member_reliability(
+    ctx: Context<'_>,
+    #[description = "Whose record"] user: User,
+) -> Result<(), Error> {
+    let (_, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+
+    let record = ctx
+        .data()
+        .db
+        .get_reliability(event.server_id, u64::from(user.id))
+        .await?;
+    ctx.send(
+        poise::CreateReply::default()
+            .content(format!(
+                "<@{}> on this server: {}",
+                user.id,
+                reliability::describe(&record)
+            ))
+            .allowed_mentions(CreateAllowedMentions::new())
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Returns the event the command was ran from, if its author manages it.
+/// Replies to the author otherwise
+async fn managed_event(ctx: Context<'_>) -> Result<Option<(i64, EventData)>, Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(Error::from("That command can only be ran in a server"))?;
+
+    let (id, event) = match current_event(ctx).await {
+        Ok(found) => found,
+        Err(StoreError::NotFound) => {
+            ctx.reply("Failed to get related event (are you running this command in a managed event channel ?)").await?;
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    if !ctx
+        .author()
+        .has_role(ctx.http(), guild_id, RoleId::from(event.manager_role_id))
+        .await?
+    {
+        ctx.reply(format!("Only the managers of {} can do that", event.name))
+            .await?;
+        return Ok(None);
+    }
+
+    Ok(Some((id, event)))
+}
+
+/// Adds a channel to the event whose channel you're in, with the same permissions
+#[poise::command(prefix_command, slash_command, rename = "add")]
+async fn add_channel(
+    ctx: Context<'_>,
+    #[description = "The channel's name"] name: String,
+    #[description = "(Optional) text, voice, stage, forum, or a thread of this channel. Defaults to text"]
+    kind: Option<ChannelKind>,
+) -> Result<(), Error> {
+    let http = ctx.http();
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(Error::from("That command can only be ran in a server"))?;
+
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    if event.mode == EventMode::Thread {
+        ctx.reply(format!("{} is a thread event, it has no channels to add to", event.name))
+            .await?;
+        return Ok(());
+    }
+    if event.archived_at.is_some() {
+        ctx.reply(format!("{} is archived, its channels can't change anymore", event.name))
+            .await?;
+        return Ok(());
+    }
+
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 100 {
+        ctx.reply("Channel names must be between 1 and 100 characters")
+            .await?;
+        return Ok(());
+    }
+
+    let kind = kind.unwrap_or(ChannelKind::Text);
+    let channel_id = match kind {
+        ChannelKind::Thread => {
+            let channel_ids = ctx.data().db.get_channels_by_event_id(id).await?;
+            let parent = ctx.guild_channel().await;
+            match parent {
+                Some(parent)
+                    if parent.kind == ChannelType::Text
+                        && channel_ids.contains(&u64::from(parent.id)) =>
+                {
+                    parent
+                        .id
+                        .create_thread(http, CreateThread::new(&name).kind(kind.channel_type()))
+                        .await?
+                        .id
                 }
-                None => {
-                    println!("Event creator role has been deleted from server {}, wiping from database and recreating ...", u64::from(guild_id));
-                    let _ = delete_server_manager_role(
-                        &ctx.data().conn.get().unwrap(),
-                        u64::from(guild_id),
-                    );
+                _ => {
+                    ctx.reply("Threads are opened in the channel the command is ran in, which must be one of the event's text channels")
+                        .await?;
+                    return Ok(());
                 }
             }
         }
-        Err(_) => {}
+        kind => {
+            // New channels get the same overwrites as the rest of the event
+            let mut permissions = ChannelId::new(event.category_id)
+                .to_channel(http)
+                .await?
+                .guild()
+                .map(|category| category.permission_overwrites)
+                .unwrap_or_default();
+            if event.timed_voice && matches!(kind, ChannelKind::Voice | ChannelKind::Stage) {
+                let player = RoleId::from(event.participant_role_id);
+                permissions.retain(|p| p.kind != PermissionOverwriteType::Role(player));
+                permissions.push(voice_overwrite(player, event.voice_open));
+            }
+
+            guild_id
+                .create_channel(
+                    http,
+                    CreateChannel::new(&name)
+                        .kind(kind.channel_type())
+                        .permissions(permissions)
+                        .category(ChannelId::new(event.category_id)),
+                )
+                .await?
+                .id
+        }
+    };
+
+    ctx.data()
+        .db
+        .insert_channels(id, vec![u64::from(channel_id)])
+        .await?;
+
+    info!(
+        "Added {} channel {} to event {} on server {}",
+        kind.name(),
+        name,
+        event.name,
+        guild_id
+    );
+    ctx.reply(format!("Added <#{}> to {}", channel_id, event.name))
+        .await?;
+
+    Ok(())
+}
+
+/// Deletes one of the channels of the event whose channel you're in
+#[poise::command(prefix_command, slash_command, rename = "remove")]
+async fn remove_channel(
+    ctx: Context<'_>,
+    #[description = "The channel to delete"] channel: GuildChannel,
+) -> Result<(), Error> {
+    let http = ctx.http();
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(Error::from("That command can only be ran in a server"))?;
+
+    let (id, event) = match managed_event(ctx).await? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    if event.mode == EventMode::Thread {
+        ctx.reply(format!("{} is a thread event, delete it with /event delete instead", event.name))
+            .await?;
+        return Ok(());
+    }
+    if event.archived_at.is_some() {
+        ctx.reply(format!("{} is archived, its channels can't change anymore", event.name))
+            .await?;
+        return Ok(());
+    }
+
+    let channel_ids = ctx.data().db.get_channels_by_event_id(id).await?;
+    if !channel_ids.contains(&u64::from(channel.id)) {
+        ctx.reply(format!("<#{}> isn't one of {}'s channels", channel.id, event.name))
+            .await?;
+        return Ok(());
+    }
+    // Join requests would have nowhere to go
+    if Some(u64::from(channel.id)) == event.requests_channel_id {
+        ctx.reply(format!(
+            "<#{}> is where {}'s join requests are posted, it goes away with the event (/event delete)",
+            channel.id, event.name
+        ))
+        .await?;
+        return Ok(());
+    }
+    let team = ctx.data().db.get_teams(id).await?.into_iter().find(|team| {
+        [team.text_channel_id, team.voice_channel_id].contains(&u64::from(channel.id))
+    });
+    if let Some(team) = team {
+        ctx.reply(format!(
+            "<#{}> belongs to team {}, remove the teams with /event teams clear instead",
+            channel.id, team.number
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    // Event commands are ran from text channels, keep at least one
+    if channel.kind == ChannelType::Text {
+        let guild_channels = guild_id.channels(http).await?;
+        let other_text_channels = channel_ids
+            .iter()
+            .filter(|other| **other != u64::from(channel.id))
+            .filter_map(|other| guild_channels.get(&ChannelId::new(*other)))
+            .filter(|other| other.kind == ChannelType::Text)
+            .count();
+        if other_text_channels == 0 {
+            ctx.reply(format!(
+                "<#{}> is the last text channel of {}, it can't be removed",
+                channel.id, event.name
+            ))
+            .await?;
+            return Ok(());
+        }
+    }
+
+    ctx.reply(format!("Deleting #{}", channel.name)).await?;
+    channel.id.delete(http).await?;
+    ctx.data()
+        .db
+        .delete_channel(id, u64::from(channel.id))
+        .await?;
+
+    info!(
+        "Removed channel {} from event {} on server {}",
+        channel.name, event.name, guild_id
+    );
+
+    Ok(())
+}
+
+/// Creates the relevant role and server data for this server. Call this once before using the bot
+#[poise::command(prefix_command, slash_command)]
+pub async fn init(ctx: Context<'_>) -> Result<(), Error> {
+    let http = ctx.http();
+    let guild_id = ctx
+        .guild_id()
+        .expect("This command can only be ran in a server");
+
+    if let Ok(id) = ctx
+        .data()
+        .db
+        .get_server_manager_role_id(u64::from(guild_id))
+        .await
+    {
+        let role_id = RoleId::from(id);
+        let role_set = guild_id.roles(http).await.unwrap();
+
+        match role_set.get(&role_id) {
+            Some(role) => {
+                let _ = ctx.reply(format!("Server is already initialized.\nGrant someone the [{}] role to allow them to create events",
+                role.name)).await;
+                return Ok(());
+            }
+            None => {
+                warn!("Event creator role has been deleted from server {}, recreating ...", u64::from(guild_id));
+            }
+        }
     }
 
+    // Keep the server's settings if it was initialized before
+    let settings = ctx
+        .data()
+        .db
+        .get_guild_settings(u64::from(guild_id))
+        .await
+        .unwrap_or_default();
+
     let menad = guild_id
-        .create_role(ctx.http(), EditRole::new().name("Menad"))
+        .create_role(ctx.http(), EditRole::new().name(settings.creator_role_name()))
         .await
         .expect("Couldn't create role. Please try again");
 
-    println!(
+    info!(
         "Created event creator role on server {}",
         u64::from(guild_id)
     );
 
-    insert_server_manager_role(
-        &ctx.data().conn.get().unwrap(),
-        u64::from(guild_id),
-        u64::from(menad.id),
-    )
-    .expect(
+    ctx.data()
+        .db
+        .insert_server_manager_role(u64::from(guild_id), u64::from(menad.id))
+        .await
+        .expect(
         "Couldn't write new MENAD role to database. Please delete the role and call /init again",
     );
 
-    println!("Wrote new role to database");
+    info!("Wrote new role to database");
 
     let _ = ctx.reply("Server initialized successfully !").await;
 
     Ok(())
 }
+
+/// Shows or changes this server's settings
+#[poise::command(
+    prefix_command,
+    slash_command,
+    rename = "config",
+    guild_only,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    subcommands("get", "set", "reset")
+)]
+pub async fn settings(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Loads the server's settings, or tells the user to call /init first
+async fn guild_settings(ctx: Context<'_>) -> Result<Option<GuildSettings>, Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(Error::from("That command can only be ran in a server"))?;
+
+    match ctx.data().db.get_guild_settings(u64::from(guild_id)).await {
+        Ok(settings) => Ok(Some(settings)),
+        Err(StoreError::NotFound) => {
+            ctx.reply("This server isn't initialized yet. Please call /init first")
+                .await?;
+            Ok(None)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Shows one setting, or all of them
+#[poise::command(prefix_command, slash_command)]
+async fn get(
+    ctx: Context<'_>,
+    #[description = "(Optional) The setting to show"] key: Option<SettingKey>,
+) -> Result<(), Error> {
+    let settings = match guild_settings(ctx).await? {
+        Some(settings) => settings,
+        None => return Ok(()),
+    };
+
+    let keys = match key {
+        Some(key) => vec![key],
+        None => SettingKey::ALL.to_vec(),
+    };
+    let body = keys
+        .into_iter()
+        .map(|key| {
+            let (value, own) = settings.effective(key, &ctx.data().config);
+            format!(
+                "**{}**: {}{}",
+                key.name(),
+                value,
+                if own { "" } else { " (default)" }
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    ctx.reply(body).await?;
+
+    Ok(())
+}
+
+/// Changes a setting for this server
+#[poise::command(prefix_command, slash_command)]
+async fn set(
+    ctx: Context<'_>,
+    #[description = "The setting to change"] key: SettingKey,
+    #[description = "Its new value"] value: String,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(Error::from("That command can only be ran in a server"))?;
+    let mut settings = match guild_settings(ctx).await? {
+        Some(settings) => settings,
+        None => return Ok(()),
+    };
+
+    let value = match key.validate(&value) {
+        Ok(value) => value,
+        Err(e) => {
+            ctx.reply(format!("Invalid value for {}: {}", key.name(), e))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let allowed_channels: Option<(&[ChannelType], &str)> = match key {
+        SettingKey::AnnouncementChannel => {
+            Some((&[ChannelType::Text, ChannelType::News], "text or announcement"))
+        }
+        SettingKey::ThreadChannel => Some((&[ChannelType::Text, ChannelType::Forum], "text or forum")),
+        SettingKey::ArchiveCategory => Some((&[ChannelType::Category], "category")),
+        SettingKey::LogChannel => Some((&[ChannelType::Text], "text")),
+        _ => None,
+    };
+    if let Some((kinds, description)) = allowed_channels {
+        let channel = ChannelId::new(value.parse()?)
+            .to_channel(ctx.http())
+            .await
+            .ok()
+            .and_then(|c| c.guild());
+        match channel {
+            Some(c) if c.guild_id == guild_id && kinds.contains(&c.kind) => {}
+            _ => {
+                ctx.reply(format!(
+                    "Invalid value for {}: must be a {} channel of this server",
+                    key.name(),
+                    description
+                ))
+                .await?;
+                return Ok(());
+            }
+        }
+    }
+
+    settings.set(key, Some(value.clone()));
+    ctx.data()
+        .db
+        .set_guild_settings(u64::from(guild_id), settings)
+        .await?;
+
+    info!("Set {} to {} on server {}", key.name(), value, guild_id);
+    ctx.reply(format!("{} is now {}", key.name(), value)).await?;
+
+    Ok(())
+}
+
+/// Restores a setting's default value for this server
+#[poise::command(prefix_command, slash_command)]
+async fn reset(
+    ctx: Context<'_>,
+    #[description = "The setting to reset"] key: SettingKey,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(Error::from("That command can only be ran in a server"))?;
+    let mut settings = match guild_settings(ctx).await? {
+        Some(settings) => settings,
+        None => return Ok(()),
+    };
+
+    settings.set(key, None);
+    let (value, _) = settings.effective(key, &ctx.data().config);
+    ctx.data()
+        .db
+        .set_guild_settings(u64::from(guild_id), settings)
+        .await?;
+
+    info!("Reset {} on server {}", key.name(), guild_id);
+    ctx.reply(format!("{} is back to its default: {}", key.name(), value))
+        .await?;
+
+    Ok(())
+}
+
+/// Manages the channel layouts new events can be created with
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS",
+    subcommands("save_template", "list_templates", "delete_template")
+)]
+pub async fn template(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Suggests the server's templates
+async fn autocomplete_template(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let guild_id = match ctx.guild_id() {
+        Some(id) => id,
+        None => return Vec::new(),
+    };
+    let partial = partial.to_lowercase();
+
+    ctx.data()
+        .db
+        .get_templates(u64::from(guild_id))
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|template| template.name)
+        .filter(|name| name.to_lowercase().starts_with(&partial))
+        .collect()
+}
+
+/// Saves a channel layout, e.g. "general, voice:voice, dice-rolls:thread"
+#[poise::command(prefix_command, slash_command, rename = "save")]
+async fn save_template(
+    ctx: Context<'_>,
+    #[description = "The template's name"] name: String,
+    #[description = "Channels, each one text, voice, stage, forum or thread, e.g. \"general, lobby:voice\""]
+    layout: String,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(Error::from("That command can only be ran in a server"))?;
+
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 50 {
+        ctx.reply("Template names must be between 1 and 50 characters")
+            .await?;
+        return Ok(());
+    }
+    if let Err(e) = parse_layout(&layout) {
+        ctx.reply(format!("Invalid layout: {}", e)).await?;
+        return Ok(());
+    }
+
+    ctx.data()
+        .db
+        .save_template(
+            u64::from(guild_id),
+            Template {
+                name: name.clone(),
+                layout: layout.trim().to_string(),
+            },
+        )
+        .await?;
+
+    info!("Saved template {} on server {}", name, guild_id);
+    ctx.reply(format!(
+        "Saved template {}. Use it with /event create template:{}",
+        name, name
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Lists this server's templates
+#[poise::command(prefix_command, slash_command, rename = "list")]
+async fn list_templates(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(Error::from("That command can only be ran in a server"))?;
+
+    let templates = ctx.data().db.get_templates(u64::from(guild_id)).await?;
+    if templates.is_empty() {
+        ctx.reply("No templates yet. Use `template save` to create one !")
+            .await?;
+    } else {
+        let body = templates
+            .iter()
+            .map(|template| format!("**{}**: {}", template.name, template.layout))
+            .collect::<Vec<String>>()
+            .join("\n");
+        ctx.reply(body).await?;
+    }
+
+    Ok(())
+}
+
+/// Deletes a template. Events created with it are left untouched
+#[poise::command(prefix_command, slash_command, rename = "delete")]
+async fn delete_template(
+    ctx: Context<'_>,
+    #[description = "The template to delete"]
+    #[autocomplete = "autocomplete_template"]
+    name: String,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(Error::from("That command can only be ran in a server"))?;
+
+    match ctx
+        .data()
+        .db
+        .delete_template(u64::from(guild_id), name.clone())
+        .await?
+    {
+        0 => {
+            ctx.reply(format!("There is no template named {} on this server", name))
+                .await?
+        }
+        _ => {
+            info!("Deleted template {} on server {}", name, guild_id);
+            ctx.reply(format!("Deleted template {}", name)).await?
+        }
+    };
+
+    Ok(())
+}
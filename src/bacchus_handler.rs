@@ -1,19 +1,42 @@
-use crate::events::{get_event_by_manifest, DatabasePool};
+use crate::events::EventStatus;
+use crate::approval;
+use crate::bracket;
+use crate::checkin;
+use crate::composition;
+use crate::notifications;
+use crate::participation;
+use crate::polls;
+use crate::repository::Repository;
 use poise::serenity_prelude::prelude::TypeMapKey;
-use poise::serenity_prelude::{CacheHttp, Context, EventHandler, Reaction, ReactionType, RoleId};
+use poise::serenity_prelude::{
+    CacheHttp, Context, EventHandler, Interaction, Reaction, ReactionType,
+};
 use std::future::Future;
 use std::pin::Pin;
+use tracing::{info, warn};
 
 pub struct BacchusHandler;
 
 pub struct DBWrapper {
-    pub(crate) pool: DatabasePool,
+    pub(crate) db: Repository,
 }
 
 impl TypeMapKey for DBWrapper {
     type Value = DBWrapper;
 }
 
+/// Whether [reaction] is [emoji], either a unicode emoji or a custom one like <:name:id>.
+/// Custom emojis are compared by id, their name may be missing from gateway events
+pub fn is_emoji(reaction: &ReactionType, emoji: &str) -> bool {
+    match (reaction, ReactionType::try_from(emoji)) {
+        (ReactionType::Custom { id, .. }, Ok(ReactionType::Custom { id: expected, .. })) => {
+            *id == expected
+        }
+        (ReactionType::Unicode(name), Ok(ReactionType::Unicode(expected))) => *name == expected,
+        _ => false,
+    }
+}
+
 impl EventHandler for BacchusHandler {
     fn reaction_add<'life0, 'async_trait>(
         &'life0 self,
@@ -25,10 +48,7 @@ impl EventHandler for BacchusHandler {
         'life0: 'async_trait,
     {
         Box::pin(async move {
-            //1: Check that the reaction is the right emoji, and in a server
-            if add_reaction.emoji != ReactionType::Unicode(String::from("✅")) {
-                return;
-            }
+            //1: Check that the reaction is in a server
             let guild_id = match add_reaction.guild_id {
                 None => {
                     return;
@@ -37,15 +57,18 @@ impl EventHandler for BacchusHandler {
             };
 
             //2: Check that there's an event linked to the original message
-            let data = ctx.data.read().await;
-            let conn = data
+            let db = ctx
+                .data
+                .read()
+                .await
                 .get::<DBWrapper>()
                 .expect("Shared db could not be found")
-                .pool
-                .get()
-                .expect("Couldn't connect to Shared DB");
+                .db
+                .clone();
 
-            let (id, event) = match get_event_by_manifest(&conn, u64::from(add_reaction.message_id))
+            let (id, event) = match db
+                .get_event_by_manifest(u64::from(add_reaction.message_id))
+                .await
             {
                 Err(_) => {
                     return;
@@ -53,16 +76,64 @@ impl EventHandler for BacchusHandler {
                 Ok((id, event)) => (id, event),
             };
 
+            // Each event keeps the emoji it was created with.
+            // Only published events can be joined, archives aren't anymore
+            if !is_emoji(&add_reaction.emoji, &event.rsvp_emoji)
+                || event.status != EventStatus::Published
+                || event.archived_at.is_some()
+            {
+                return;
+            }
+
             let user_id = add_reaction.user_id.expect("Authorless reaction");
             let user = guild_id.member(ctx.http(), user_id).await.unwrap();
 
-            //3. Add corresponding role to user
-            let player_role_id = RoleId::from(event.participant_role_id);
-            user.add_role(ctx.http(), player_role_id)
-                .await
-                .expect("Error adding role to user");
+            // Including our own reaction on the manifest, bots don't take part in events
+            if user_id == ctx.cache.current_user().id || user.user.bot {
+                return;
+            }
+
+            //3. Turn away members the event's rules keep out
+            match participation::check_eligibility(ctx.http(), &db, guild_id, id, user_id).await {
+                Ok(Ok(())) => {}
+                Ok(Err(reason)) => {
+                    info!("{} can't join event {}(id {}): {}", user_id, event.name, id, reason);
+                    if let Err(e) = add_reaction.delete(ctx.http()).await {
+                        warn!("Couldn't remove the reaction of {}: {}", user_id, e);
+                    }
+                    let text = format!("You can't join {}: {}", event.name, reason);
+                    if let Err(e) = notifications::dm(ctx.http(), user_id, &text).await {
+                        warn!("Couldn't tell {} why they can't join: {}", user_id, e);
+                    }
+                    return;
+                }
+                Err(e) => {
+                    warn!("Couldn't check whether {} may join event {}: {}", user_id, id, e);
+                    return;
+                }
+            }
+
+            //4. Invite-only events ask their managers first
+            if event.requests_channel_id.is_some() {
+                let joined = db
+                    .get_participants(id)
+                    .await
+                    .is_ok_and(|participants| participants.contains(&u64::from(user_id)));
+                if !joined {
+                    if let Err(e) = approval::request(ctx.http(), &db, id, &event, user_id).await {
+                        warn!("Couldn't ask to let {} into event {}: {}", user_id, id, e);
+                    }
+                    return;
+                }
+            }
 
-            println!(
+            //5. Let the user into the event
+            if let Err(e) = participation::join(ctx.http(), &db, guild_id, id, &event, user_id).await {
+                warn!("Couldn't let {} into event {}: {}", user_id, id, e);
+                return;
+            }
+
+            info!(
                 "Granted {} (id {}) player privileges for event {}(id {}) on {}(id {})",
                 user.display_name(),
                 user_id,
@@ -74,7 +145,7 @@ impl EventHandler for BacchusHandler {
                 guild_id
             );
 
-            //4. TODO: Send message on Discord to inform user of new privileges
+            //6. TODO: Send message on Discord to inform user of new privileges
         })
     }
 
@@ -88,10 +159,7 @@ impl EventHandler for BacchusHandler {
         'life0: 'async_trait,
     {
         Box::pin(async move {
-            //1: Check that the reaction is the right emoji, and in a server
-            if remove_reaction.emoji != ReactionType::Unicode(String::from("✅")) {
-                return;
-            }
+            //1: Check that the reaction is in a server
             let guild_id = match remove_reaction.guild_id {
                 None => {
                     return;
@@ -100,32 +168,62 @@ impl EventHandler for BacchusHandler {
             };
 
             //2: Check that there's an event linked to the original message
-            let data = ctx.data.read().await;
-            let conn = data
+            let db = ctx
+                .data
+                .read()
+                .await
                 .get::<DBWrapper>()
                 .expect("Shared db could not be found")
-                .pool
-                .get()
-                .expect("Couldn't connect to Shared DB");
-
-            let (id, event) =
-                match get_event_by_manifest(&conn, u64::from(remove_reaction.message_id)) {
-                    Err(_) => {
-                        return;
-                    }
-                    Ok((id, event)) => (id, event),
-                };
+                .db
+                .clone();
+
+            let (id, event) = match db
+                .get_event_by_manifest(u64::from(remove_reaction.message_id))
+                .await
+            {
+                Err(_) => {
+                    return;
+                }
+                Ok((id, event)) => (id, event),
+            };
+
+            // Each event keeps the emoji it was created with
+            if !is_emoji(&remove_reaction.emoji, &event.rsvp_emoji) {
+                return;
+            }
 
             let user_id = remove_reaction.user_id.expect("Authorless reaction");
             let user = guild_id.member(ctx.http(), user_id).await.unwrap();
 
-            //3. Add corresponding role to user
-            let player_role_id = RoleId::from(event.participant_role_id);
-            user.remove_role(ctx.http(), player_role_id)
-                .await
-                .expect("Error adding role to user");
+            // Including our own reaction on the manifest, bots don't take part in events
+            if user_id == ctx.cache.current_user().id || user.user.bot {
+                return;
+            }
 
-            println!(
+            //3. Take back a pending join request, or take the user out of the event
+            match approval::withdraw(ctx.http(), &db, id, &event, user_id).await {
+                Ok(true) => {
+                    info!("{} withdrew their request to join event {}", user_id, id);
+                    return;
+                }
+                Ok(false) => {}
+                Err(e) => warn!("Couldn't withdraw the join request of {}: {}", user_id, e),
+            }
+            // The bot removes the reactions of those it turned away, they never joined
+            match db.get_participants(id).await {
+                Ok(participants) if !participants.contains(&u64::from(user_id)) => return,
+                Ok(_) => {}
+                Err(e) => {
+                    warn!("Couldn't get the participants of event {}: {}", id, e);
+                    return;
+                }
+            }
+            if let Err(e) = participation::drop_out(ctx.http(), &db, guild_id, id, &event, user_id).await {
+                warn!("Couldn't take {} out of event {}: {}", user_id, id, e);
+                return;
+            }
+
+            info!(
                 "Stripped {} (id {}) of player privileges for event {}(id {}) on {}(id {})",
                 user.display_name(),
                 user_id,
@@ -138,4 +236,80 @@ impl EventHandler for BacchusHandler {
             );
         })
     }
+
+    fn interaction_create<'life0, 'async_trait>(
+        &'life0 self,
+        ctx: Context,
+        interaction: Interaction,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'async_trait>>
+    where
+        Self: 'async_trait,
+        'life0: 'async_trait,
+    {
+        Box::pin(async move {
+            // Slash commands are poise's, only the components and forms of the bot's messages are handled here
+            let db = ctx
+                .data
+                .read()
+                .await
+                .get::<DBWrapper>()
+                .expect("Shared db could not be found")
+                .db
+                .clone();
+
+            let component = match interaction {
+                Interaction::Component(component) => component,
+                Interaction::Modal(modal) => {
+                    if modal.data.custom_id.starts_with(checkin::CODE_PREFIX) {
+                        if let Err(e) = checkin::submit(ctx.http(), &db, &modal).await {
+                            warn!(
+                                "Couldn't check {} in with a code on {}: {}",
+                                modal.user.id, modal.data.custom_id, e
+                            );
+                        }
+                    }
+                    return;
+                }
+                _ => return,
+            };
+
+            let custom_id = &component.data.custom_id;
+            if custom_id.starts_with(polls::POLL_PREFIX) {
+                if let Err(e) = polls::vote(&ctx, &db, &component).await {
+                    warn!(
+                        "Couldn't save the vote of {} on poll {}: {}",
+                        component.user.id, custom_id, e
+                    );
+                }
+            } else if custom_id.starts_with(composition::SLOT_PREFIX) {
+                if let Err(e) = composition::pick(ctx.http(), &db, &component).await {
+                    warn!(
+                        "Couldn't pick a slot for {} on {}: {}",
+                        component.user.id, custom_id, e
+                    );
+                }
+            } else if custom_id.starts_with(approval::REQUEST_PREFIX) {
+                if let Err(e) = approval::decide(ctx.http(), &db, &component).await {
+                    warn!(
+                        "Couldn't answer join request {} for {}: {}",
+                        custom_id, component.user.id, e
+                    );
+                }
+            } else if custom_id.starts_with(bracket::CONFIRM_PREFIX) {
+                if let Err(e) = bracket::confirm(ctx.http(), &db, &component).await {
+                    warn!(
+                        "Couldn't confirm result {} for {}: {}",
+                        custom_id, component.user.id, e
+                    );
+                }
+            } else if custom_id.starts_with(checkin::CHECKIN_PREFIX) {
+                if let Err(e) = checkin::press(ctx.http(), &db, &component).await {
+                    warn!(
+                        "Couldn't check {} in on {}: {}",
+                        component.user.id, custom_id, e
+                    );
+                }
+            }
+        })
+    }
 }
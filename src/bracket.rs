@@ -0,0 +1,896 @@
+use crate::bacchus::Error;
+use crate::bracket_image;
+use crate::events::{Bracket, BracketFormat, Entrant, EventData, MatchReport};
+use crate::repository::Repository;
+use crate::store::StoreError;
+use crate::teams;
+use poise::serenity_prelude::{
+    ButtonStyle, ChannelId, ComponentInteraction, CreateActionRow, CreateAllowedMentions,
+    CreateAttachment, CreateButton, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateMessage, EditMessage, Http, MessageId, RoleId,
+};
+use poise::ChoiceParameter;
+use rand::seq::SliceRandom;
+use std::fmt;
+use std::str::FromStr;
+use tracing::{info, warn};
+
+/// Custom id of the button confirming a reported result, followed by <event id>:<match id>:<winner seed>
+pub const CONFIRM_PREFIX: &str = "bracket_confirm:";
+/// Keeps the picture readable and the rounds within an embed's 25 fields
+pub const MAX_ENTRANTS: usize = 32;
+/// Name of the picture attached to a bracket's message
+const IMAGE_NAME: &str = "bracket.png";
+/// Characters an embed field can hold
+const MAX_FIELD_LENGTH: usize = 1024;
+/// Characters of an entrant's name shown in the text of a bracket
+const MAX_NAME_LENGTH: usize = 32;
+
+/// Who a bracket is made of
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EntrantSource {
+    /// Each participant plays for themselves
+    #[default]
+    #[name = "participants"]
+    Participants,
+    /// The teams made with /event teams generate
+    #[name = "teams"]
+    Teams,
+}
+
+/// How entrants are seeded, the best seeds meet as late as possible
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Seeding {
+    #[default]
+    #[name = "random"]
+    Random,
+    /// By total skill, see /event teams skill
+    #[name = "skill"]
+    Skill,
+    /// Participants in the order they joined, teams by number
+    #[name = "order"]
+    Order,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Side {
+    Winners,
+    /// Where entrants of a double elimination bracket go after their first loss
+    Losers,
+    /// The grand final of a double elimination bracket, played twice when the losers' champion wins it
+    Final,
+}
+
+/// A match of a bracket, written like W1-2 (second match of the first winners round), L3-1, GF or GF2
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MatchId {
+    pub side: Side,
+    /// Starts at 1
+    pub round: u32,
+    /// Starts at 1, from the top of the bracket
+    pub index: u32,
+}
+
+impl MatchId {
+    pub fn new(side: Side, round: u32, index: u32) -> MatchId {
+        MatchId { side, round, index }
+    }
+}
+
+impl fmt::Display for MatchId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.side {
+            Side::Winners => write!(f, "W{}-{}", self.round, self.index),
+            Side::Losers => write!(f, "L{}-{}", self.round, self.index),
+            Side::Final if self.round == 1 => write!(f, "GF"),
+            Side::Final => write!(f, "GF{}", self.round),
+        }
+    }
+}
+
+impl FromStr for MatchId {
+    type Err = String;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        let id = id.trim().to_uppercase();
+        let invalid = || format!("`{}` isn't a match, they look like W1-2, L3-1 or GF", id);
+        match id.as_str() {
+            "GF" => return Ok(MatchId::new(Side::Final, 1, 1)),
+            "GF2" => return Ok(MatchId::new(Side::Final, 2, 1)),
+            _ => {}
+        }
+
+        let side = match id.chars().next() {
+            Some('W') => Side::Winners,
+            Some('L') => Side::Losers,
+            _ => return Err(invalid()),
+        };
+        let (round, index) = id[1..].split_once('-').ok_or_else(invalid)?;
+        let round: u32 = round.parse().map_err(|_| invalid())?;
+        let index: u32 = index.parse().map_err(|_| invalid())?;
+        if round == 0 || index == 0 {
+            return Err(invalid());
+        }
+        Ok(MatchId::new(side, round, index))
+    }
+}
+
+/// Where one of the two sides of a match comes from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    Seed(u32),
+    Winner(MatchId),
+    Loser(MatchId),
+    /// The loser of a grand final, when they came from the winners bracket and deserve a rematch
+    Rematch(MatchId),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MatchSpec {
+    pub id: MatchId,
+    pub sources: [Source; 2],
+}
+
+/// One side of a match
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slot {
+    /// By seed
+    Entrant(u32),
+    /// Nobody, the other side goes through
+    Bye,
+    /// Waiting for an earlier match
+    Pending,
+}
+
+/// A match as far as the bracket has been played
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MatchState {
+    pub id: MatchId,
+    pub slots: [Slot; 2],
+    /// Decided by a confirmed report, or a bye
+    pub winner: Option<Slot>,
+    /// Seed of the winner a player reported, waiting for confirmation
+    pub reported: Option<u32>,
+}
+
+impl MatchState {
+    pub fn loser(&self) -> Option<Slot> {
+        self.winner.map(|winner| {
+            if winner == self.slots[0] {
+                self.slots[1]
+            } else {
+                self.slots[0]
+            }
+        })
+    }
+
+    /// Whether both sides are known, and neither is a bye
+    pub fn is_playable(&self) -> bool {
+        self.slots
+            .iter()
+            .all(|slot| matches!(slot, Slot::Entrant(_)))
+    }
+
+    /// Seeds of the sides of the match
+    pub fn seeds(&self) -> Vec<u32> {
+        self.slots
+            .iter()
+            .filter_map(|slot| match slot {
+                Slot::Entrant(seed) => Some(*seed),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// The seeds of the first round from the top of a bracket of [size] (a power of two) entrants.
+/// Seeds of a match add up to size + 1, and the best two can only meet in the final
+pub fn seed_order(size: usize) -> Vec<u32> {
+    let mut order = vec![1];
+    while order.len() < size {
+        let sum = order.len() as u32 * 2 + 1;
+        order = order.iter().flat_map(|seed| [*seed, sum - seed]).collect();
+    }
+    order
+}
+
+/// Every match of a bracket of [entrants], in an order where matches come after those they depend on.
+/// Brackets are rounded up to a power of two, the missing seeds are byes
+pub fn structure(format: BracketFormat, entrants: usize) -> Vec<MatchSpec> {
+    let size = entrants.max(2).next_power_of_two();
+    let rounds = size.trailing_zeros();
+    let winners = |round, index| MatchId::new(Side::Winners, round, index);
+    let losers = |round, index| MatchId::new(Side::Losers, round, index);
+
+    let mut specs: Vec<MatchSpec> = seed_order(size)
+        .chunks(2)
+        .enumerate()
+        .map(|(i, pair)| MatchSpec {
+            id: winners(1, i as u32 + 1),
+            sources: [Source::Seed(pair[0]), Source::Seed(pair[1])],
+        })
+        .collect();
+    for round in 2..=rounds {
+        for index in 1..=(size >> round) as u32 {
+            specs.push(MatchSpec {
+                id: winners(round, index),
+                sources: [
+                    Source::Winner(winners(round - 1, 2 * index - 1)),
+                    Source::Winner(winners(round - 1, 2 * index)),
+                ],
+            });
+        }
+    }
+    if format == BracketFormat::Single {
+        return specs;
+    }
+
+    // The losers of the first round pair up. Then each even losers round takes on
+    // the losers of the next winners round, flipped so that rematches come late,
+    // and each odd one halves the field
+    let losers_champion = if rounds == 1 {
+        Source::Loser(winners(1, 1))
+    } else {
+        for index in 1..=(size >> 2) as u32 {
+            specs.push(MatchSpec {
+                id: losers(1, index),
+                sources: [
+                    Source::Loser(winners(1, 2 * index - 1)),
+                    Source::Loser(winners(1, 2 * index)),
+                ],
+            });
+        }
+        for round in 1..rounds {
+            let count = (size >> (round + 1)) as u32;
+            for index in 1..=count {
+                specs.push(MatchSpec {
+                    id: losers(2 * round, index),
+                    sources: [
+                        Source::Winner(losers(2 * round - 1, index)),
+                        Source::Loser(winners(round + 1, count + 1 - index)),
+                    ],
+                });
+            }
+            if round + 1 < rounds {
+                for index in 1..=count / 2 {
+                    specs.push(MatchSpec {
+                        id: losers(2 * round + 1, index),
+                        sources: [
+                            Source::Winner(losers(2 * round, 2 * index - 1)),
+                            Source::Winner(losers(2 * round, 2 * index)),
+                        ],
+                    });
+                }
+            }
+        }
+        Source::Winner(losers(2 * (rounds - 1), 1))
+    };
+
+    let grand_final = MatchId::new(Side::Final, 1, 1);
+    specs.push(MatchSpec {
+        id: grand_final,
+        sources: [Source::Winner(winners(rounds, 1)), losers_champion],
+    });
+    specs.push(MatchSpec {
+        id: MatchId::new(Side::Final, 2, 1),
+        sources: [Source::Winner(grand_final), Source::Rematch(grand_final)],
+    });
+    specs
+}
+
+/// Plays the matches of [specs] with the reports of a bracket of [entrants].
+/// Only confirmed reports decide a match, and reports naming someone who isn't in the match are ignored,
+/// so that correcting an early result undoes the later ones it invalidates
+pub fn play(specs: &[MatchSpec], entrants: usize, reports: &[MatchReport]) -> Vec<MatchState> {
+    let mut states: Vec<MatchState> = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let earlier = |id: MatchId| states.iter().find(|state| state.id == id);
+        let slots = spec.sources.map(|source| match source {
+            Source::Seed(seed) if seed as usize <= entrants => Slot::Entrant(seed),
+            Source::Seed(_) => Slot::Bye,
+            Source::Winner(id) => earlier(id)
+                .and_then(|state| state.winner)
+                .unwrap_or(Slot::Pending),
+            Source::Loser(id) => earlier(id)
+                .and_then(MatchState::loser)
+                .unwrap_or(Slot::Pending),
+            Source::Rematch(id) => match earlier(id) {
+                Some(state) if state.winner.is_some() && state.winner == Some(state.slots[1]) => {
+                    state.slots[0]
+                }
+                Some(state) if state.winner.is_some() => Slot::Bye,
+                _ => Slot::Pending,
+            },
+        });
+
+        let mut state = MatchState {
+            id: spec.id,
+            slots,
+            winner: None,
+            reported: None,
+        };
+        match slots {
+            [Slot::Entrant(_), Slot::Entrant(_)] => {
+                let id = spec.id.to_string();
+                let report = reports.iter().find(|report| {
+                    report.match_id == id && slots.contains(&Slot::Entrant(report.winner))
+                });
+                match report {
+                    Some(report) if report.confirmed => {
+                        state.winner = Some(Slot::Entrant(report.winner))
+                    }
+                    Some(report) => state.reported = Some(report.winner),
+                    None => {}
+                }
+            }
+            [winner, Slot::Bye] | [Slot::Bye, winner] if winner != Slot::Pending => {
+                state.winner = Some(winner)
+            }
+            _ => {}
+        }
+        states.push(state);
+    }
+    states
+}
+
+/// Seed of the entrant who won the bracket, once its last match is decided
+pub fn champion(states: &[MatchState]) -> Option<u32> {
+    match states.last()?.winner? {
+        Slot::Entrant(seed) => Some(seed),
+        _ => None,
+    }
+}
+
+/// Orders [entrants] and numbers their seeds from 1
+pub fn seed(mut entrants: Vec<Entrant>, skills: &[(u64, i64)], seeding: Seeding) -> Vec<Entrant> {
+    match seeding {
+        Seeding::Random => entrants.shuffle(&mut rand::thread_rng()),
+        Seeding::Skill => {
+            // Entrants of the same skill are still drawn at random
+            entrants.shuffle(&mut rand::thread_rng());
+            entrants.sort_by_key(|entrant| {
+                std::cmp::Reverse(teams::total_skill(&entrant.members, skills))
+            });
+        }
+        Seeding::Order => {}
+    }
+    for (i, entrant) in entrants.iter_mut().enumerate() {
+        entrant.seed = i as u32 + 1;
+    }
+    entrants
+}
+
+/// The name of the entrant with [seed], "?" for unknown seeds
+pub fn entrant_name(bracket: &Bracket, seed: u32) -> &str {
+    bracket
+        .entrants
+        .iter()
+        .find(|entrant| entrant.seed == seed)
+        .map_or("?", |entrant| entrant.name.as_str())
+}
+
+/// The seed of the side of [state] [user_id] plays for
+pub fn side_of(bracket: &Bracket, state: &MatchState, user_id: u64) -> Option<u32> {
+    state.seeds().into_iter().find(|seed| {
+        bracket
+            .entrants
+            .iter()
+            .any(|entrant| entrant.seed == *seed && entrant.members.contains(&user_id))
+    })
+}
+
+/// What the round of [id] is called
+pub fn round_name(format: BracketFormat, id: &MatchId, winners_rounds: u32) -> String {
+    match (format, id.side) {
+        (BracketFormat::Single, _) if id.round == winners_rounds => String::from("Final"),
+        (BracketFormat::Single, _) => format!("Round {}", id.round),
+        (BracketFormat::Double, Side::Winners) if id.round == winners_rounds => {
+            String::from("Winners final")
+        }
+        (BracketFormat::Double, Side::Winners) => format!("Winners round {}", id.round),
+        (BracketFormat::Double, Side::Losers) => format!("Losers round {}", id.round),
+        (BracketFormat::Double, Side::Final) if id.round == 1 => String::from("Grand final"),
+        (BracketFormat::Double, Side::Final) => String::from("Grand final reset"),
+    }
+}
+
+/// One embed field per round, with a line per match. Matches with a bye are left out, they aren't played
+pub fn fields(bracket: &Bracket, states: &[MatchState]) -> Vec<(String, String)> {
+    let winners_rounds = states
+        .iter()
+        .filter(|state| state.id.side == Side::Winners)
+        .map(|state| state.id.round)
+        .max()
+        .unwrap_or(1);
+    let side = |state: &MatchState, slot: Slot| match slot {
+        Slot::Entrant(seed) => {
+            let name: String = entrant_name(bracket, seed)
+                .chars()
+                .take(MAX_NAME_LENGTH)
+                .collect();
+            if state.winner == Some(slot) {
+                format!("**{}**", name)
+            } else if state.reported == Some(seed) {
+                format!("{} (reported)", name)
+            } else {
+                name
+            }
+        }
+        _ => String::from("TBD"),
+    };
+
+    let mut rounds: Vec<(String, Vec<String>)> = vec![];
+    for state in states {
+        if state.slots.contains(&Slot::Bye) {
+            continue;
+        }
+        let name = round_name(bracket.format, &state.id, winners_rounds);
+        let line = format!(
+            "`{}` {} vs {}",
+            state.id,
+            side(state, state.slots[0]),
+            side(state, state.slots[1])
+        );
+        match rounds.last_mut() {
+            Some((round, lines)) if *round == name => lines.push(line),
+            _ => rounds.push((name, vec![line])),
+        }
+    }
+
+    rounds
+        .into_iter()
+        .map(|(name, lines)| {
+            let mut value = String::new();
+            for line in lines {
+                if value.chars().count() + line.chars().count() + 2 > MAX_FIELD_LENGTH {
+                    value += "\n…";
+                    break;
+                }
+                if !value.is_empty() {
+                    value += "\n";
+                }
+                value += &line;
+            }
+            (name, value)
+        })
+        .collect()
+}
+
+/// The embed and picture of [bracket]
+fn view(
+    event: &EventData,
+    bracket: &Bracket,
+    reports: &[MatchReport],
+) -> Result<(CreateEmbed, CreateAttachment), Error> {
+    let specs = structure(bracket.format, bracket.entrants.len());
+    let states = play(&specs, bracket.entrants.len(), reports);
+
+    let mut description = format!(
+        "{}, {} entrants",
+        bracket.format.name(),
+        bracket.entrants.len()
+    );
+    description += &match champion(&states) {
+        Some(seed) => format!("\n🏆 **{}** won", entrant_name(bracket, seed)),
+        None => String::from("\nReport results with /event bracket report"),
+    };
+    let embed = CreateEmbed::new()
+        .title(format!("{} bracket", event.name))
+        .description(description)
+        .fields(
+            fields(bracket, &states)
+                .into_iter()
+                .map(|(name, value)| (name, value, false)),
+        )
+        .image(format!("attachment://{}", IMAGE_NAME));
+    let image = bracket_image::render(bracket, &specs, &states)?;
+
+    Ok((embed, CreateAttachment::bytes(image, IMAGE_NAME)))
+}
+
+/// Posts a new bracket for an event in [channel_id], replacing its previous one
+pub async fn post(
+    http: &Http,
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+    channel_id: ChannelId,
+    format: BracketFormat,
+    entrants: Vec<Entrant>,
+) -> Result<Bracket, Error> {
+    remove(http, db, event_id, event).await?;
+
+    let mut bracket = Bracket {
+        format,
+        channel_id: u64::from(channel_id),
+        message_id: 0,
+        entrants,
+    };
+    let (embed, image) = view(event, &bracket, &[])?;
+    let message = channel_id
+        .send_message(http, CreateMessage::new().embed(embed).add_file(image))
+        .await?;
+    bracket.message_id = u64::from(message.id);
+    db.create_bracket(event_id, bracket.clone()).await?;
+
+    info!(
+        "Posted a {} bracket of {} entrants for event {}(id {})",
+        format.as_str(),
+        bracket.entrants.len(),
+        event.name,
+        event_id
+    );
+    Ok(bracket)
+}
+
+/// The embed and picture of an event's bracket, to show it elsewhere
+pub async fn show(
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+) -> Result<(CreateEmbed, CreateAttachment), Error> {
+    let bracket = db.get_bracket(event_id).await?;
+    let reports = db.get_results(event_id).await?;
+    view(event, &bracket, &reports)
+}
+
+/// Shows the latest results on the message of an event's bracket
+pub async fn refresh(
+    http: &Http,
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+) -> Result<(), Error> {
+    let bracket = db.get_bracket(event_id).await?;
+    let reports = db.get_results(event_id).await?;
+    let (embed, image) = view(event, &bracket, &reports)?;
+    ChannelId::new(bracket.channel_id)
+        .edit_message(
+            http,
+            MessageId::new(bracket.message_id),
+            EditMessage::new().embed(embed).new_attachment(image),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Deletes the bracket of an event and its message, returns how many brackets there were
+pub async fn remove(
+    http: &Http,
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+) -> Result<usize, Error> {
+    let bracket = match db.get_bracket(event_id).await {
+        Ok(bracket) => bracket,
+        Err(StoreError::NotFound) => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    // People may have deleted the message by hand
+    if let Err(e) = ChannelId::new(bracket.channel_id)
+        .delete_message(http, MessageId::new(bracket.message_id))
+        .await
+    {
+        warn!(
+            "Couldn't delete the bracket message of event {}(id {}): {}",
+            event.name, event_id, e
+        );
+    }
+    Ok(db.delete_bracket(event_id).await?)
+}
+
+/// The button the other side of a match presses to confirm a reported result
+pub fn confirm_button(event_id: i64, match_id: &MatchId, winner: u32) -> CreateActionRow {
+    CreateActionRow::Buttons(vec![CreateButton::new(format!(
+        "{}{}:{}:{}",
+        CONFIRM_PREFIX, event_id, match_id, winner
+    ))
+    .label("Confirm")
+    .style(ButtonStyle::Success)])
+}
+
+/// Reads the custom id of a confirm button: the event, the match and the seed of its winner
+pub fn parse_custom_id(custom_id: &str) -> Option<(i64, MatchId, u32)> {
+    let mut parts = custom_id.strip_prefix(CONFIRM_PREFIX)?.split(':');
+    let event_id = parts.next()?.parse().ok()?;
+    let match_id = parts.next()?.parse().ok()?;
+    let winner = parts.next()?.parse().ok()?;
+    match parts.next() {
+        None => Some((event_id, match_id, winner)),
+        Some(_) => None,
+    }
+}
+
+/// Confirms the result a player reported, when the other side of the match or a manager presses the button
+pub async fn confirm(
+    http: &Http,
+    db: &Repository,
+    interaction: &ComponentInteraction,
+) -> Result<(), Error> {
+    let (event_id, match_id, winner) = match parse_custom_id(&interaction.data.custom_id) {
+        Some(parsed) => parsed,
+        None => return Ok(()),
+    };
+
+    let refuse = |text: String| {
+        CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(text)
+                .ephemeral(true),
+        )
+    };
+
+    let (_, event) = match db.get_event_by_id(event_id).await {
+        Ok(found) => found,
+        Err(StoreError::NotFound) => {
+            interaction
+                .create_response(
+                    http,
+                    refuse(String::from("This event doesn't exist anymore")),
+                )
+                .await?;
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let bracket = match db.get_bracket(event_id).await {
+        Ok(bracket) => bracket,
+        Err(StoreError::NotFound) => {
+            interaction
+                .create_response(
+                    http,
+                    refuse(format!("{} has no bracket anymore", event.name)),
+                )
+                .await?;
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let reports = db.get_results(event_id).await?;
+    let report = match reports.iter().find(|report| {
+        report.match_id == match_id.to_string() && report.winner == winner && !report.confirmed
+    }) {
+        Some(report) => report,
+        None => {
+            interaction
+                .create_response(
+                    http,
+                    refuse(String::from(
+                        "This result was already confirmed, or replaced by another",
+                    )),
+                )
+                .await?;
+            return Ok(());
+        }
+    };
+    let states = play(
+        &structure(bracket.format, bracket.entrants.len()),
+        bracket.entrants.len(),
+        &reports,
+    );
+    let state = match states.iter().find(|state| state.id == match_id) {
+        Some(state) if state.is_playable() => state,
+        _ => {
+            interaction
+                .create_response(
+                    http,
+                    refuse(format!("`{}` can't be played anymore", match_id)),
+                )
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let user_id = u64::from(interaction.user.id);
+    let is_manager = interaction
+        .member
+        .as_ref()
+        .is_some_and(|m| m.roles.contains(&RoleId::new(event.manager_role_id)));
+    let side = side_of(&bracket, state, user_id);
+    if !is_manager && (side.is_none() || side == side_of(&bracket, state, report.reported_by)) {
+        interaction
+            .create_response(
+                http,
+                refuse(format!(
+                    "Only the other side of `{}` or the managers of {} can confirm it",
+                    match_id, event.name
+                )),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    db.confirm_result(event_id, match_id.to_string()).await?;
+    interaction
+        .create_response(
+            http,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(format!(
+                        "{} won `{}`, confirmed by <@{}>",
+                        entrant_name(&bracket, winner),
+                        match_id,
+                        user_id
+                    ))
+                    .allowed_mentions(CreateAllowedMentions::new())
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+    info!(
+        "{} confirmed that seed {} won {} of event {}(id {})",
+        user_id, winner, match_id, event.name, event_id
+    );
+
+    if let Err(e) = refresh(http, db, event_id, &event).await {
+        warn!(
+            "Couldn't update the bracket of event {}(id {}): {}",
+            event.name, event_id, e
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(match_id: &str, winner: u32, confirmed: bool) -> MatchReport {
+        MatchReport {
+            match_id: match_id.to_string(),
+            winner,
+            reported_by: 1,
+            confirmed,
+        }
+    }
+
+    fn state<'a>(states: &'a [MatchState], id: &str) -> &'a MatchState {
+        let id: MatchId = id.parse().unwrap();
+        states.iter().find(|state| state.id == id).unwrap()
+    }
+
+    #[test]
+    fn parses_match_ids() {
+        assert_eq!(
+            "w1-2".parse::<MatchId>(),
+            Ok(MatchId::new(Side::Winners, 1, 2))
+        );
+        assert_eq!(
+            " GF2 ".parse::<MatchId>(),
+            Ok(MatchId::new(Side::Final, 2, 1))
+        );
+        for id in ["L3-1", "W10-4", "GF", "GF2"] {
+            assert_eq!(id.parse::<MatchId>().unwrap().to_string(), id);
+        }
+        for id in ["", "X1-1", "W1", "W0-1", "L1-", "GF3"] {
+            assert!(id.parse::<MatchId>().is_err(), "{}", id);
+        }
+    }
+
+    #[test]
+    fn seeds_the_best_apart() {
+        assert_eq!(seed_order(2), vec![1, 2]);
+        assert_eq!(seed_order(8), vec![1, 8, 4, 5, 2, 7, 3, 6]);
+    }
+
+    #[test]
+    fn counts_matches() {
+        let count = |format, entrants| structure(format, entrants).len();
+        assert_eq!(count(BracketFormat::Single, 2), 1);
+        assert_eq!(count(BracketFormat::Single, 5), 7);
+        // The winners bracket, the losers bracket, and two grand finals
+        assert_eq!(count(BracketFormat::Double, 2), 1 + 2);
+        assert_eq!(count(BracketFormat::Double, 8), 7 + 6 + 2);
+        assert_eq!(count(BracketFormat::Double, 32), 31 + 30 + 2);
+    }
+
+    #[test]
+    fn plays_single_elimination_with_byes() {
+        let specs = structure(BracketFormat::Single, 3);
+        let states = play(&specs, 3, &[]);
+        assert_eq!(state(&states, "W1-1").winner, Some(Slot::Entrant(1)));
+        assert_eq!(
+            state(&states, "W2-1").slots,
+            [Slot::Entrant(1), Slot::Pending]
+        );
+
+        // Reports wait for confirmation
+        let states = play(&specs, 3, &[report("W1-2", 3, false)]);
+        assert_eq!(state(&states, "W1-2").winner, None);
+        assert_eq!(state(&states, "W1-2").reported, Some(3));
+
+        let states = play(
+            &specs,
+            3,
+            &[report("W1-2", 3, true), report("W2-1", 2, true)],
+        );
+        assert_eq!(
+            state(&states, "W2-1").slots,
+            [Slot::Entrant(1), Slot::Entrant(3)]
+        );
+        // 2 is out, its report doesn't count
+        assert_eq!(champion(&states), None);
+
+        let states = play(
+            &specs,
+            3,
+            &[report("W1-2", 3, true), report("W2-1", 3, true)],
+        );
+        assert_eq!(champion(&states), Some(3));
+    }
+
+    #[test]
+    fn plays_double_elimination() {
+        let specs = structure(BracketFormat::Double, 4);
+        let mut reports = vec![
+            report("W1-1", 1, true),
+            report("W1-2", 2, true),
+            report("L1-1", 3, true),
+            report("W2-1", 2, true),
+        ];
+        let states = play(&specs, 4, &reports);
+        assert_eq!(
+            state(&states, "L1-1").slots,
+            [Slot::Entrant(4), Slot::Entrant(3)]
+        );
+        assert_eq!(
+            state(&states, "L2-1").slots,
+            [Slot::Entrant(3), Slot::Entrant(1)]
+        );
+
+        reports.push(report("L2-1", 1, true));
+        let states = play(&specs, 4, &reports);
+        assert_eq!(
+            state(&states, "GF").slots,
+            [Slot::Entrant(2), Slot::Entrant(1)]
+        );
+
+        // The winners' champion wins it all at once
+        let mut won = reports.clone();
+        won.push(report("GF", 2, true));
+        assert_eq!(champion(&play(&specs, 4, &won)), Some(2));
+
+        // The losers' champion has to win twice
+        reports.push(report("GF", 1, true));
+        let states = play(&specs, 4, &reports);
+        assert_eq!(
+            state(&states, "GF2").slots,
+            [Slot::Entrant(1), Slot::Entrant(2)]
+        );
+        assert_eq!(champion(&states), None);
+        reports.push(report("GF2", 1, true));
+        assert_eq!(champion(&play(&specs, 4, &reports)), Some(1));
+    }
+
+    #[test]
+    fn lists_rounds_without_byes() {
+        let entrant = |seed: u32, name: &str| Entrant {
+            seed,
+            name: name.to_string(),
+            members: vec![seed as u64],
+        };
+        let bracket = Bracket {
+            format: BracketFormat::Single,
+            channel_id: 1,
+            message_id: 2,
+            entrants: vec![entrant(1, "alice"), entrant(2, "bob"), entrant(3, "carol")],
+        };
+        let states = play(
+            &structure(bracket.format, 3),
+            3,
+            &[report("W1-2", 3, false)],
+        );
+        assert_eq!(
+            fields(&bracket, &states),
+            vec![
+                (
+                    String::from("Round 1"),
+                    String::from("`W1-2` bob vs carol (reported)")
+                ),
+                (String::from("Final"), String::from("`W2-1` alice vs TBD")),
+            ]
+        );
+        assert_eq!(side_of(&bracket, state(&states, "W1-2"), 3), Some(3));
+        assert_eq!(side_of(&bracket, state(&states, "W1-2"), 1), None);
+    }
+}
@@ -0,0 +1,303 @@
+use crate::bracket::{entrant_name, MatchId, MatchSpec, MatchState, Side, Slot, Source};
+use crate::events::Bracket;
+use std::collections::HashMap;
+
+type Colour = [u8; 3];
+
+/// Discord's dark theme, so that the picture blends in
+const BACKGROUND: Colour = [0x31, 0x33, 0x38];
+const BOX: Colour = [0x1e, 0x1f, 0x22];
+const TEXT: Colour = [0xdb, 0xde, 0xe1];
+const DIM: Colour = [0x80, 0x84, 0x8e];
+const WON: Colour = [0x24, 0x80, 0x46];
+const REPORTED: Colour = [0xf0, 0xb2, 0x32];
+
+/// Pixels per dot of the font
+const SCALE: usize = 2;
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+const ADVANCE: usize = (GLYPH_WIDTH + 1) * SCALE;
+/// Characters of a name that fit in a box
+const NAME_LENGTH: usize = 16;
+const PADDING: usize = 6;
+const ROW_HEIGHT: usize = 16;
+const BOX_WIDTH: usize = NAME_LENGTH * ADVANCE + 2 * PADDING;
+const BOX_HEIGHT: usize = 2 * ROW_HEIGHT;
+const COLUMN_WIDTH: usize = BOX_WIDTH + 32;
+/// Height given to each match of the first round
+const SPACING: usize = BOX_HEIGHT + 12;
+const HEADER: usize = 24;
+const MARGIN: usize = 16;
+const LINE: usize = 2;
+
+/// Rows of a character in a 3x5 font, the leftmost dot is the highest bit.
+/// Letters are uppercase, unknown characters are question marks
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        ' ' => [0; 5],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        _ => [0b110, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+/// RGB pixels, drawn on from the top left corner
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Canvas {
+        Canvas {
+            width,
+            height,
+            pixels: BACKGROUND.repeat(width * height),
+        }
+    }
+
+    /// Fills a rectangle, clipped to the canvas
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, colour: Colour) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                let i = (row * self.width + column) * 3;
+                self.pixels[i..i + 3].copy_from_slice(&colour);
+            }
+        }
+    }
+
+    fn text(&mut self, x: usize, y: usize, text: &str, colour: Colour) {
+        for (i, c) in text.chars().enumerate() {
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                        self.fill(
+                            x + i * ADVANCE + column * SCALE,
+                            y + row * SCALE,
+                            SCALE,
+                            SCALE,
+                            colour,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Joins two points with a horizontal line, a vertical one just before [to], and a horizontal one again
+    fn elbow(&mut self, from: (usize, usize), to: (usize, usize)) {
+        let turn = to.0 - (COLUMN_WIDTH - BOX_WIDTH) / 2;
+        self.fill(from.0, from.1, turn - from.0 + LINE, LINE, DIM);
+        let (top, bottom) = (from.1.min(to.1), from.1.max(to.1));
+        self.fill(turn, top, LINE, bottom - top + LINE, DIM);
+        self.fill(turn, to.1, to.0 - turn, LINE, DIM);
+    }
+
+    fn encode(self) -> Result<Vec<u8>, png::EncodingError> {
+        let mut image = vec![];
+        let mut encoder = png::Encoder::new(&mut image, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+        Ok(image)
+    }
+}
+
+/// A column of matches of the same round
+struct Column {
+    label: String,
+    x: usize,
+    /// Top and height of the band the matches are spread over
+    band: (usize, usize),
+    matches: Vec<usize>,
+}
+
+/// Draws [bracket] as a PNG picture: the winners bracket on top, the losers bracket below it,
+/// and the grand finals on the right. [states] are the plays of [specs], in the same order
+pub fn render(
+    bracket: &Bracket,
+    specs: &[MatchSpec],
+    states: &[MatchState],
+) -> Result<Vec<u8>, png::EncodingError> {
+    let first_round = |side: Side| {
+        states
+            .iter()
+            .filter(|state| state.id.side == side && state.id.round == 1)
+            .count()
+    };
+    let last_round = |side: Side| {
+        states
+            .iter()
+            .filter(|state| state.id.side == side)
+            .map(|state| state.id.round as usize)
+            .max()
+            .unwrap_or(0)
+    };
+    // Losers rounds go by pairs of the same size
+    let winners_band = (MARGIN + HEADER, first_round(Side::Winners) * SPACING);
+    let losers_band = (
+        winners_band.0 + winners_band.1 + HEADER,
+        first_round(Side::Losers) * SPACING,
+    );
+    let final_column = last_round(Side::Winners).max(last_round(Side::Losers));
+
+    let mut columns: Vec<Column> = vec![];
+    for (i, state) in states.iter().enumerate() {
+        let label = match state.id.side {
+            Side::Winners => format!("W{}", state.id.round),
+            Side::Losers => format!("L{}", state.id.round),
+            Side::Final => state.id.to_string(),
+        };
+        match columns.last_mut() {
+            Some(column) if column.label == label => column.matches.push(i),
+            _ => {
+                let (index, band) = match state.id.side {
+                    Side::Winners => (state.id.round as usize - 1, winners_band),
+                    Side::Losers => (state.id.round as usize - 1, losers_band),
+                    Side::Final => (final_column + state.id.round as usize - 1, winners_band),
+                };
+                columns.push(Column {
+                    label,
+                    x: MARGIN + index * COLUMN_WIDTH,
+                    band,
+                    matches: vec![i],
+                });
+            }
+        }
+    }
+
+    let mut boxes: HashMap<MatchId, (usize, usize)> = HashMap::new();
+    for column in &columns {
+        let (top, height) = column.band;
+        let count = column.matches.len();
+        for (i, state) in column.matches.iter().enumerate() {
+            let centre = top + (2 * i + 1) * height / (2 * count);
+            boxes.insert(states[*state].id, (column.x, centre - BOX_HEIGHT / 2));
+        }
+    }
+
+    let width = columns.iter().map(|column| column.x).max().unwrap_or(0) + BOX_WIDTH + MARGIN;
+    let height = if losers_band.1 > 0 {
+        losers_band.0 + losers_band.1
+    } else {
+        winners_band.0 + winners_band.1
+    } + MARGIN;
+    let mut canvas = Canvas::new(width, height);
+
+    // Lines go below the boxes
+    for spec in specs {
+        let to = boxes[&spec.id];
+        for (row, source) in spec.sources.iter().enumerate() {
+            if let Source::Winner(from) = source {
+                let from = boxes[from];
+                canvas.elbow(
+                    (from.0 + BOX_WIDTH, from.1 + BOX_HEIGHT / 2),
+                    (to.0, to.1 + row * ROW_HEIGHT + ROW_HEIGHT / 2),
+                );
+            }
+        }
+    }
+
+    for column in &columns {
+        canvas.text(column.x, column.band.0 - HEADER + 6, &column.label, DIM);
+    }
+    for state in states {
+        let (x, y) = boxes[&state.id];
+        canvas.fill(x, y, BOX_WIDTH, BOX_HEIGHT, BOX);
+        for (row, slot) in state.slots.iter().enumerate() {
+            let top = y + row * ROW_HEIGHT;
+            if state.winner == Some(*slot) && matches!(slot, Slot::Entrant(_)) {
+                canvas.fill(x, top, BOX_WIDTH, ROW_HEIGHT, WON);
+            }
+            let (name, colour) = match slot {
+                Slot::Entrant(seed) if state.reported == Some(*seed) => {
+                    (entrant_name(bracket, *seed), REPORTED)
+                }
+                Slot::Entrant(seed) => (entrant_name(bracket, *seed), TEXT),
+                Slot::Bye => ("bye", DIM),
+                Slot::Pending => ("", DIM),
+            };
+            let name: String = name.chars().take(NAME_LENGTH).collect();
+            canvas.text(
+                x + PADDING,
+                top + (ROW_HEIGHT - GLYPH_HEIGHT * SCALE) / 2,
+                &name,
+                colour,
+            );
+        }
+        canvas.fill(x, y + ROW_HEIGHT, BOX_WIDTH, 1, BACKGROUND);
+    }
+
+    canvas.encode()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bracket::{play, structure};
+    use crate::events::{BracketFormat, Entrant};
+
+    #[test]
+    fn draws_every_size() {
+        for format in [BracketFormat::Single, BracketFormat::Double] {
+            for entrants in 2..=crate::bracket::MAX_ENTRANTS {
+                let bracket = Bracket {
+                    format,
+                    channel_id: 1,
+                    message_id: 2,
+                    entrants: (1..=entrants as u32)
+                        .map(|seed| Entrant {
+                            seed,
+                            name: format!("Entrant {}", seed),
+                            members: vec![],
+                        })
+                        .collect(),
+                };
+                let specs = structure(format, entrants);
+                let image = render(&bracket, &specs, &play(&specs, entrants, &[])).unwrap();
+                assert!(image.starts_with(b"\x89PNG"), "{:?} {}", format, entrants);
+            }
+        }
+    }
+}
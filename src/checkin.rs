@@ -0,0 +1,417 @@
+use crate::bacchus::Error;
+use crate::events::{CheckIn, EventData, EventStatus, Outcome};
+use crate::reliability;
+use crate::repository::Repository;
+use crate::scheduler::{format_time, now};
+use crate::store::StoreError;
+use chrono::DateTime;
+use poise::serenity_prelude::{
+    ActionRowComponent, ButtonStyle, ChannelId, ComponentInteraction, CreateActionRow,
+    CreateButton, CreateEmbed, CreateInputText, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateModal, EditMessage, Http, InputTextStyle, MessageId,
+    ModalInteraction, UserId,
+};
+use rand::Rng;
+use tracing::{info, warn};
+
+/// Custom id of a check-in button, followed by the event's id
+pub const CHECKIN_PREFIX: &str = "event_checkin:";
+/// Custom id of the form asking for the code, followed by the event's id
+pub const CODE_PREFIX: &str = "event_code:";
+/// Without the characters people mix up, like 0 and O
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 4;
+/// Characters of each list in the attendance summary, a message holds 2000
+const MAX_LIST_LENGTH: usize = 900;
+
+/// A fresh code to show at the venue
+pub fn generate_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..CODE_LENGTH)
+        .map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char)
+        .collect()
+}
+
+/// Whether [given] is the code, whatever its case and surrounding spaces
+pub fn code_matches(code: &str, given: &str) -> bool {
+    given.trim().eq_ignore_ascii_case(code)
+}
+
+/// Who checked in, earliest first, and the participants who didn't
+pub fn split(participants: &[u64], attendance: &[(u64, i64)]) -> (Vec<(u64, i64)>, Vec<u64>) {
+    let missing = participants
+        .iter()
+        .filter(|participant| !attendance.iter().any(|(user, _)| user == *participant))
+        .copied()
+        .collect();
+    (attendance.to_vec(), missing)
+}
+
+/// Mentions of [users], with a count of those that don't fit
+fn mention_list(users: &[u64]) -> String {
+    if users.is_empty() {
+        return String::from("nobody");
+    }
+
+    let mut list = String::new();
+    for (i, user) in users.iter().enumerate() {
+        let mention = format!("<@{}>", user);
+        if list.len() + mention.len() + 2 > MAX_LIST_LENGTH {
+            return format!("{} and {} more", list, users.len() - i);
+        }
+        if !list.is_empty() {
+            list += ", ";
+        }
+        list += &mention;
+    }
+    list
+}
+
+/// Who came and who didn't, for /event attendance
+pub fn describe(came: &[(u64, i64)], missing: &[u64]) -> String {
+    let came: Vec<u64> = came.iter().map(|(user, _)| *user).collect();
+    format!(
+        "**Came ({})**: {}\n**Didn't come ({})**: {}",
+        came.len(),
+        mention_list(&came),
+        missing.len(),
+        mention_list(missing)
+    )
+}
+
+/// Quotes a CSV field when it needs to. Names are chosen by users, those a spreadsheet
+/// would run as a formula are prefixed with ' so they show as text
+fn csv_field(value: &str) -> String {
+    if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("\"'{}\"", value.replace('"', "\"\""))
+    } else if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The attendance of an event as CSV, those who came first. [names] are the users' display names
+pub fn to_csv(came: &[(u64, i64)], missing: &[u64], names: &[(u64, String)]) -> String {
+    let name = |user: u64| {
+        names
+            .iter()
+            .find(|(id, _)| *id == user)
+            .map_or("", |(_, name)| name.as_str())
+    };
+
+    let mut csv = String::from("user_id,name,attended,checked_in_at\n");
+    for (user, at) in came {
+        let at = DateTime::from_timestamp(*at, 0)
+            .map(|at| at.to_rfc3339())
+            .unwrap_or_default();
+        csv += &format!("{},{},yes,{}\n", user, csv_field(name(*user)), at);
+    }
+    for user in missing {
+        csv += &format!("{},{},no,\n", user, csv_field(name(*user)));
+    }
+    csv
+}
+
+/// The message participants check in with
+pub fn checkin_embed(event: &EventData, checkin: &CheckIn, open: bool) -> CreateEmbed {
+    let description = if open {
+        format!(
+            "Press **Check in** once you're there{}.\nCloses {}",
+            if checkin.code.is_some() {
+                ", with the code shown at the venue"
+            } else {
+                ""
+            },
+            format_time(checkin.closes_at)
+        )
+    } else {
+        String::from("Check-in is closed")
+    };
+    CreateEmbed::new()
+        .title(format!("Check-in for {}", event.name))
+        .description(description)
+}
+
+/// The check-in button, while the window is open
+pub fn components(event_id: i64, open: bool) -> Vec<CreateActionRow> {
+    if !open {
+        return vec![];
+    }
+    vec![CreateActionRow::Buttons(vec![CreateButton::new(format!(
+        "{}{}",
+        CHECKIN_PREFIX, event_id
+    ))
+    .label("Check in")
+    .style(ButtonStyle::Success)])]
+}
+
+/// Closes the check-in window of an event, and takes the button off its message.
+/// Returns how many windows were open
+pub async fn close(
+    http: &Http,
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+) -> Result<usize, Error> {
+    let checkin = match db.get_checkin(event_id).await {
+        Ok(checkin) => checkin,
+        Err(StoreError::NotFound) => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    // People may have deleted the message by hand
+    if let Err(e) = ChannelId::new(checkin.channel_id)
+        .edit_message(
+            http,
+            MessageId::new(checkin.message_id),
+            EditMessage::new()
+                .embed(checkin_embed(event, &checkin, false))
+                .components(vec![]),
+        )
+        .await
+    {
+        warn!(
+            "Couldn't close the check-in message of event {}(id {}): {}",
+            event.name, event_id, e
+        );
+    }
+    Ok(db.close_checkin(event_id).await?)
+}
+
+/// Whether [user_id] may check in to the event now. The reason is meant for them
+async fn check_window(
+    db: &Repository,
+    event_id: i64,
+    user_id: UserId,
+) -> Result<Result<(EventData, CheckIn), String>, Error> {
+    let (_, event) = match db.get_event_by_id(event_id).await {
+        Ok(found) => found,
+        Err(StoreError::NotFound) => {
+            return Ok(Err(String::from("This event doesn't exist anymore")))
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let checkin = match db.get_checkin(event_id).await {
+        Ok(checkin) => checkin,
+        Err(StoreError::NotFound) => {
+            return Ok(Err(format!("Check-in for {} is closed", event.name)))
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if event.status != EventStatus::Published {
+        return Ok(Err(format!(
+            "{} is {}, check-in is closed",
+            event.name,
+            event.status.as_str()
+        )));
+    }
+    if now() >= checkin.closes_at {
+        return Ok(Err(format!(
+            "Check-in for {} closed {}",
+            event.name,
+            format_time(checkin.closes_at)
+        )));
+    }
+    if !db
+        .get_participants(event_id)
+        .await?
+        .contains(&u64::from(user_id))
+    {
+        return Ok(Err(format!(
+            "Only the participants of {} can check in",
+            event.name
+        )));
+    }
+
+    Ok(Ok((event, checkin)))
+}
+
+/// Records that [user_id] came, in the event's attendance and their history, and tells them
+async fn record(
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+    user_id: UserId,
+) -> Result<CreateInteractionResponse, Error> {
+    let text = if db.check_in(event_id, u64::from(user_id), now()).await? {
+        info!(
+            "{} checked in to event {}(id {})",
+            user_id, event.name, event_id
+        );
+        reliability::record(db, event_id, event, u64::from(user_id), Outcome::Attended).await?;
+        format!("You're checked in to {}, enjoy !", event.name)
+    } else {
+        format!("You already checked in to {}", event.name)
+    };
+    Ok(CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .content(text)
+            .ephemeral(true),
+    ))
+}
+
+fn refuse(text: String) -> CreateInteractionResponse {
+    CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .content(text)
+            .ephemeral(true),
+    )
+}
+
+/// Checks in whoever pressed the button, or asks them for the code first
+pub async fn press(
+    http: &Http,
+    db: &Repository,
+    interaction: &ComponentInteraction,
+) -> Result<(), Error> {
+    let event_id = match interaction
+        .data
+        .custom_id
+        .strip_prefix(CHECKIN_PREFIX)
+        .and_then(|id| id.parse::<i64>().ok())
+    {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+
+    let user_id = interaction.user.id;
+    let (event, checkin) = match check_window(db, event_id, user_id).await? {
+        Ok(open) => open,
+        Err(reason) => {
+            interaction.create_response(http, refuse(reason)).await?;
+            return Ok(());
+        }
+    };
+
+    let response = if checkin.code.is_some() {
+        CreateInteractionResponse::Modal(
+            CreateModal::new(
+                format!("{}{}", CODE_PREFIX, event_id),
+                format!("Check in to {}", event.name)
+                    .chars()
+                    .take(45)
+                    .collect::<String>(),
+            )
+            .components(vec![CreateActionRow::InputText(
+                CreateInputText::new(InputTextStyle::Short, "Code shown at the venue", "code")
+                    .min_length(1)
+                    .max_length(20),
+            )]),
+        )
+    } else {
+        record(db, event_id, &event, user_id).await?
+    };
+    interaction.create_response(http, response).await?;
+
+    Ok(())
+}
+
+/// Checks in whoever typed in the right code
+pub async fn submit(
+    http: &Http,
+    db: &Repository,
+    interaction: &ModalInteraction,
+) -> Result<(), Error> {
+    let event_id = match interaction
+        .data
+        .custom_id
+        .strip_prefix(CODE_PREFIX)
+        .and_then(|id| id.parse::<i64>().ok())
+    {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    let given = interaction
+        .data
+        .components
+        .iter()
+        .flat_map(|row| row.components.iter())
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(input) => input.value.clone(),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let user_id = interaction.user.id;
+    let (event, checkin) = match check_window(db, event_id, user_id).await? {
+        Ok(open) => open,
+        Err(reason) => {
+            interaction.create_response(http, refuse(reason)).await?;
+            return Ok(());
+        }
+    };
+    // Codes can be dropped by reopening the window while the form is up
+    if let Some(code) = &checkin.code {
+        if !code_matches(code, &given) {
+            interaction
+                .create_response(
+                    http,
+                    refuse(String::from(
+                        "That's not the code, look for it at the venue",
+                    )),
+                )
+                .await?;
+            return Ok(());
+        }
+    }
+
+    let response = record(db, event_id, &event, user_id).await?;
+    interaction.create_response(http, response).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_readable_codes() {
+        for _ in 0..100 {
+            let code = generate_code();
+            assert_eq!(code.len(), CODE_LENGTH);
+            assert!(code.bytes().all(|c| CODE_ALPHABET.contains(&c)), "{}", code);
+        }
+        assert!(code_matches("K7QX", " k7qx "));
+        assert!(!code_matches("K7QX", "K7Q"));
+    }
+
+    #[test]
+    fn lists_who_came() {
+        let (came, missing) = split(&[1, 2, 3], &[(3, 20), (4, 10)]);
+        assert_eq!(came, vec![(3, 20), (4, 10)]);
+        assert_eq!(missing, vec![1, 2]);
+        assert_eq!(
+            describe(&came, &missing),
+            "**Came (2)**: <@3>, <@4>\n**Didn't come (2)**: <@1>, <@2>"
+        );
+        assert_eq!(
+            describe(&[], &[]),
+            "**Came (0)**: nobody\n**Didn't come (0)**: nobody"
+        );
+
+        let crowd: Vec<u64> = (0..100)
+            .map(|user| 100_000_000_000_000_000 + user)
+            .collect();
+        let list = mention_list(&crowd);
+        assert!(list.len() <= MAX_LIST_LENGTH + 20, "{}", list.len());
+        assert!(list.ends_with(" more"));
+    }
+
+    #[test]
+    fn exports_csv() {
+        let names = [
+            (3, String::from("Smith, \"Jo\"")),
+            (1, String::from("ann")),
+            (4, String::from("=HYPERLINK(\"https://evil.example\",\"x\")")),
+        ];
+        assert_eq!(
+            to_csv(&[(3, 0)], &[1, 2, 4], &names),
+            "user_id,name,attended,checked_in_at\n\
+             3,\"Smith, \"\"Jo\"\"\",yes,1970-01-01T00:00:00+00:00\n\
+             1,ann,no,\n\
+             2,,no,\n\
+             4,\"'=HYPERLINK(\"\"https://evil.example\"\",\"\"x\"\")\",no,\n"
+        );
+    }
+}
@@ -0,0 +1,419 @@
+use crate::bacchus::Error;
+use crate::events::{EventData, EventStatus, TeamSlot};
+use crate::manifest;
+use crate::repository::Repository;
+use poise::serenity_prelude::{
+    ChannelId, ComponentInteraction, ComponentInteractionDataKind, CreateActionRow,
+    CreateInteractionResponse, CreateInteractionResponseFollowup, CreateInteractionResponseMessage,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, EditMessage, GuildId, Http,
+    MessageId, RoleId, UserId,
+};
+use tracing::{info, warn};
+
+/// Custom id of the slot menu of a manifest, followed by the event's id
+pub const SLOT_PREFIX: &str = "event_slot:";
+/// Value of a menu option taking a slot, followed by the slot's name
+const TAKE: &str = "take:";
+/// Value of the menu option leaving one's slot
+const LEAVE: &str = "leave";
+/// Options a Discord select menu can hold, minus the one to leave
+pub const MAX_SLOTS: usize = 24;
+/// Menu option values are 100 characters at most, [TAKE] included
+pub const MAX_NAME_LENGTH: usize = 80;
+/// Characters an embed field can hold
+const MAX_FIELD_LENGTH: usize = 1024;
+
+/// The people in the slot [name], in the order they took it
+pub fn members_of(name: &str, members: &[(String, u64)]) -> Vec<u64> {
+    members
+        .iter()
+        .filter(|(slot, _)| slot == name)
+        .map(|(_, user)| *user)
+        .collect()
+}
+
+/// Whether somebody else can take [slot]
+pub fn has_room(slot: &TeamSlot, members: &[(String, u64)]) -> bool {
+    members_of(&slot.name, members).len() < slot.capacity as usize
+}
+
+/// One line per slot with its members, None without slots.
+/// Only the counts are shown when the members don't fit in an embed field
+pub fn composition(slots: &[TeamSlot], members: &[(String, u64)]) -> Option<String> {
+    if slots.is_empty() {
+        return None;
+    }
+
+    let count = |slot: &TeamSlot| {
+        format!(
+            "**{}** {}/{}",
+            slot.name,
+            members_of(&slot.name, members).len(),
+            slot.capacity
+        )
+    };
+    let full = slots
+        .iter()
+        .map(|slot| {
+            let people = members_of(&slot.name, members);
+            if people.is_empty() {
+                count(slot)
+            } else {
+                format!(
+                    "{}: {}",
+                    count(slot),
+                    people
+                        .iter()
+                        .map(|user| format!("<@{}>", user))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    if full.chars().count() <= MAX_FIELD_LENGTH {
+        Some(full)
+    } else {
+        Some(slots.iter().map(count).collect::<Vec<String>>().join("\n"))
+    }
+}
+
+/// The menu participants pick their slot with
+pub fn slot_menu(event_id: i64, slots: &[TeamSlot], members: &[(String, u64)]) -> CreateActionRow {
+    let mut options: Vec<CreateSelectMenuOption> = slots
+        .iter()
+        .map(|slot| {
+            let option = CreateSelectMenuOption::new(
+                format!(
+                    "{} ({}/{})",
+                    slot.name,
+                    members_of(&slot.name, members).len(),
+                    slot.capacity
+                ),
+                format!("{}{}", TAKE, slot.name),
+            );
+            if has_room(slot, members) {
+                option
+            } else {
+                option.description("Full")
+            }
+        })
+        .collect();
+    options.push(CreateSelectMenuOption::new("Leave my slot", LEAVE));
+
+    CreateActionRow::SelectMenu(
+        CreateSelectMenu::new(
+            format!("{}{}", SLOT_PREFIX, event_id),
+            CreateSelectMenuKind::String { options },
+        )
+        .placeholder("Pick your slot"),
+    )
+}
+
+/// The components of a manifest: the slot menu, while the event can be joined
+pub fn components(
+    event_id: i64,
+    event: &EventData,
+    slots: &[TeamSlot],
+    members: &[(String, u64)],
+) -> Vec<CreateActionRow> {
+    if slots.is_empty() || event.status != EventStatus::Published || event.archived_at.is_some() {
+        vec![]
+    } else {
+        vec![slot_menu(event_id, slots, members)]
+    }
+}
+
+/// Shows the current composition of an event on its manifest
+pub async fn update_manifest(
+    http: &Http,
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+) -> Result<(), Error> {
+    let slots = db.get_slots(event_id).await?;
+    let members = db.get_slot_members(event_id).await?;
+
+    let channel_id = ChannelId::new(event.manifest_channel_id);
+    let manifest_id = MessageId::new(event.manifest_id);
+    let message = channel_id.message(http, manifest_id).await?;
+    let embeds = message
+        .embeds
+        .into_iter()
+        .map(|embed| manifest::recompose(embed, composition(&slots, &members)))
+        .collect();
+    channel_id
+        .edit_message(
+            http,
+            manifest_id,
+            EditMessage::new()
+                .embeds(embeds)
+                .components(components(event_id, event, &slots, &members)),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Gives [user_id] the role of the slot they took, and takes back the role of the one they left.
+/// Slot roles can be deleted by hand, failing to swap them doesn't undo the pick
+async fn swap_roles(
+    http: &Http,
+    guild_id: GuildId,
+    user_id: UserId,
+    left: Option<&TeamSlot>,
+    taken: Option<&TeamSlot>,
+) {
+    if let Some(role_id) = left.and_then(|slot| slot.role_id) {
+        if let Err(e) = http
+            .remove_member_role(guild_id, user_id, RoleId::new(role_id), None)
+            .await
+        {
+            warn!(
+                "Couldn't take slot role {} from {}: {}",
+                role_id, user_id, e
+            );
+        }
+    }
+    if let Some(role_id) = taken.and_then(|slot| slot.role_id) {
+        if let Err(e) = http
+            .add_member_role(guild_id, user_id, RoleId::new(role_id), None)
+            .await
+        {
+            warn!("Couldn't give slot role {} to {}: {}", role_id, user_id, e);
+        }
+    }
+}
+
+/// Takes [user_id] out of their slot, for participants leaving the event
+pub async fn release(
+    http: &Http,
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+    user_id: UserId,
+) -> Result<(), Error> {
+    let members = db.get_slot_members(event_id).await?;
+    let name = match members.iter().find(|(_, user)| *user == u64::from(user_id)) {
+        Some((name, _)) => name,
+        None => return Ok(()),
+    };
+    let slots = db.get_slots(event_id).await?;
+    db.leave_slot(event_id, u64::from(user_id)).await?;
+
+    let left = slots.iter().find(|slot| &slot.name == name);
+    swap_roles(http, GuildId::new(event.server_id), user_id, left, None).await;
+    if let Err(e) = update_manifest(http, db, event_id, event).await {
+        warn!(
+            "Couldn't update the composition of event {}(id {}): {}",
+            event.name, event_id, e
+        );
+    }
+
+    Ok(())
+}
+
+/// Takes or leaves the slot picked in a manifest's menu, and updates the composition
+pub async fn pick(
+    http: &Http,
+    db: &Repository,
+    interaction: &ComponentInteraction,
+) -> Result<(), Error> {
+    let event_id = match interaction
+        .data
+        .custom_id
+        .strip_prefix(SLOT_PREFIX)
+        .and_then(|id| id.parse::<i64>().ok())
+    {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    let value = match &interaction.data.kind {
+        ComponentInteractionDataKind::StringSelect { values } if !values.is_empty() => {
+            values[0].clone()
+        }
+        _ => return Ok(()),
+    };
+
+    let refuse = |text: String| {
+        CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(text)
+                .ephemeral(true),
+        )
+    };
+
+    let (_, event) = match db.get_event_by_id(event_id).await {
+        Ok(found) => found,
+        Err(_) => {
+            interaction
+                .create_response(
+                    http,
+                    refuse(String::from("This event doesn't exist anymore")),
+                )
+                .await?;
+            return Ok(());
+        }
+    };
+    if event.status != EventStatus::Published || event.archived_at.is_some() {
+        interaction
+            .create_response(
+                http,
+                refuse(format!("{} can't be joined anymore", event.name)),
+            )
+            .await?;
+        return Ok(());
+    }
+    let user_id = interaction.user.id;
+    if !db
+        .get_participants(event_id)
+        .await?
+        .contains(&u64::from(user_id))
+    {
+        interaction
+            .create_response(
+                http,
+                refuse(format!(
+                    "React with {} to join {} first",
+                    event.rsvp_emoji, event.name
+                )),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    let slots = db.get_slots(event_id).await?;
+    let members = db.get_slot_members(event_id).await?;
+    let current = members
+        .iter()
+        .find(|(_, user)| *user == u64::from(user_id))
+        .and_then(|(name, _)| slots.iter().find(|slot| &slot.name == name));
+    let guild_id = GuildId::new(event.server_id);
+
+    let answer = match value.strip_prefix(TAKE) {
+        Some(name) => {
+            let slot = match slots.iter().find(|slot| slot.name == name) {
+                Some(slot) => slot,
+                None => {
+                    interaction
+                        .create_response(
+                            http,
+                            refuse(format!(
+                                "{} doesn't have a {} slot anymore",
+                                event.name, name
+                            )),
+                        )
+                        .await?;
+                    return Ok(());
+                }
+            };
+            if current == Some(slot) {
+                interaction
+                    .create_response(http, refuse(format!("You already are in {}", name)))
+                    .await?;
+                return Ok(());
+            }
+            // Checked by the store, somebody may have taken the last place since the menu was shown
+            if !db
+                .take_slot(event_id, u64::from(user_id), slot.name.clone())
+                .await?
+            {
+                interaction
+                    .create_response(http, refuse(format!("{} is full", name)))
+                    .await?;
+                return Ok(());
+            }
+            swap_roles(http, guild_id, user_id, current, Some(slot)).await;
+            format!("You took a {} slot", slot.name)
+        }
+        None => {
+            let left = match current {
+                Some(slot) => slot,
+                None => {
+                    interaction
+                        .create_response(http, refuse(String::from("You don't have a slot")))
+                        .await?;
+                    return Ok(());
+                }
+            };
+
+            db.leave_slot(event_id, u64::from(user_id)).await?;
+            swap_roles(http, guild_id, user_id, Some(left), None).await;
+            format!("You left your {} slot", left.name)
+        }
+    };
+
+    let members = db.get_slot_members(event_id).await?;
+    let embeds = interaction
+        .message
+        .embeds
+        .iter()
+        .map(|embed| manifest::recompose(embed.clone(), composition(&slots, &members)))
+        .collect();
+    interaction
+        .create_response(
+            http,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embeds(embeds)
+                    .components(components(event_id, &event, &slots, &members)),
+            ),
+        )
+        .await?;
+    interaction
+        .create_followup(
+            http,
+            CreateInteractionResponseFollowup::new()
+                .content(answer)
+                .ephemeral(true),
+        )
+        .await?;
+
+    info!(
+        "{} picked {} in event {}(id {})",
+        user_id, value, event.name, event_id
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot(name: &str, capacity: u32) -> TeamSlot {
+        TeamSlot {
+            name: name.to_string(),
+            capacity,
+            role_id: None,
+        }
+    }
+
+    #[test]
+    fn renders_the_composition() {
+        let slots = [slot("Tank", 2), slot("Healer", 2), slot("DPS", 6)];
+        let members = [
+            (String::from("Tank"), 1),
+            (String::from("DPS"), 2),
+            (String::from("Tank"), 3),
+        ];
+        assert!(!has_room(&slots[0], &members));
+        assert!(has_room(&slots[1], &members));
+        assert_eq!(
+            composition(&slots, &members).unwrap(),
+            "**Tank** 2/2: <@1>, <@3>\n**Healer** 0/2\n**DPS** 1/6: <@2>"
+        );
+        assert_eq!(composition(&[], &members), None);
+    }
+
+    #[test]
+    fn falls_back_to_counts() {
+        let slots = [slot("Crowd", 100)];
+        let members: Vec<(String, u64)> = (0..100)
+            .map(|user| (String::from("Crowd"), 100_000_000_000_000_000 + user))
+            .collect();
+        assert_eq!(composition(&slots, &members).unwrap(), "**Crowd** 100/100");
+    }
+}
@@ -0,0 +1,278 @@
+use crate::layout::{parse_layout, ChannelSpec};
+use clap::Parser;
+use poise::serenity_prelude::{GuildId, ReactionType};
+use serde::Deserialize;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use tracing::Level;
+
+/// Read when no --config is given, if it exists
+const DEFAULT_CONFIG_FILE: &str = "bacchus.toml";
+
+/// Command line flags. Each one can also be set through its environment variable,
+/// and falls back to the configuration file, then to the defaults
+#[derive(Parser, Debug, Default)]
+#[command(version, about = "A discord bot to manage events on community servers")]
+pub struct Cli {
+    /// TOML configuration file [default: bacchus.toml, if it exists]
+    #[arg(long, env = "BACCHUS_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Discord bot token
+    #[arg(long, env = "DISCORD_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+
+    /// Where events are stored: sqlite://<path>, postgres://... or memory:// [default: sqlite://database.sqlite]
+    #[arg(long, env = "DATABASE_URL")]
+    pub database_url: Option<String>,
+
+    /// error, warn, info, debug or trace [default: info]
+    #[arg(long, env = "BACCHUS_LOG_LEVEL")]
+    pub log_level: Option<String>,
+
+    /// Port of the health check endpoint. Disabled when unset
+    #[arg(long, env = "BACCHUS_HTTP_PORT")]
+    pub http_port: Option<u16>,
+
+    /// Emoji people react with to join an event [default: ✅]
+    #[arg(long, env = "BACCHUS_RSVP_EMOJI")]
+    pub rsvp_emoji: Option<String>,
+
+    /// Channels created for new events, e.g. "general, lobby:voice" [default: general]
+    #[arg(long, env = "BACCHUS_CHANNEL_LAYOUT")]
+    pub channel_layout: Option<String>,
+
+    /// Register the commands on this server only, they show up instantly there
+    #[arg(long, env = "BACCHUS_DEV_GUILD")]
+    pub dev_guild: Option<u64>,
+}
+
+/// The configuration file, same settings as [Cli]
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    pub token: Option<String>,
+    pub database_url: Option<String>,
+    pub log_level: Option<String>,
+    pub http_port: Option<u16>,
+    pub rsvp_emoji: Option<String>,
+    pub channel_layout: Option<String>,
+    pub dev_guild: Option<u64>,
+}
+
+/// The validated configuration the bot runs with
+#[derive(Debug)]
+pub struct Config {
+    pub token: String,
+    pub database_url: String,
+    pub log_level: Level,
+    pub http_port: Option<u16>,
+    pub rsvp_emoji: String,
+    pub channel_layout: Vec<ChannelSpec>,
+    pub dev_guild: Option<GuildId>,
+}
+
+#[derive(Debug)]
+pub struct ConfigError(String);
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Reads the command line, the environment and the configuration file.
+    /// Exits on --help and --version
+    pub fn load() -> Result<Config, ConfigError> {
+        let cli = Cli::parse();
+
+        let file = match &cli.config {
+            Some(path) => read_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                read_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => FileConfig::default(),
+        };
+
+        Config::resolve(cli, file)
+    }
+
+    /// Merges both layers, flags and environment first, and validates the result
+    pub fn resolve(cli: Cli, file: FileConfig) -> Result<Config, ConfigError> {
+        let token = cli
+            .token
+            .or(file.token)
+            .ok_or_else(|| {
+                ConfigError(String::from(
+                    "missing Discord token: set DISCORD_TOKEN, --token or `token` in the configuration file",
+                ))
+            })?
+            .trim()
+            .to_string();
+        if token.is_empty() || token.contains(char::is_whitespace) {
+            return Err(ConfigError(String::from(
+                "the Discord token must be a single non-empty word",
+            )));
+        }
+
+        let database_url = cli
+            .database_url
+            .or(file.database_url)
+            .unwrap_or_else(|| String::from("sqlite://database.sqlite"));
+        if !["sqlite://", "postgres://", "postgresql://", "memory://"]
+            .iter()
+            .any(|scheme| database_url.starts_with(scheme))
+        {
+            return Err(ConfigError(format!(
+                "invalid database URL `{}`: expected sqlite://<path>, postgres://... or memory://",
+                database_url
+            )));
+        }
+
+        let log_level = cli
+            .log_level
+            .or(file.log_level)
+            .unwrap_or_else(|| String::from("info"));
+        let log_level = log_level.parse::<Level>().map_err(|_| {
+            ConfigError(format!(
+                "invalid log level `{}`: expected error, warn, info, debug or trace",
+                log_level
+            ))
+        })?;
+
+        let http_port = cli.http_port.or(file.http_port);
+        if http_port == Some(0) {
+            return Err(ConfigError(String::from("the HTTP port can't be 0")));
+        }
+
+        let rsvp_emoji = cli
+            .rsvp_emoji
+            .or(file.rsvp_emoji)
+            .unwrap_or_else(|| String::from("✅"))
+            .trim()
+            .to_string();
+        if rsvp_emoji.is_empty() || ReactionType::try_from(rsvp_emoji.as_str()).is_err() {
+            return Err(ConfigError(format!(
+                "invalid RSVP emoji `{}`: expected a unicode emoji or a custom one like <:name:id>",
+                rsvp_emoji
+            )));
+        }
+
+        let channel_layout = cli
+            .channel_layout
+            .or(file.channel_layout)
+            .unwrap_or_else(|| String::from("general"));
+        let channel_layout = parse_layout(&channel_layout)
+            .map_err(|e| ConfigError(format!("invalid channel layout: {}", e)))?;
+
+        let dev_guild = match cli.dev_guild.or(file.dev_guild) {
+            Some(0) => return Err(ConfigError(String::from("the dev guild id can't be 0"))),
+            Some(id) => Some(GuildId::new(id)),
+            None => None,
+        };
+
+        Ok(Config {
+            token,
+            database_url,
+            log_level,
+            http_port,
+            rsvp_emoji,
+            channel_layout,
+            dev_guild,
+        })
+    }
+}
+
+fn read_file(path: &Path) -> Result<FileConfig, ConfigError> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        ConfigError(format!(
+            "couldn't read configuration file {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    toml::from_str(&content).map_err(|e| {
+        ConfigError(format!(
+            "invalid configuration file {}: {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::ChannelKind;
+
+    fn cli_with_token() -> Cli {
+        Cli {
+            token: Some(String::from("cli-token")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn defaults() {
+        let config = Config::resolve(cli_with_token(), FileConfig::default()).unwrap();
+        assert_eq!(config.database_url, "sqlite://database.sqlite");
+        assert_eq!(config.log_level, Level::INFO);
+        assert_eq!(config.http_port, None);
+        assert_eq!(config.rsvp_emoji, "✅");
+        assert_eq!(config.channel_layout.len(), 1);
+        assert_eq!(config.channel_layout[0].kind, ChannelKind::Text);
+        assert_eq!(config.dev_guild, None);
+    }
+
+    #[test]
+    fn flags_override_the_file() {
+        let file: FileConfig = toml::from_str(
+            r#"
+            token = "file-token"
+            database_url = "memory://"
+            log_level = "debug"
+            http_port = 8080
+            "#,
+        )
+        .unwrap();
+        let cli = Cli {
+            log_level: Some(String::from("warn")),
+            ..cli_with_token()
+        };
+
+        let config = Config::resolve(cli, file).unwrap();
+        assert_eq!(config.token, "cli-token");
+        assert_eq!(config.database_url, "memory://");
+        assert_eq!(config.log_level, Level::WARN);
+        assert_eq!(config.http_port, Some(8080));
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert!(Config::resolve(Cli::default(), FileConfig::default()).is_err());
+        for cli in [
+            Cli {
+                database_url: Some(String::from("mysql://localhost")),
+                ..cli_with_token()
+            },
+            Cli {
+                log_level: Some(String::from("loud")),
+                ..cli_with_token()
+            },
+            Cli {
+                channel_layout: Some(String::from("lobby:voice")),
+                ..cli_with_token()
+            },
+            Cli {
+                dev_guild: Some(0),
+                ..cli_with_token()
+            },
+        ] {
+            assert!(Config::resolve(cli, FileConfig::default()).is_err());
+        }
+        assert!(toml::from_str::<FileConfig>("colour = \"red\"").is_err());
+    }
+}
@@ -1,27 +1,421 @@
+use crate::layout::Template;
+use crate::migrations::{CREATE_MIGRATIONS_TABLE, MIGRATIONS};
+use crate::settings::GuildSettings;
+use crate::store::{EventStore, StoreError, StoreResult};
 use r2d2::{Error, Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::{params, Connection, Result};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+use rusqlite::{params, Connection, Params, Result, Row, ToSql};
 use std::iter::Iterator;
-use std::path::Path;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
 
 pub type SqlitePool = Pool<SqliteConnectionManager>;
 pub type PooledSqliteConnection = PooledConnection<SqliteConnectionManager>;
 
+/// How long a connection waits on a locked database before giving up with SQLITE_BUSY
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Clone)]
 pub struct DatabasePool(Arc<SqlitePool>);
 impl DatabasePool {
     pub fn new(database_url: &str) -> Result<Self, r2d2::Error> {
-        let manager = SqliteConnectionManager::file(database_url);
+        let manager = SqliteConnectionManager::file(database_url).with_init(configure_connection);
         let pool = Pool::new(manager)?;
         Ok(DatabasePool(Arc::new(pool)))
     }
-    pub fn get(&self) -> std::result::Result<PooledConnection<SqliteConnectionManager>, Error> {
+
+    /// A private in-memory database, shared by every handle of the pool
+    #[cfg(test)]
+    pub fn in_memory() -> Result<Self, r2d2::Error> {
+        let manager = SqliteConnectionManager::memory().with_init(configure_connection);
+        let pool = Pool::builder().max_size(1).build(manager)?;
+        Ok(DatabasePool(Arc::new(pool)))
+    }
+
+    pub fn get(&self) -> std::result::Result<PooledSqliteConnection, Error> {
         self.0.get()
     }
+
+    /// Runs SQLite's integrity check on the database.
+    /// Returns the problems reported by SQLite, if any
+    pub fn check_integrity(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let conn = self.get()?;
+        let mut statement = conn.prepare("PRAGMA integrity_check")?;
+        let report = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<String>>>()?;
+
+        if report == ["ok"] {
+            Ok(())
+        } else {
+            Err(report.join("\n").into())
+        }
+    }
+}
+
+/// Ran on every connection opened by the pool.
+/// Foreign keys are needed for the `ON DELETE CASCADE` clauses,
+/// WAL and the busy timeout let the reaction handlers write concurrently
+fn configure_connection(conn: &mut Connection) -> Result<()> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.execute_batch(
+        r#"PRAGMA foreign_keys = ON;
+        PRAGMA journal_mode = WAL;
+        PRAGMA synchronous = NORMAL;"#,
+    )
+}
+
+/// Where an event's participants meet
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EventMode {
+    /// A category with its own channels
+    #[default]
+    #[name = "category"]
+    Category,
+    /// A single private thread, or a forum post. Doesn't count towards the server's channel limits
+    #[name = "thread"]
+    Thread,
+}
+
+impl EventMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventMode::Category => "category",
+            EventMode::Thread => "thread",
+        }
+    }
+}
+
+impl FromStr for EventMode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> std::result::Result<Self, Self::Err> {
+        match mode {
+            "category" => Ok(EventMode::Category),
+            "thread" => Ok(EventMode::Thread),
+            _ => Err(format!("unknown event mode `{}`", mode)),
+        }
+    }
+}
+
+impl ToSql for EventMode {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for EventMode {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+/// How many losses knock an entrant out of a bracket
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BracketFormat {
+    #[default]
+    #[name = "single elimination"]
+    Single,
+    /// Losers drop to a losers bracket, whose winner meets the winners bracket's in the grand final
+    #[name = "double elimination"]
+    Double,
+}
+
+impl BracketFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BracketFormat::Single => "single",
+            BracketFormat::Double => "double",
+        }
+    }
+}
+
+impl FromStr for BracketFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> std::result::Result<Self, Self::Err> {
+        match format {
+            "single" => Ok(BracketFormat::Single),
+            "double" => Ok(BracketFormat::Double),
+            _ => Err(format!("unknown bracket format `{}`", format)),
+        }
+    }
+}
+
+impl ToSql for BracketFormat {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for BracketFormat {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+/// What a member did about an event they joined, kept in MEMBER_HISTORY
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Joined,
+    /// Checked in
+    Attended,
+    /// Left shortly before the event, see [crate::reliability::LATE_CANCEL]
+    LateCancel,
+    /// Still a participant when the event completed, without having checked in
+    NoShow,
+}
+
+impl Outcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Joined => "joined",
+            Outcome::Attended => "attended",
+            Outcome::LateCancel => "late_cancel",
+            Outcome::NoShow => "no_show",
+        }
+    }
+}
+
+impl FromStr for Outcome {
+    type Err = String;
+
+    fn from_str(outcome: &str) -> std::result::Result<Self, Self::Err> {
+        match outcome {
+            "joined" => Ok(Outcome::Joined),
+            "attended" => Ok(Outcome::Attended),
+            "late_cancel" => Ok(Outcome::LateCancel),
+            "no_show" => Ok(Outcome::NoShow),
+            _ => Err(format!("unknown outcome `{}`", outcome)),
+        }
+    }
+}
+
+impl ToSql for Outcome {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for Outcome {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+/// How many of a server's events a member joined, came to, and let down
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reliability {
+    pub joined: u32,
+    pub attended: u32,
+    pub late_cancels: u32,
+    pub no_shows: u32,
+}
+
+impl Reliability {
+    /// Adds [times] [outcome]
+    pub fn count(&mut self, outcome: Outcome, times: u32) {
+        match outcome {
+            Outcome::Joined => self.joined += times,
+            Outcome::Attended => self.attended += times,
+            Outcome::LateCancel => self.late_cancels += times,
+            Outcome::NoShow => self.no_shows += times,
+        }
+    }
+}
+
+/// Where an event is in its lifecycle.
+/// Drafts get published, published events end up completed or cancelled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EventStatus {
+    /// Only visible to its members, nobody can join yet
+    Draft,
+    #[default]
+    Published,
+    Cancelled,
+    Completed,
+}
+
+impl EventStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventStatus::Draft => "draft",
+            EventStatus::Published => "published",
+            EventStatus::Cancelled => "cancelled",
+            EventStatus::Completed => "completed",
+        }
+    }
+
+    /// Whether an event may go from this status to [next]
+    pub fn can_become(self, next: EventStatus) -> bool {
+        matches!(
+            (self, next),
+            (EventStatus::Draft, EventStatus::Published)
+                | (EventStatus::Draft, EventStatus::Cancelled)
+                | (EventStatus::Published, EventStatus::Cancelled)
+                | (EventStatus::Published, EventStatus::Completed)
+        )
+    }
+}
+
+impl FromStr for EventStatus {
+    type Err = String;
+
+    fn from_str(status: &str) -> std::result::Result<Self, Self::Err> {
+        match status {
+            "draft" => Ok(EventStatus::Draft),
+            "published" => Ok(EventStatus::Published),
+            "cancelled" => Ok(EventStatus::Cancelled),
+            "completed" => Ok(EventStatus::Completed),
+            _ => Err(format!("unknown event status `{}`", status)),
+        }
+    }
+}
+
+impl ToSql for EventStatus {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for EventStatus {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+/// A change made to an event after its creation, kept in EVENT_CHANGES
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventChange {
+    /// Unix seconds
+    pub changed_at: i64,
+    pub author_id: u64,
+    /// What changed, e.g. "status" or "starts_at"
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub reason: Option<String>,
+}
+
+/// A poll offering candidate start times for an event, at most one per event
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DatePoll {
+    /// Where the poll's message was posted
+    pub channel_id: u64,
+    pub message_id: u64,
+    /// Candidate start times in unix seconds, earliest first
+    pub slots: Vec<i64>,
+}
+
+/// Who may join an event, kept in EVENT_RULES and EVENT_RULE_ROLES.
+/// The default lets everyone in
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Eligibility {
+    /// Roles a member must all have, sorted
+    pub required_roles: Vec<u64>,
+    /// Roles that keep a member out, sorted
+    pub forbidden_roles: Vec<u64>,
+    /// Days since the member joined the server
+    pub min_member_days: Option<u32>,
+    /// Days since the member's Discord account was created
+    pub min_account_days: Option<u32>,
+}
+
+/// Someone kept out of an event, or of every event of a server
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ban {
+    pub user_id: u64,
+    pub banned_by: u64,
+    /// Unix seconds
+    pub banned_at: i64,
+    pub reason: Option<String>,
+}
+
+/// A named place in an event's composition, like the tanks of a raid
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TeamSlot {
+    pub name: String,
+    /// How many people the slot takes
+    pub capacity: u32,
+    /// Role given to the people in the slot, if the managers asked for one
+    pub role_id: Option<u64>,
+}
+
+/// One of the teams generated for an event, see /event teams
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Team {
+    /// Starts at 1, in the order the teams were generated
+    pub number: u32,
+    pub role_id: u64,
+    pub text_channel_id: u64,
+    pub voice_channel_id: u64,
+    /// Sorted
+    pub members: Vec<u64>,
+}
+
+/// A player or a team in a bracket
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entrant {
+    /// Starts at 1, the best seeds meet last
+    pub seed: u32,
+    pub name: String,
+    /// Who plays for the entrant and may report its matches, sorted
+    pub members: Vec<u64>,
+}
+
+/// The tournament bracket of an event, at most one per event
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bracket {
+    pub format: BracketFormat,
+    /// Where the bracket was posted, it's edited as matches are played
+    pub channel_id: u64,
+    pub message_id: u64,
+    /// By seed
+    pub entrants: Vec<Entrant>,
+}
+
+/// Who won a match of a bracket, according to [reported_by]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatchReport {
+    /// Like W1-2, see [crate::bracket::MatchId]
+    pub match_id: String,
+    /// Seed of the winner
+    pub winner: u32,
+    pub reported_by: u64,
+    /// Reports of players wait for their opponent or a manager, managers' count right away
+    pub confirmed: bool,
+}
+
+/// A check-in window of an event, at most one per event
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckIn {
+    /// Where the check-in button was posted
+    pub channel_id: u64,
+    pub message_id: u64,
+    /// The code shown at the venue, participants type it in to check in
+    pub code: Option<String>,
+    /// Unix seconds
+    pub opened_at: i64,
+    pub closes_at: i64,
 }
 
 /// An event, as seen by the database
+#[derive(Clone, Debug, PartialEq)]
 pub struct EventData {
     pub(crate) name: String,
     pub(crate) short_description: Option<String>,
@@ -35,63 +429,57 @@ pub struct EventData {
     pub(crate) participant_role_id: u64,
     pub(crate) manifest_id: u64,
     pub(crate) manifest_channel_id: u64,
-    pub(crate) category_id: u64
-}
-
-/// A channel or channel category
-struct Channel {
-    event: String, //Cross-references EventID
-    channel_id: u64,
-}
-
-struct Server{
-    server_id: u64,
-    event_creator_role_id: u64
-}
-
-pub fn create_connection<P: AsRef<Path>>(path: P) -> Result<Connection> {
-    let conn = Connection::open(path)?;
-    conn.execute("PRAGMA foreign_keys = ON;", [])?;
-    Ok(conn)
+    pub(crate) category_id: u64,
+    /// The emoji people react with on the manifest to join
+    pub(crate) rsvp_emoji: String,
+    /// Unix timestamps (seconds) of the event window, if known
+    pub(crate) starts_at: Option<i64>,
+    pub(crate) ends_at: Option<i64>,
+    /// Whether voice and stage channels only open during the event window
+    pub(crate) timed_voice: bool,
+    /// Whether participants can currently connect to the voice and stage channels
+    pub(crate) voice_open: bool,
+    /// Category events own CATEGORY_ID and their channels.
+    /// Thread events live in a single thread, stored both as CATEGORY_ID and in CHANNELS
+    pub(crate) mode: EventMode,
+    /// When the event was archived, see /event archive
+    pub(crate) archived_at: Option<i64>,
+    /// Where the event is in its lifecycle, only published events can be joined
+    pub(crate) status: EventStatus,
+    /// Manager-only channel join requests are posted in for approval.
+    /// None when anyone may join
+    pub(crate) requests_channel_id: Option<u64>,
+    /// When the conversations of the event were exported, so a retried deletion doesn't post them twice
+    pub(crate) transcript_exported_at: Option<i64>,
 }
 
-/// Creates the necessary tables
+/// Creates the necessary tables, by applying every pending migration
 pub fn create_tables(conn: &Connection) -> Result<()> {
-    conn.execute(
-        r#"CREATE TABLE IF NOT EXISTS EVENTS(
-                    ID INTEGER PRIMARY KEY AUTOINCREMENT,
-                    NAME TEXT NOT NULL,
-                    SHORT_DESCRIPTION TEXT,
-                    DESCRIPTION TEXT,
-                    THUMBNAIL TEXT,
-                    PICTURE TEXT,
-                    MAX_PARTICIPANTS INTEGER,
-                    SERVER_ID INTEGER NOT NULL,
-                    MANAGER_ROLE_ID INTEGER NOT NULL,
-                    PARTICIPANT_ROLE_ID INTEGER NOT NULL,
-                    MANIFEST_ID INTEGER NOT NULL,
-                    MANIFEST_CHANNEL_ID INTEGER NOT NULL,
-                    CATEGORY_ID INTEGER NOT NULL
-          )"#,
-        (),
-    )?;
+    conn.execute(CREATE_MIGRATIONS_TABLE, ())?;
 
-    conn.execute(
-        r#"CREATE TABLE IF NOT EXISTS CHANNELS (
-            EVENT_ID INTEGER,                       -- Define the EVENT_ID column first
-            CHANNEL_ID INTEGER NOT NULL,               -- Define the CHANNEL_ID column
-            FOREIGN KEY(EVENT_ID) REFERENCES EVENTS(ID) ON DELETE CASCADE
-        );"#,
-        (),
-    )?;
+    for migration in MIGRATIONS {
+        let applied = conn.query_row(
+            r#"SELECT COUNT(*) FROM SCHEMA_MIGRATIONS WHERE VERSION=?1"#,
+            params![migration.version],
+            |row| row.get::<_, i64>(0),
+        )? > 0;
+        if applied {
+            continue;
+        }
 
-    conn.execute(
-        r#" CREATE TABLE IF NOT EXISTS SERVERS (
-            SERVER_ID INTEGER PRIMARY KEY,
-            EVENT_CREATOR_ROLE_ID NOT NULL
-        )"#,
-        ()
-    )?;
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(migration.sqlite)?;
+        tx.execute(
+            r#"INSERT INTO SCHEMA_MIGRATIONS(VERSION, DESCRIPTION) VALUES (?1, ?2)"#,
+            params![migration.version, migration.description],
+        )?;
+        tx.commit()?;
+
+        info!(
+            "Applied migration {} ({})",
+            migration.version, migration.description
+        );
+    }
 
     Ok(())
 }
@@ -112,8 +500,18 @@ pub fn insert_event(conn: &Connection, data: EventData) -> Result<i64> {
         PARTICIPANT_ROLE_ID,
         MANIFEST_ID,
         MANIFEST_CHANNEL_ID,
-        CATEGORY_ID
-    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)"#,
+        CATEGORY_ID,
+        RSVP_EMOJI,
+        STARTS_AT,
+        ENDS_AT,
+        TIMED_VOICE,
+        VOICE_OPEN,
+        MODE,
+        ARCHIVED_AT,
+        STATUS,
+        REQUESTS_CHANNEL_ID,
+        TRANSCRIPT_EXPORTED_AT
+    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)"#,
         params![
             data.name,
             data.short_description,
@@ -126,14 +524,22 @@ pub fn insert_event(conn: &Connection, data: EventData) -> Result<i64> {
             data.participant_role_id,
             data.manifest_id,
             data.manifest_channel_id,
-            data.category_id
+            data.category_id,
+            data.rsvp_emoji,
+            data.starts_at,
+            data.ends_at,
+            data.timed_voice,
+            data.voice_open,
+            data.mode,
+            data.archived_at,
+            data.status,
+            data.requests_channel_id,
+            data.transcript_exported_at
         ],
     )?;
 
     let mut stmt = conn.prepare("SELECT ID FROM EVENTS WHERE ROWID=?1")?;
-    stmt.query_row(params![conn.last_insert_rowid()], |row| {
-        Ok(row.get::<_, i64>(0)?)
-    })
+    stmt.query_row(params![conn.last_insert_rowid()], |row| row.get::<_, i64>(0))
 }
 
 /// Inserts channels into the table.
@@ -156,110 +562,1328 @@ pub fn get_channels_by_event_id(conn: &Connection, event_id: i64) -> Result<Vec<
     let mut statement = conn.prepare(r#"SELECT CHANNEL_ID FROM CHANNELS WHERE EVENT_ID=?1"#)?;
     let rows = statement.query_map(params![event_id], |row| row.get::<_, u64>(0))?;
 
-    Ok(rows.filter(|x| x.is_ok()).map(|x| x.unwrap()).collect())
+    rows.collect()
+}
+
+/// Returns the number of removed channels
+pub fn delete_channel(conn: &Connection, event_id: i64, channel_id: u64) -> Result<usize> {
+    conn.execute(
+        r#"DELETE FROM CHANNELS WHERE EVENT_ID=?1 AND CHANNEL_ID=?2"#,
+        params![event_id, channel_id],
+    )
 }
 
 /// Returns Ok(number of affected rows) if all went well
+/// The event's channels are removed along with it (ON DELETE CASCADE)
 pub fn delete_event(conn: &Connection, event_id: i64) -> Result<usize> {
-    conn.execute(r#"DELETE FROM CHANNELS WHERE EVENT_ID=?1"#, params![event_id])
+    conn.execute(r#"DELETE FROM EVENTS WHERE ID=?1"#, params![event_id])
+}
+
+/// Every column read by [event_from_row]. Add new EVENTS columns here and there only
+const EVENT_COLUMNS: &str = r#"ID, NAME, SHORT_DESCRIPTION, DESCRIPTION, THUMBNAIL, PICTURE,
+    MAX_PARTICIPANTS, SERVER_ID, MANAGER_ROLE_ID, PARTICIPANT_ROLE_ID, MANIFEST_ID,
+    MANIFEST_CHANNEL_ID, CATEGORY_ID, RSVP_EMOJI,
+    STARTS_AT, ENDS_AT, TIMED_VOICE, VOICE_OPEN, MODE, ARCHIVED_AT, STATUS, REQUESTS_CHANNEL_ID,
+    TRANSCRIPT_EXPORTED_AT"#;
+
+fn event_from_row(row: &Row) -> Result<(i64, EventData)> {
+    Ok((
+        row.get("ID")?,
+        EventData {
+            name: row.get("NAME")?,
+            short_description: row.get("SHORT_DESCRIPTION")?,
+            description: row.get("DESCRIPTION")?,
+            thumbnail: row.get("THUMBNAIL")?,
+            picture: row.get("PICTURE")?,
+            max_participants: row.get("MAX_PARTICIPANTS")?,
+            server_id: row.get("SERVER_ID")?,
+            manager_role_id: row.get("MANAGER_ROLE_ID")?,
+            participant_role_id: row.get("PARTICIPANT_ROLE_ID")?,
+            manifest_id: row.get("MANIFEST_ID")?,
+            manifest_channel_id: row.get("MANIFEST_CHANNEL_ID")?,
+            category_id: row.get("CATEGORY_ID")?,
+            rsvp_emoji: row.get("RSVP_EMOJI")?,
+            starts_at: row.get("STARTS_AT")?,
+            ends_at: row.get("ENDS_AT")?,
+            timed_voice: row.get("TIMED_VOICE")?,
+            voice_open: row.get("VOICE_OPEN")?,
+            mode: row.get("MODE")?,
+            archived_at: row.get("ARCHIVED_AT")?,
+            status: row.get("STATUS")?,
+            requests_channel_id: row.get("REQUESTS_CHANNEL_ID")?,
+            transcript_exported_at: row.get("TRANSCRIPT_EXPORTED_AT")?,
+        },
+    ))
+}
+
+/// Returns the first event matching [condition], a WHERE clause over EVENTS
+fn query_event<P: Params>(conn: &Connection, condition: &str, params: P) -> Result<(i64, EventData)> {
+    conn.query_row(
+        &format!("SELECT {} FROM EVENTS WHERE {}", EVENT_COLUMNS, condition),
+        params,
+        event_from_row,
+    )
+}
+
+/// Returns every event matching [condition], a WHERE clause over EVENTS
+fn query_events<P: Params>(conn: &Connection, condition: &str, params: P) -> Result<Vec<(i64, EventData)>> {
+    let mut statement = conn.prepare(&format!(
+        "SELECT {} FROM EVENTS WHERE {} ORDER BY ID",
+        EVENT_COLUMNS, condition
+    ))?;
+    let event_iter = statement.query_map(params, event_from_row)?;
+
+    event_iter.collect()
+}
+
+pub fn get_event_by_id(conn: &Connection, event_id: i64) -> Result<(i64, EventData)> {
+    query_event(conn, "ID=?1", params![event_id])
 }
 
 /// Returns Ok((Event_ID, Event_Data)) if an event owns channel [channel_id]
 pub fn get_event_by_channel(conn: &Connection, channel_id: u64) -> Result<(i64, EventData)> {
-    let id = conn.query_row(
-        r#"SELECT EVENT_ID FROM CHANNELS WHERE CHANNEL_ID=?1"#,
+    query_event(
+        conn,
+        "ID=(SELECT EVENT_ID FROM CHANNELS WHERE CHANNEL_ID=?1)",
         params![channel_id],
-        |row| row.get::<_, i64>(0),
+    )
+}
+
+pub fn get_event_by_manifest(conn: &Connection, manifest_id: u64) -> Result<(i64, EventData)> {
+    query_event(conn, "MANIFEST_ID=?1", params![manifest_id])
+}
+
+pub fn get_event_by_category(conn: &Connection, category_id: u64) -> Result<(i64, EventData)> {
+    query_event(conn, "CATEGORY_ID=?1", params![category_id])
+}
+
+pub fn get_events_by_guild(conn: &Connection, server_id: u64) -> Result<Vec<(i64, EventData)>> {
+    query_events(conn, "SERVER_ID=?1", params![server_id])
+}
+
+pub fn get_timed_voice_events(conn: &Connection) -> Result<Vec<(i64, EventData)>> {
+    query_events(
+        conn,
+        "TIMED_VOICE AND ARCHIVED_AT IS NULL AND STATUS IN ('draft', 'published')",
+        params![],
+    )
+}
+
+pub fn set_voice_open(conn: &Connection, event_id: i64, open: bool) -> Result<usize> {
+    conn.execute(
+        r#"UPDATE EVENTS SET VOICE_OPEN=?2 WHERE ID=?1"#,
+        params![event_id, open],
+    )
+}
+
+pub fn archive_event(conn: &Connection, event_id: i64, archived_at: i64) -> Result<usize> {
+    conn.execute(
+        r#"UPDATE EVENTS SET ARCHIVED_AT=?2 WHERE ID=?1"#,
+        params![event_id, archived_at],
+    )
+}
+
+pub fn set_transcript_exported(conn: &Connection, event_id: i64, exported_at: i64) -> Result<usize> {
+    conn.execute(
+        r#"UPDATE EVENTS SET TRANSCRIPT_EXPORTED_AT=?2 WHERE ID=?1"#,
+        params![event_id, exported_at],
+    )
+}
+
+pub fn set_status(conn: &Connection, event_id: i64, status: EventStatus) -> Result<usize> {
+    conn.execute(
+        r#"UPDATE EVENTS SET STATUS=?2 WHERE ID=?1"#,
+        params![event_id, status],
+    )
+}
+
+pub fn set_manifest(conn: &Connection, event_id: i64, channel_id: u64, manifest_id: u64) -> Result<usize> {
+    conn.execute(
+        r#"UPDATE EVENTS SET MANIFEST_CHANNEL_ID=?2, MANIFEST_ID=?3 WHERE ID=?1"#,
+        params![event_id, channel_id, manifest_id],
+    )
+}
+
+pub fn set_event_times(conn: &Connection, event_id: i64, starts_at: Option<i64>, ends_at: Option<i64>) -> Result<usize> {
+    conn.execute(
+        r#"UPDATE EVENTS SET STARTS_AT=?2, ENDS_AT=?3 WHERE ID=?1"#,
+        params![event_id, starts_at, ends_at],
+    )
+}
+
+pub fn insert_event_change(conn: &Connection, event_id: i64, change: &EventChange) -> Result<()> {
+    conn.execute(
+        r#"INSERT INTO EVENT_CHANGES(EVENT_ID, CHANGED_AT, AUTHOR_ID, FIELD, OLD_VALUE, NEW_VALUE, REASON)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+        params![
+            event_id,
+            change.changed_at,
+            change.author_id,
+            change.field,
+            change.old_value,
+            change.new_value,
+            change.reason
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn get_event_changes(conn: &Connection, event_id: i64) -> Result<Vec<EventChange>> {
+    let mut statement = conn.prepare(
+        r#"SELECT CHANGED_AT, AUTHOR_ID, FIELD, OLD_VALUE, NEW_VALUE, REASON
+        FROM EVENT_CHANGES WHERE EVENT_ID=?1 ORDER BY ID"#,
     )?;
+    let rows = statement.query_map(params![event_id], |row| {
+        Ok(EventChange {
+            changed_at: row.get("CHANGED_AT")?,
+            author_id: row.get("AUTHOR_ID")?,
+            field: row.get("FIELD")?,
+            old_value: row.get("OLD_VALUE")?,
+            new_value: row.get("NEW_VALUE")?,
+            reason: row.get("REASON")?,
+        })
+    })?;
 
-    conn.query_row(r#"SELECT * FROM EVENTS WHERE ID=?1"#, params![id], |row| {
-        Ok((
-            row.get::<_, i64>(0)?,
-            EventData {
-                name: row.get(1)?,
-                short_description: row.get(2)?,
-                description: row.get(3)?,
-                thumbnail: row.get(4)?,
-                picture: row.get(5)?,
-                max_participants: row.get(6)?,
-                server_id: row.get(7)?,
-                manager_role_id: row.get(8)?,
-                participant_role_id: row.get(9)?,
-                manifest_id: row.get(10)?,
-                manifest_channel_id: row.get(11)?,
-                category_id: row.get(12)?
-            },
-        ))
-    })
+    rows.collect()
 }
 
-pub fn get_event_by_manifest(conn: &Connection, manifest_id: u64) -> Result<(i64, EventData)> {
-    conn.query_row(r#"SELECT * FROM EVENTS WHERE MANIFEST_ID=?1"#, params![manifest_id], |row| {
-        Ok((
-            row.get::<_, i64>(0)?,
-            EventData {
-                name: row.get(1)?,
-                short_description: row.get(2)?,
-                description: row.get(3)?,
-                thumbnail: row.get(4)?,
-                picture: row.get(5)?,
-                max_participants: row.get(6)?,
-                server_id: row.get(7)?,
-                manager_role_id: row.get(8)?,
-                participant_role_id: row.get(9)?,
-                manifest_id: row.get(10)?,
-                manifest_channel_id: row.get(11)?,
-                category_id: row.get(12)?
-            },
-        ))
-    })
+pub fn set_dm_notifications(conn: &Connection, user_id: u64, enabled: bool) -> Result<()> {
+    if enabled {
+        conn.execute(
+            r#"INSERT OR IGNORE INTO DM_NOTIFICATIONS(USER_ID) VALUES (?1)"#,
+            params![user_id],
+        )?;
+    } else {
+        conn.execute(r#"DELETE FROM DM_NOTIFICATIONS WHERE USER_ID=?1"#, params![user_id])?;
+    }
+    Ok(())
+}
+
+pub fn get_dm_subscribers(conn: &Connection, event_id: i64) -> Result<Vec<u64>> {
+    let mut statement = conn.prepare(
+        r#"SELECT P.USER_ID FROM PARTICIPANTS P JOIN DM_NOTIFICATIONS N ON N.USER_ID=P.USER_ID
+        WHERE P.EVENT_ID=?1 ORDER BY P.ROWID"#,
+    )?;
+    let rows = statement.query_map(params![event_id], |row| row.get::<_, u64>(0))?;
+
+    rows.collect()
+}
+
+pub fn create_poll(conn: &Connection, event_id: i64, poll: &DatePoll) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    // Votes and slots of the previous poll cascade
+    tx.execute(r#"DELETE FROM POLLS WHERE EVENT_ID=?1"#, params![event_id])?;
+    tx.execute(
+        r#"INSERT INTO POLLS(EVENT_ID, CHANNEL_ID, MESSAGE_ID) VALUES (?1, ?2, ?3)"#,
+        params![event_id, poll.channel_id, poll.message_id],
+    )?;
+    {
+        let mut stmt =
+            tx.prepare(r#"INSERT OR IGNORE INTO POLL_SLOTS(EVENT_ID, STARTS_AT) VALUES (?1, ?2)"#)?;
+        for slot in &poll.slots {
+            stmt.execute(params![event_id, slot])?;
+        }
+    }
+    tx.commit()
 }
 
-pub fn get_all_events(conn: &Connection) -> Result<Vec<(i64, EventData)>> {
-    let mut statement = conn.prepare("SELECT * FROM EVENTS")?;
-    let event_iter = statement.query_map([], |row| {
-            Ok((
-                row.get::<_, i64>(0)?,
-                EventData {
-                    name: row.get(1)?,
-                    short_description: row.get(2)?,
-                    description: row.get(3)?,
-                    thumbnail: row.get(4)?,
-                    picture: row.get(5)?,
-                    max_participants: row.get(6)?,
-                    server_id: row.get(7)?,
-                    manager_role_id: row.get(8)?,
-                    participant_role_id: row.get(9)?,
-                    manifest_id: row.get(10)?,
-                    manifest_channel_id: row.get(11)?,
-                    category_id: row.get(12)?,
-                },
-            ))
-        })?;
-
-    Ok(event_iter.filter(|x| x.is_ok()).map(|x| x.unwrap()).collect())
+pub fn get_poll(conn: &Connection, event_id: i64) -> Result<DatePoll> {
+    let (channel_id, message_id) = conn.query_row(
+        r#"SELECT CHANNEL_ID, MESSAGE_ID FROM POLLS WHERE EVENT_ID=?1"#,
+        params![event_id],
+        |row| Ok((row.get::<_, u64>(0)?, row.get::<_, u64>(1)?)),
+    )?;
+    let mut statement = conn.prepare(
+        r#"SELECT STARTS_AT FROM POLL_SLOTS WHERE EVENT_ID=?1 ORDER BY STARTS_AT"#,
+    )?;
+    let slots = statement
+        .query_map(params![event_id], |row| row.get::<_, i64>(0))?
+        .collect::<Result<Vec<i64>>>()?;
+
+    Ok(DatePoll {
+        channel_id,
+        message_id,
+        slots,
+    })
 }
 
+pub fn set_poll_votes(conn: &Connection, event_id: i64, user_id: u64, slots: &[i64]) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        r#"DELETE FROM POLL_VOTES WHERE EVENT_ID=?1 AND USER_ID=?2"#,
+        params![event_id, user_id],
+    )?;
+    {
+        // Going through POLL_SLOTS skips the slots the poll doesn't offer
+        let mut stmt = tx.prepare(
+            r#"INSERT OR IGNORE INTO POLL_VOTES(EVENT_ID, STARTS_AT, USER_ID)
+            SELECT EVENT_ID, STARTS_AT, ?3 FROM POLL_SLOTS WHERE EVENT_ID=?1 AND STARTS_AT=?2"#,
+        )?;
+        for slot in slots {
+            stmt.execute(params![event_id, slot, user_id])?;
+        }
+    }
+    tx.commit()
+}
 
-pub fn insert_server_manager_role(conn: &Connection, server_id: u64, role_id: u64) -> Result<()>{
-    conn.execute(r#"INSERT INTO SERVERS(
-        SERVER_ID,
-        EVENT_CREATOR_ROLE_ID
-    ) VALUES (?1, ?2)"#,
-    params![server_id, role_id]
+pub fn get_poll_votes(conn: &Connection, event_id: i64) -> Result<Vec<(i64, u64)>> {
+    let mut statement = conn.prepare(
+        r#"SELECT STARTS_AT, USER_ID FROM POLL_VOTES WHERE EVENT_ID=?1 ORDER BY STARTS_AT, USER_ID"#,
     )?;
+    let rows = statement.query_map(params![event_id], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, u64>(1)?))
+    })?;
 
+    rows.collect()
+}
+
+pub fn delete_poll(conn: &Connection, event_id: i64) -> Result<usize> {
+    conn.execute(r#"DELETE FROM POLLS WHERE EVENT_ID=?1"#, params![event_id])
+}
+
+pub fn insert_join_request(
+    conn: &Connection,
+    event_id: i64,
+    user_id: u64,
+    message_id: u64,
+) -> Result<()> {
+    conn.execute(
+        r#"INSERT INTO JOIN_REQUESTS(EVENT_ID, USER_ID, MESSAGE_ID) VALUES (?1, ?2, ?3)
+        ON CONFLICT(EVENT_ID, USER_ID) DO UPDATE SET MESSAGE_ID=excluded.MESSAGE_ID"#,
+        params![event_id, user_id, message_id],
+    )?;
     Ok(())
 }
 
-pub fn get_server_manager_role_id(conn: &Connection, server_id : u64) -> Result<u64>{
-    conn.query_row(r#"SELECT EVENT_CREATOR_ROLE_ID FROM SERVERS WHERE SERVER_ID=?"#, params![server_id],
-    |row| {
-        Ok(row.get(0)?)
+pub fn get_join_request(conn: &Connection, event_id: i64, user_id: u64) -> Result<u64> {
+    conn.query_row(
+        r#"SELECT MESSAGE_ID FROM JOIN_REQUESTS WHERE EVENT_ID=?1 AND USER_ID=?2"#,
+        params![event_id, user_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn delete_join_request(conn: &Connection, event_id: i64, user_id: u64) -> Result<usize> {
+    conn.execute(
+        r#"DELETE FROM JOIN_REQUESTS WHERE EVENT_ID=?1 AND USER_ID=?2"#,
+        params![event_id, user_id],
+    )
+}
+
+pub fn get_eligibility(conn: &Connection, event_id: i64) -> Result<Eligibility> {
+    let mut rules = match conn.query_row(
+        r#"SELECT MIN_MEMBER_DAYS, MIN_ACCOUNT_DAYS FROM EVENT_RULES WHERE EVENT_ID=?1"#,
+        params![event_id],
+        |row| {
+            Ok(Eligibility {
+                min_member_days: row.get(0)?,
+                min_account_days: row.get(1)?,
+                ..Eligibility::default()
+            })
+        },
+    ) {
+        Ok(rules) => rules,
+        Err(rusqlite::Error::QueryReturnedNoRows) => Eligibility::default(),
+        Err(e) => return Err(e),
+    };
+
+    let mut statement = conn.prepare(
+        r#"SELECT ROLE_ID, REQUIRED FROM EVENT_RULE_ROLES WHERE EVENT_ID=?1 ORDER BY ROLE_ID"#,
+    )?;
+    let roles = statement.query_map(params![event_id], |row| {
+        Ok((row.get::<_, u64>(0)?, row.get::<_, bool>(1)?))
+    })?;
+    for role in roles {
+        match role? {
+            (role_id, true) => rules.required_roles.push(role_id),
+            (role_id, false) => rules.forbidden_roles.push(role_id),
+        }
+    }
+
+    Ok(rules)
+}
+
+pub fn set_eligibility(conn: &Connection, event_id: i64, rules: &Eligibility) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(r#"DELETE FROM EVENT_RULES WHERE EVENT_ID=?1"#, params![event_id])?;
+    tx.execute(r#"DELETE FROM EVENT_RULE_ROLES WHERE EVENT_ID=?1"#, params![event_id])?;
+    tx.execute(
+        r#"INSERT INTO EVENT_RULES(EVENT_ID, MIN_MEMBER_DAYS, MIN_ACCOUNT_DAYS) VALUES (?1, ?2, ?3)"#,
+        params![event_id, rules.min_member_days, rules.min_account_days],
+    )?;
+    {
+        let mut stmt = tx.prepare(
+            r#"INSERT OR REPLACE INTO EVENT_RULE_ROLES(EVENT_ID, ROLE_ID, REQUIRED) VALUES (?1, ?2, ?3)"#,
+        )?;
+        for role_id in &rules.required_roles {
+            stmt.execute(params![event_id, role_id, true])?;
+        }
+        for role_id in &rules.forbidden_roles {
+            stmt.execute(params![event_id, role_id, false])?;
+        }
+    }
+    tx.commit()
+}
+
+const BAN_COLUMNS: &str = "USER_ID, BANNED_BY, BANNED_AT, REASON";
+
+fn ban_from_row(row: &Row) -> Result<Ban> {
+    Ok(Ban {
+        user_id: row.get("USER_ID")?,
+        banned_by: row.get("BANNED_BY")?,
+        banned_at: row.get("BANNED_AT")?,
+        reason: row.get("REASON")?,
     })
 }
 
-pub fn delete_server_manager_role(conn: &Connection, server_id: u64) -> Result<()> {
-    conn.execute(r#"DELETE FROM SERVERS WHERE SERVER_ID=?"#, params![server_id])?;
+/// Bans go in EVENT_BANS or SERVER_BANS, [owner] being the event or the server
+fn insert_ban(conn: &Connection, table: &str, owner: &str, owner_id: i64, ban: &Ban) -> Result<()> {
+    conn.execute(
+        &format!(
+            r#"INSERT OR REPLACE INTO {}({}, {}) VALUES (?1, ?2, ?3, ?4, ?5)"#,
+            table, owner, BAN_COLUMNS
+        ),
+        params![owner_id, ban.user_id, ban.banned_by, ban.banned_at, ban.reason],
+    )?;
+    Ok(())
+}
+
+fn get_bans(conn: &Connection, table: &str, owner: &str, owner_id: i64) -> Result<Vec<Ban>> {
+    let mut statement = conn.prepare(&format!(
+        r#"SELECT {} FROM {} WHERE {}=?1 ORDER BY BANNED_AT, USER_ID"#,
+        BAN_COLUMNS, table, owner
+    ))?;
+    let rows = statement.query_map(params![owner_id], ban_from_row)?;
+
+    rows.collect()
+}
+
+pub fn insert_event_ban(conn: &Connection, event_id: i64, ban: &Ban) -> Result<()> {
+    insert_ban(conn, "EVENT_BANS", "EVENT_ID", event_id, ban)
+}
+
+pub fn delete_event_ban(conn: &Connection, event_id: i64, user_id: u64) -> Result<usize> {
+    conn.execute(
+        r#"DELETE FROM EVENT_BANS WHERE EVENT_ID=?1 AND USER_ID=?2"#,
+        params![event_id, user_id],
+    )
+}
+
+pub fn get_event_bans(conn: &Connection, event_id: i64) -> Result<Vec<Ban>> {
+    get_bans(conn, "EVENT_BANS", "EVENT_ID", event_id)
+}
+
+pub fn insert_server_ban(conn: &Connection, server_id: u64, ban: &Ban) -> Result<()> {
+    insert_ban(conn, "SERVER_BANS", "SERVER_ID", server_id as i64, ban)
+}
+
+pub fn delete_server_ban(conn: &Connection, server_id: u64, user_id: u64) -> Result<usize> {
+    conn.execute(
+        r#"DELETE FROM SERVER_BANS WHERE SERVER_ID=?1 AND USER_ID=?2"#,
+        params![server_id, user_id],
+    )
+}
+
+pub fn get_server_bans(conn: &Connection, server_id: u64) -> Result<Vec<Ban>> {
+    get_bans(conn, "SERVER_BANS", "SERVER_ID", server_id as i64)
+}
+
+pub fn get_ban(conn: &Connection, event_id: i64, user_id: u64) -> Result<Ban> {
+    conn.query_row(
+        &format!(
+            r#"SELECT {0} FROM EVENT_BANS WHERE EVENT_ID=?1 AND USER_ID=?2
+            UNION ALL
+            SELECT {0} FROM SERVER_BANS WHERE USER_ID=?2
+            AND SERVER_ID=(SELECT SERVER_ID FROM EVENTS WHERE ID=?1)
+            LIMIT 1"#,
+            BAN_COLUMNS
+        ),
+        params![event_id, user_id],
+        ban_from_row,
+    )
+}
+
+pub fn get_archived_events(conn: &Connection) -> Result<Vec<(i64, EventData)>> {
+    let mut statement = conn.prepare(&format!(
+        "SELECT {} FROM EVENTS WHERE ARCHIVED_AT IS NOT NULL ORDER BY ARCHIVED_AT, ID",
+        EVENT_COLUMNS
+    ))?;
+    let event_iter = statement.query_map(params![], event_from_row)?;
+
+    event_iter.collect()
+}
+
+
+pub fn insert_slot(conn: &Connection, event_id: i64, slot: &TeamSlot) -> Result<()> {
+    conn.execute(
+        r#"INSERT INTO SLOTS(EVENT_ID, NAME, CAPACITY, ROLE_ID, POSITION) VALUES (?1, ?2, ?3, ?4,
+            (SELECT COALESCE(MAX(POSITION) + 1, 0) FROM SLOTS WHERE EVENT_ID=?1))
+        ON CONFLICT(EVENT_ID, NAME) DO UPDATE SET CAPACITY=excluded.CAPACITY, ROLE_ID=excluded.ROLE_ID"#,
+        params![event_id, slot.name, slot.capacity, slot.role_id],
+    )?;
     Ok(())
-}
\ No newline at end of file
+}
+
+pub fn delete_slot(conn: &Connection, event_id: i64, name: &str) -> Result<usize> {
+    conn.execute(
+        r#"DELETE FROM SLOTS WHERE EVENT_ID=?1 AND NAME=?2"#,
+        params![event_id, name],
+    )
+}
+
+pub fn get_slots(conn: &Connection, event_id: i64) -> Result<Vec<TeamSlot>> {
+    let mut statement = conn.prepare(
+        r#"SELECT NAME, CAPACITY, ROLE_ID FROM SLOTS WHERE EVENT_ID=?1 ORDER BY POSITION"#,
+    )?;
+    let rows = statement.query_map(params![event_id], |row| {
+        Ok(TeamSlot {
+            name: row.get(0)?,
+            capacity: row.get(1)?,
+            role_id: row.get(2)?,
+        })
+    })?;
+
+    rows.collect()
+}
+
+/// Replacing the row gives it a new ROWID, the user goes last in their new slot.
+/// The capacity is checked by the insert itself, two people can't both take the last place
+pub fn take_slot(conn: &Connection, event_id: i64, user_id: u64, name: &str) -> Result<bool> {
+    let taken = conn.execute(
+        r#"INSERT OR REPLACE INTO SLOT_MEMBERS(EVENT_ID, USER_ID, SLOT_NAME)
+        SELECT ?1, ?2, ?3 FROM SLOTS WHERE EVENT_ID=?1 AND NAME=?3 AND CAPACITY > (
+            SELECT COUNT(*) FROM SLOT_MEMBERS WHERE EVENT_ID=?1 AND SLOT_NAME=?3 AND USER_ID<>?2
+        )"#,
+        params![event_id, user_id, name],
+    )?;
+    Ok(taken > 0)
+}
+
+pub fn leave_slot(conn: &Connection, event_id: i64, user_id: u64) -> Result<usize> {
+    conn.execute(
+        r#"DELETE FROM SLOT_MEMBERS WHERE EVENT_ID=?1 AND USER_ID=?2"#,
+        params![event_id, user_id],
+    )
+}
+
+pub fn get_slot_members(conn: &Connection, event_id: i64) -> Result<Vec<(String, u64)>> {
+    let mut statement = conn.prepare(
+        r#"SELECT SLOT_NAME, USER_ID FROM SLOT_MEMBERS WHERE EVENT_ID=?1 ORDER BY ROWID"#,
+    )?;
+    let rows = statement.query_map(params![event_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    rows.collect()
+}
+
+pub fn set_skill(conn: &Connection, event_id: i64, user_id: u64, skill: Option<i64>) -> Result<()> {
+    match skill {
+        Some(skill) => conn.execute(
+            r#"INSERT OR REPLACE INTO SKILLS(EVENT_ID, USER_ID, SKILL) VALUES (?1, ?2, ?3)"#,
+            params![event_id, user_id, skill],
+        )?,
+        None => conn.execute(
+            r#"DELETE FROM SKILLS WHERE EVENT_ID=?1 AND USER_ID=?2"#,
+            params![event_id, user_id],
+        )?,
+    };
+    Ok(())
+}
+
+pub fn get_skills(conn: &Connection, event_id: i64) -> Result<Vec<(u64, i64)>> {
+    let mut statement = conn
+        .prepare(r#"SELECT USER_ID, SKILL FROM SKILLS WHERE EVENT_ID=?1 ORDER BY USER_ID"#)?;
+    let rows = statement.query_map(params![event_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    rows.collect()
+}
+
+pub fn insert_team(conn: &Connection, event_id: i64, team: &Team) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        r#"INSERT INTO TEAMS(EVENT_ID, NUMBER, ROLE_ID, TEXT_CHANNEL_ID, VOICE_CHANNEL_ID)
+        VALUES (?1, ?2, ?3, ?4, ?5)"#,
+        params![
+            event_id,
+            team.number,
+            team.role_id,
+            team.text_channel_id,
+            team.voice_channel_id
+        ],
+    )?;
+    {
+        let mut stmt = tx.prepare(
+            r#"INSERT OR REPLACE INTO TEAM_MEMBERS(EVENT_ID, NUMBER, USER_ID) VALUES (?1, ?2, ?3)"#,
+        )?;
+        for user_id in &team.members {
+            stmt.execute(params![event_id, team.number, user_id])?;
+        }
+    }
+    tx.commit()
+}
+
+pub fn get_teams(conn: &Connection, event_id: i64) -> Result<Vec<Team>> {
+    let mut statement = conn.prepare(
+        r#"SELECT NUMBER, ROLE_ID, TEXT_CHANNEL_ID, VOICE_CHANNEL_ID FROM TEAMS
+        WHERE EVENT_ID=?1 ORDER BY NUMBER"#,
+    )?;
+    let mut teams = statement
+        .query_map(params![event_id], |row| {
+            Ok(Team {
+                number: row.get(0)?,
+                role_id: row.get(1)?,
+                text_channel_id: row.get(2)?,
+                voice_channel_id: row.get(3)?,
+                members: vec![],
+            })
+        })?
+        .collect::<Result<Vec<Team>>>()?;
+
+    let mut statement = conn.prepare(
+        r#"SELECT NUMBER, USER_ID FROM TEAM_MEMBERS WHERE EVENT_ID=?1 ORDER BY USER_ID"#,
+    )?;
+    let members = statement.query_map(params![event_id], |row| {
+        Ok((row.get::<_, u32>(0)?, row.get::<_, u64>(1)?))
+    })?;
+    for member in members {
+        let (number, user_id) = member?;
+        if let Some(team) = teams.iter_mut().find(|team| team.number == number) {
+            team.members.push(user_id);
+        }
+    }
+
+    Ok(teams)
+}
+
+pub fn delete_teams(conn: &Connection, event_id: i64) -> Result<usize> {
+    conn.execute(r#"DELETE FROM TEAMS WHERE EVENT_ID=?1"#, params![event_id])
+}
+
+pub fn create_bracket(conn: &Connection, event_id: i64, bracket: &Bracket) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(r#"DELETE FROM BRACKETS WHERE EVENT_ID=?1"#, params![event_id])?;
+    tx.execute(
+        r#"INSERT INTO BRACKETS(EVENT_ID, FORMAT, CHANNEL_ID, MESSAGE_ID) VALUES (?1, ?2, ?3, ?4)"#,
+        params![event_id, bracket.format, bracket.channel_id, bracket.message_id],
+    )?;
+    {
+        let mut entrants = tx.prepare(
+            r#"INSERT INTO BRACKET_ENTRANTS(EVENT_ID, SEED, NAME) VALUES (?1, ?2, ?3)"#,
+        )?;
+        let mut members = tx.prepare(
+            r#"INSERT OR IGNORE INTO BRACKET_MEMBERS(EVENT_ID, SEED, USER_ID) VALUES (?1, ?2, ?3)"#,
+        )?;
+        for entrant in &bracket.entrants {
+            entrants.execute(params![event_id, entrant.seed, entrant.name])?;
+            for user_id in &entrant.members {
+                members.execute(params![event_id, entrant.seed, user_id])?;
+            }
+        }
+    }
+    tx.commit()
+}
+
+pub fn get_bracket(conn: &Connection, event_id: i64) -> Result<Bracket> {
+    let mut bracket = conn.query_row(
+        r#"SELECT FORMAT, CHANNEL_ID, MESSAGE_ID FROM BRACKETS WHERE EVENT_ID=?1"#,
+        params![event_id],
+        |row| {
+            Ok(Bracket {
+                format: row.get(0)?,
+                channel_id: row.get(1)?,
+                message_id: row.get(2)?,
+                entrants: vec![],
+            })
+        },
+    )?;
+
+    let mut statement = conn.prepare(
+        r#"SELECT SEED, NAME FROM BRACKET_ENTRANTS WHERE EVENT_ID=?1 ORDER BY SEED"#,
+    )?;
+    bracket.entrants = statement
+        .query_map(params![event_id], |row| {
+            Ok(Entrant {
+                seed: row.get(0)?,
+                name: row.get(1)?,
+                members: vec![],
+            })
+        })?
+        .collect::<Result<Vec<Entrant>>>()?;
+
+    let mut statement = conn.prepare(
+        r#"SELECT SEED, USER_ID FROM BRACKET_MEMBERS WHERE EVENT_ID=?1 ORDER BY USER_ID"#,
+    )?;
+    let members = statement.query_map(params![event_id], |row| {
+        Ok((row.get::<_, u32>(0)?, row.get::<_, u64>(1)?))
+    })?;
+    for member in members {
+        let (seed, user_id) = member?;
+        if let Some(entrant) = bracket.entrants.iter_mut().find(|e| e.seed == seed) {
+            entrant.members.push(user_id);
+        }
+    }
+
+    Ok(bracket)
+}
+
+pub fn delete_bracket(conn: &Connection, event_id: i64) -> Result<usize> {
+    conn.execute(r#"DELETE FROM BRACKETS WHERE EVENT_ID=?1"#, params![event_id])
+}
+
+pub fn report_result(conn: &Connection, event_id: i64, report: &MatchReport) -> Result<()> {
+    conn.execute(
+        r#"INSERT OR REPLACE INTO BRACKET_RESULTS(EVENT_ID, MATCH_ID, WINNER, REPORTED_BY, CONFIRMED)
+        VALUES (?1, ?2, ?3, ?4, ?5)"#,
+        params![
+            event_id,
+            report.match_id,
+            report.winner,
+            report.reported_by,
+            report.confirmed
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn confirm_result(conn: &Connection, event_id: i64, match_id: &str) -> Result<usize> {
+    conn.execute(
+        r#"UPDATE BRACKET_RESULTS SET CONFIRMED=1 WHERE EVENT_ID=?1 AND MATCH_ID=?2"#,
+        params![event_id, match_id],
+    )
+}
+
+pub fn get_results(conn: &Connection, event_id: i64) -> Result<Vec<MatchReport>> {
+    let mut statement = conn.prepare(
+        r#"SELECT MATCH_ID, WINNER, REPORTED_BY, CONFIRMED FROM BRACKET_RESULTS
+        WHERE EVENT_ID=?1 ORDER BY MATCH_ID"#,
+    )?;
+    let rows = statement.query_map(params![event_id], |row| {
+        Ok(MatchReport {
+            match_id: row.get(0)?,
+            winner: row.get(1)?,
+            reported_by: row.get(2)?,
+            confirmed: row.get(3)?,
+        })
+    })?;
+
+    rows.collect()
+}
+
+pub fn open_checkin(conn: &Connection, event_id: i64, checkin: &CheckIn) -> Result<()> {
+    conn.execute(
+        r#"INSERT OR REPLACE INTO CHECKINS(EVENT_ID, CHANNEL_ID, MESSAGE_ID, CODE, OPENED_AT, CLOSES_AT)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+        params![
+            event_id,
+            checkin.channel_id,
+            checkin.message_id,
+            checkin.code,
+            checkin.opened_at,
+            checkin.closes_at
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn get_checkin(conn: &Connection, event_id: i64) -> Result<CheckIn> {
+    conn.query_row(
+        r#"SELECT CHANNEL_ID, MESSAGE_ID, CODE, OPENED_AT, CLOSES_AT FROM CHECKINS WHERE EVENT_ID=?1"#,
+        params![event_id],
+        |row| {
+            Ok(CheckIn {
+                channel_id: row.get(0)?,
+                message_id: row.get(1)?,
+                code: row.get(2)?,
+                opened_at: row.get(3)?,
+                closes_at: row.get(4)?,
+            })
+        },
+    )
+}
+
+pub fn close_checkin(conn: &Connection, event_id: i64) -> Result<usize> {
+    conn.execute(r#"DELETE FROM CHECKINS WHERE EVENT_ID=?1"#, params![event_id])
+}
+
+pub fn check_in(conn: &Connection, event_id: i64, user_id: u64, at: i64) -> Result<bool> {
+    let inserted = conn.execute(
+        r#"INSERT OR IGNORE INTO ATTENDANCE(EVENT_ID, USER_ID, CHECKED_IN_AT) VALUES (?1, ?2, ?3)"#,
+        params![event_id, user_id, at],
+    )?;
+    Ok(inserted > 0)
+}
+
+pub fn get_attendance(conn: &Connection, event_id: i64) -> Result<Vec<(u64, i64)>> {
+    let mut statement = conn.prepare(
+        r#"SELECT USER_ID, CHECKED_IN_AT FROM ATTENDANCE WHERE EVENT_ID=?1
+        ORDER BY CHECKED_IN_AT, USER_ID"#,
+    )?;
+    let rows = statement.query_map(params![event_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    rows.collect()
+}
+
+pub fn record_outcome(
+    conn: &Connection,
+    server_id: u64,
+    user_id: u64,
+    event_id: i64,
+    outcome: Outcome,
+    at: i64,
+) -> Result<bool> {
+    let inserted = conn.execute(
+        r#"INSERT OR IGNORE INTO MEMBER_HISTORY(SERVER_ID, USER_ID, EVENT_ID, OUTCOME, RECORDED_AT)
+        VALUES (?1, ?2, ?3, ?4, ?5)"#,
+        params![server_id, user_id, event_id, outcome, at],
+    )?;
+    Ok(inserted > 0)
+}
+
+pub fn get_reliability(conn: &Connection, server_id: u64, user_id: u64) -> Result<Reliability> {
+    let mut statement = conn.prepare(
+        r#"SELECT OUTCOME, COUNT(*) FROM MEMBER_HISTORY WHERE SERVER_ID=?1 AND USER_ID=?2
+        GROUP BY OUTCOME"#,
+    )?;
+    let rows = statement.query_map(params![server_id, user_id], |row| {
+        Ok((row.get::<_, Outcome>(0)?, row.get::<_, u32>(1)?))
+    })?;
+
+    let mut reliability = Reliability::default();
+    for row in rows {
+        let (outcome, times) = row?;
+        reliability.count(outcome, times);
+    }
+    Ok(reliability)
+}
+
+/// Registers the server's event creator role, or replaces it. The server's settings are kept
+pub fn insert_server_manager_role(conn: &Connection, server_id: u64, role_id: u64) -> Result<()>{
+    conn.execute(r#"INSERT INTO SERVERS(
+        SERVER_ID,
+        EVENT_CREATOR_ROLE_ID
+    ) VALUES (?1, ?2)
+    ON CONFLICT(SERVER_ID) DO UPDATE SET EVENT_CREATOR_ROLE_ID=excluded.EVENT_CREATOR_ROLE_ID"#,
+    params![server_id, role_id]
+    )?;
+
+    Ok(())
+}
+
+pub fn get_server_manager_role_id(conn: &Connection, server_id : u64) -> Result<u64>{
+    conn.query_row(r#"SELECT EVENT_CREATOR_ROLE_ID FROM SERVERS WHERE SERVER_ID=?"#, params![server_id],
+    |row| row.get(0))
+}
+
+pub fn get_guild_settings(conn: &Connection, server_id: u64) -> Result<GuildSettings> {
+    conn.query_row(
+        r#"SELECT CREATOR_ROLE_NAME, MANAGER_ROLE_SUFFIX, PLAYER_ROLE_SUFFIX, CHANNEL_LAYOUT,
+            RSVP_EMOJI, ANNOUNCEMENT_TEXT, ANNOUNCEMENT_CHANNEL_ID, THREAD_CHANNEL_ID,
+            ARCHIVE_CATEGORY_ID, ARCHIVE_RETENTION_DAYS, LOG_CHANNEL_ID
+        FROM SERVERS WHERE SERVER_ID=?1"#,
+        params![server_id],
+        |row| {
+            Ok(GuildSettings {
+                creator_role_name: row.get("CREATOR_ROLE_NAME")?,
+                manager_role_suffix: row.get("MANAGER_ROLE_SUFFIX")?,
+                player_role_suffix: row.get("PLAYER_ROLE_SUFFIX")?,
+                channel_layout: row.get("CHANNEL_LAYOUT")?,
+                rsvp_emoji: row.get("RSVP_EMOJI")?,
+                announcement_text: row.get("ANNOUNCEMENT_TEXT")?,
+                announcement_channel_id: row.get("ANNOUNCEMENT_CHANNEL_ID")?,
+                thread_channel_id: row.get("THREAD_CHANNEL_ID")?,
+                archive_category_id: row.get("ARCHIVE_CATEGORY_ID")?,
+                archive_retention_days: row.get("ARCHIVE_RETENTION_DAYS")?,
+                log_channel_id: row.get("LOG_CHANNEL_ID")?,
+            })
+        },
+    )
+}
+
+/// Returns the number of updated servers, 0 if the server was never initialized
+pub fn update_guild_settings(conn: &Connection, server_id: u64, settings: &GuildSettings) -> Result<usize> {
+    conn.execute(
+        r#"UPDATE SERVERS SET
+            CREATOR_ROLE_NAME=?2,
+            MANAGER_ROLE_SUFFIX=?3,
+            PLAYER_ROLE_SUFFIX=?4,
+            CHANNEL_LAYOUT=?5,
+            RSVP_EMOJI=?6,
+            ANNOUNCEMENT_TEXT=?7,
+            ANNOUNCEMENT_CHANNEL_ID=?8,
+            THREAD_CHANNEL_ID=?9,
+            ARCHIVE_CATEGORY_ID=?10,
+            ARCHIVE_RETENTION_DAYS=?11,
+            LOG_CHANNEL_ID=?12
+        WHERE SERVER_ID=?1"#,
+        params![
+            server_id,
+            settings.creator_role_name,
+            settings.manager_role_suffix,
+            settings.player_role_suffix,
+            settings.channel_layout,
+            settings.rsvp_emoji,
+            settings.announcement_text,
+            settings.announcement_channel_id,
+            settings.thread_channel_id,
+            settings.archive_category_id,
+            settings.archive_retention_days,
+            settings.log_channel_id
+        ],
+    )
+}
+
+/// Registers [user_id] as taking part in the event. Joining twice is a no-op
+pub fn insert_participant(conn: &Connection, event_id: i64, user_id: u64) -> Result<()> {
+    conn.execute(
+        r#"INSERT OR IGNORE INTO PARTICIPANTS(EVENT_ID, USER_ID) VALUES (?1, ?2)"#,
+        params![event_id, user_id],
+    )?;
+    Ok(())
+}
+
+pub fn delete_participant(conn: &Connection, event_id: i64, user_id: u64) -> Result<()> {
+    conn.execute(
+        r#"DELETE FROM PARTICIPANTS WHERE EVENT_ID=?1 AND USER_ID=?2"#,
+        params![event_id, user_id],
+    )?;
+    Ok(())
+}
+
+pub fn get_participants(conn: &Connection, event_id: i64) -> Result<Vec<u64>> {
+    let mut statement =
+        conn.prepare(r#"SELECT USER_ID FROM PARTICIPANTS WHERE EVENT_ID=?1 ORDER BY ROWID"#)?;
+    let rows = statement.query_map(params![event_id], |row| row.get::<_, u64>(0))?;
+
+    rows.collect()
+}
+
+/// Saves a template, replacing the server's template of the same name
+pub fn save_template(conn: &Connection, server_id: u64, template: &Template) -> Result<()> {
+    conn.execute(
+        r#"INSERT INTO TEMPLATES(SERVER_ID, NAME, LAYOUT) VALUES (?1, ?2, ?3)
+        ON CONFLICT(SERVER_ID, NAME) DO UPDATE SET LAYOUT=excluded.LAYOUT"#,
+        params![server_id, template.name, template.layout],
+    )?;
+    Ok(())
+}
+
+pub fn get_template(conn: &Connection, server_id: u64, name: &str) -> Result<Template> {
+    conn.query_row(
+        r#"SELECT NAME, LAYOUT FROM TEMPLATES WHERE SERVER_ID=?1 AND NAME=?2"#,
+        params![server_id, name],
+        |row| {
+            Ok(Template {
+                name: row.get(0)?,
+                layout: row.get(1)?,
+            })
+        },
+    )
+}
+
+pub fn get_templates(conn: &Connection, server_id: u64) -> Result<Vec<Template>> {
+    let mut statement =
+        conn.prepare(r#"SELECT NAME, LAYOUT FROM TEMPLATES WHERE SERVER_ID=?1 ORDER BY NAME"#)?;
+    let rows = statement.query_map(params![server_id], |row| {
+        Ok(Template {
+            name: row.get(0)?,
+            layout: row.get(1)?,
+        })
+    })?;
+
+    rows.collect()
+}
+
+/// Returns the number of deleted templates
+pub fn delete_template(conn: &Connection, server_id: u64, name: &str) -> Result<usize> {
+    conn.execute(
+        r#"DELETE FROM TEMPLATES WHERE SERVER_ID=?1 AND NAME=?2"#,
+        params![server_id, name],
+    )
+}
+
+impl DatabasePool {
+    fn with_connection<T>(&self, query: impl FnOnce(&Connection) -> Result<T>) -> StoreResult<T> {
+        let conn = self.get()?;
+        Ok(query(&conn)?)
+    }
+}
+
+impl From<r2d2::Error> for StoreError {
+    fn from(value: r2d2::Error) -> Self {
+        StoreError::Backend(Box::new(value))
+    }
+}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(value: rusqlite::Error) -> Self {
+        match value {
+            rusqlite::Error::QueryReturnedNoRows => StoreError::NotFound,
+            e => StoreError::Backend(Box::new(e)),
+        }
+    }
+}
+
+/// The SQLite backend
+impl EventStore for DatabasePool {
+    fn create_tables(&self) -> StoreResult<()> {
+        self.with_connection(create_tables)
+    }
+
+    fn insert_event(&self, data: EventData) -> StoreResult<i64> {
+        self.with_connection(|conn| insert_event(conn, data))
+    }
+
+    fn delete_event(&self, event_id: i64) -> StoreResult<usize> {
+        self.with_connection(|conn| delete_event(conn, event_id))
+    }
+
+    fn get_event_by_channel(&self, channel_id: u64) -> StoreResult<(i64, EventData)> {
+        self.with_connection(|conn| get_event_by_channel(conn, channel_id))
+    }
+
+    fn get_event_by_manifest(&self, manifest_id: u64) -> StoreResult<(i64, EventData)> {
+        self.with_connection(|conn| get_event_by_manifest(conn, manifest_id))
+    }
+
+    fn get_event_by_id(&self, event_id: i64) -> StoreResult<(i64, EventData)> {
+        self.with_connection(|conn| get_event_by_id(conn, event_id))
+    }
+
+    fn get_event_by_category(&self, category_id: u64) -> StoreResult<(i64, EventData)> {
+        self.with_connection(|conn| get_event_by_category(conn, category_id))
+    }
+
+    fn get_events_by_guild(&self, server_id: u64) -> StoreResult<Vec<(i64, EventData)>> {
+        self.with_connection(|conn| get_events_by_guild(conn, server_id))
+    }
+
+    fn get_timed_voice_events(&self) -> StoreResult<Vec<(i64, EventData)>> {
+        self.with_connection(get_timed_voice_events)
+    }
+
+    fn set_voice_open(&self, event_id: i64, open: bool) -> StoreResult<()> {
+        match self.with_connection(|conn| set_voice_open(conn, event_id, open))? {
+            0 => Err(StoreError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    fn archive_event(&self, event_id: i64, archived_at: i64) -> StoreResult<()> {
+        match self.with_connection(|conn| archive_event(conn, event_id, archived_at))? {
+            0 => Err(StoreError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    fn get_archived_events(&self) -> StoreResult<Vec<(i64, EventData)>> {
+        self.with_connection(get_archived_events)
+    }
+
+    fn set_transcript_exported(&self, event_id: i64, exported_at: i64) -> StoreResult<()> {
+        match self.with_connection(|conn| set_transcript_exported(conn, event_id, exported_at))? {
+            0 => Err(StoreError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    fn set_event_times(&self, event_id: i64, starts_at: Option<i64>, ends_at: Option<i64>) -> StoreResult<()> {
+        match self.with_connection(|conn| set_event_times(conn, event_id, starts_at, ends_at))? {
+            0 => Err(StoreError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    fn insert_event_change(&self, event_id: i64, change: EventChange) -> StoreResult<()> {
+        self.with_connection(|conn| insert_event_change(conn, event_id, &change))
+    }
+
+    fn get_event_changes(&self, event_id: i64) -> StoreResult<Vec<EventChange>> {
+        self.with_connection(|conn| get_event_changes(conn, event_id))
+    }
+
+    fn set_dm_notifications(&self, user_id: u64, enabled: bool) -> StoreResult<()> {
+        self.with_connection(|conn| set_dm_notifications(conn, user_id, enabled))
+    }
+
+    fn get_dm_subscribers(&self, event_id: i64) -> StoreResult<Vec<u64>> {
+        self.with_connection(|conn| get_dm_subscribers(conn, event_id))
+    }
+
+    fn create_poll(&self, event_id: i64, poll: DatePoll) -> StoreResult<()> {
+        self.with_connection(|conn| create_poll(conn, event_id, &poll))
+    }
+
+    fn get_poll(&self, event_id: i64) -> StoreResult<DatePoll> {
+        self.with_connection(|conn| get_poll(conn, event_id))
+    }
+
+    fn set_poll_votes(&self, event_id: i64, user_id: u64, slots: Vec<i64>) -> StoreResult<()> {
+        self.with_connection(|conn| set_poll_votes(conn, event_id, user_id, &slots))
+    }
+
+    fn get_poll_votes(&self, event_id: i64) -> StoreResult<Vec<(i64, u64)>> {
+        self.with_connection(|conn| get_poll_votes(conn, event_id))
+    }
+
+    fn delete_poll(&self, event_id: i64) -> StoreResult<usize> {
+        self.with_connection(|conn| delete_poll(conn, event_id))
+    }
+
+    fn insert_join_request(&self, event_id: i64, user_id: u64, message_id: u64) -> StoreResult<()> {
+        self.with_connection(|conn| insert_join_request(conn, event_id, user_id, message_id))
+    }
+
+    fn get_join_request(&self, event_id: i64, user_id: u64) -> StoreResult<u64> {
+        self.with_connection(|conn| get_join_request(conn, event_id, user_id))
+    }
+
+    fn delete_join_request(&self, event_id: i64, user_id: u64) -> StoreResult<usize> {
+        self.with_connection(|conn| delete_join_request(conn, event_id, user_id))
+    }
+
+    fn get_eligibility(&self, event_id: i64) -> StoreResult<Eligibility> {
+        self.with_connection(|conn| get_eligibility(conn, event_id))
+    }
+
+    fn set_eligibility(&self, event_id: i64, rules: Eligibility) -> StoreResult<()> {
+        self.with_connection(|conn| set_eligibility(conn, event_id, &rules))
+    }
+
+    fn insert_event_ban(&self, event_id: i64, ban: Ban) -> StoreResult<()> {
+        self.with_connection(|conn| insert_event_ban(conn, event_id, &ban))
+    }
+
+    fn delete_event_ban(&self, event_id: i64, user_id: u64) -> StoreResult<usize> {
+        self.with_connection(|conn| delete_event_ban(conn, event_id, user_id))
+    }
+
+    fn get_event_bans(&self, event_id: i64) -> StoreResult<Vec<Ban>> {
+        self.with_connection(|conn| get_event_bans(conn, event_id))
+    }
+
+    fn insert_server_ban(&self, server_id: u64, ban: Ban) -> StoreResult<()> {
+        self.with_connection(|conn| insert_server_ban(conn, server_id, &ban))
+    }
+
+    fn delete_server_ban(&self, server_id: u64, user_id: u64) -> StoreResult<usize> {
+        self.with_connection(|conn| delete_server_ban(conn, server_id, user_id))
+    }
+
+    fn get_server_bans(&self, server_id: u64) -> StoreResult<Vec<Ban>> {
+        self.with_connection(|conn| get_server_bans(conn, server_id))
+    }
+
+    fn get_ban(&self, event_id: i64, user_id: u64) -> StoreResult<Ban> {
+        self.with_connection(|conn| get_ban(conn, event_id, user_id))
+    }
+
+    fn insert_slot(&self, event_id: i64, slot: TeamSlot) -> StoreResult<()> {
+        self.with_connection(|conn| insert_slot(conn, event_id, &slot))
+    }
+
+    fn delete_slot(&self, event_id: i64, name: &str) -> StoreResult<usize> {
+        self.with_connection(|conn| delete_slot(conn, event_id, name))
+    }
+
+    fn get_slots(&self, event_id: i64) -> StoreResult<Vec<TeamSlot>> {
+        self.with_connection(|conn| get_slots(conn, event_id))
+    }
+
+    fn take_slot(&self, event_id: i64, user_id: u64, name: &str) -> StoreResult<bool> {
+        self.with_connection(|conn| take_slot(conn, event_id, user_id, name))
+    }
+
+    fn leave_slot(&self, event_id: i64, user_id: u64) -> StoreResult<usize> {
+        self.with_connection(|conn| leave_slot(conn, event_id, user_id))
+    }
+
+    fn get_slot_members(&self, event_id: i64) -> StoreResult<Vec<(String, u64)>> {
+        self.with_connection(|conn| get_slot_members(conn, event_id))
+    }
+
+    fn set_skill(&self, event_id: i64, user_id: u64, skill: Option<i64>) -> StoreResult<()> {
+        self.with_connection(|conn| set_skill(conn, event_id, user_id, skill))
+    }
+
+    fn get_skills(&self, event_id: i64) -> StoreResult<Vec<(u64, i64)>> {
+        self.with_connection(|conn| get_skills(conn, event_id))
+    }
+
+    fn insert_team(&self, event_id: i64, team: Team) -> StoreResult<()> {
+        self.with_connection(|conn| insert_team(conn, event_id, &team))
+    }
+
+    fn get_teams(&self, event_id: i64) -> StoreResult<Vec<Team>> {
+        self.with_connection(|conn| get_teams(conn, event_id))
+    }
+
+    fn delete_teams(&self, event_id: i64) -> StoreResult<usize> {
+        self.with_connection(|conn| delete_teams(conn, event_id))
+    }
+
+    fn create_bracket(&self, event_id: i64, bracket: Bracket) -> StoreResult<()> {
+        self.with_connection(|conn| create_bracket(conn, event_id, &bracket))
+    }
+
+    fn get_bracket(&self, event_id: i64) -> StoreResult<Bracket> {
+        self.with_connection(|conn| get_bracket(conn, event_id))
+    }
+
+    fn delete_bracket(&self, event_id: i64) -> StoreResult<usize> {
+        self.with_connection(|conn| delete_bracket(conn, event_id))
+    }
+
+    fn report_result(&self, event_id: i64, report: MatchReport) -> StoreResult<()> {
+        self.with_connection(|conn| report_result(conn, event_id, &report))
+    }
+
+    fn confirm_result(&self, event_id: i64, match_id: &str) -> StoreResult<usize> {
+        self.with_connection(|conn| confirm_result(conn, event_id, match_id))
+    }
+
+    fn get_results(&self, event_id: i64) -> StoreResult<Vec<MatchReport>> {
+        self.with_connection(|conn| get_results(conn, event_id))
+    }
+
+    fn open_checkin(&self, event_id: i64, checkin: CheckIn) -> StoreResult<()> {
+        self.with_connection(|conn| open_checkin(conn, event_id, &checkin))
+    }
+
+    fn get_checkin(&self, event_id: i64) -> StoreResult<CheckIn> {
+        self.with_connection(|conn| get_checkin(conn, event_id))
+    }
+
+    fn close_checkin(&self, event_id: i64) -> StoreResult<usize> {
+        self.with_connection(|conn| close_checkin(conn, event_id))
+    }
+
+    fn check_in(&self, event_id: i64, user_id: u64, at: i64) -> StoreResult<bool> {
+        self.with_connection(|conn| check_in(conn, event_id, user_id, at))
+    }
+
+    fn get_attendance(&self, event_id: i64) -> StoreResult<Vec<(u64, i64)>> {
+        self.with_connection(|conn| get_attendance(conn, event_id))
+    }
+
+    fn record_outcome(
+        &self,
+        server_id: u64,
+        user_id: u64,
+        event_id: i64,
+        outcome: Outcome,
+        at: i64,
+    ) -> StoreResult<bool> {
+        self.with_connection(|conn| record_outcome(conn, server_id, user_id, event_id, outcome, at))
+    }
+
+    fn get_reliability(&self, server_id: u64, user_id: u64) -> StoreResult<Reliability> {
+        self.with_connection(|conn| get_reliability(conn, server_id, user_id))
+    }
+
+    fn set_status(&self, event_id: i64, status: EventStatus) -> StoreResult<()> {
+        match self.with_connection(|conn| set_status(conn, event_id, status))? {
+            0 => Err(StoreError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    fn set_manifest(&self, event_id: i64, channel_id: u64, manifest_id: u64) -> StoreResult<()> {
+        match self.with_connection(|conn| set_manifest(conn, event_id, channel_id, manifest_id))? {
+            0 => Err(StoreError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    fn insert_channels(&self, event_id: i64, channels: Vec<u64>) -> StoreResult<()> {
+        self.with_connection(|conn| insert_channels(conn, event_id, channels))
+    }
+
+    fn get_channels_by_event_id(&self, event_id: i64) -> StoreResult<Vec<u64>> {
+        self.with_connection(|conn| get_channels_by_event_id(conn, event_id))
+    }
+
+    fn delete_channel(&self, event_id: i64, channel_id: u64) -> StoreResult<usize> {
+        self.with_connection(|conn| delete_channel(conn, event_id, channel_id))
+    }
+
+    fn insert_server_manager_role(&self, server_id: u64, role_id: u64) -> StoreResult<()> {
+        self.with_connection(|conn| insert_server_manager_role(conn, server_id, role_id))
+    }
+
+    fn get_server_manager_role_id(&self, server_id: u64) -> StoreResult<u64> {
+        self.with_connection(|conn| get_server_manager_role_id(conn, server_id))
+    }
+
+    fn get_guild_settings(&self, server_id: u64) -> StoreResult<GuildSettings> {
+        self.with_connection(|conn| get_guild_settings(conn, server_id))
+    }
+
+    fn set_guild_settings(&self, server_id: u64, settings: GuildSettings) -> StoreResult<()> {
+        match self.with_connection(|conn| update_guild_settings(conn, server_id, &settings))? {
+            0 => Err(StoreError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    fn insert_participant(&self, event_id: i64, user_id: u64) -> StoreResult<()> {
+        self.with_connection(|conn| insert_participant(conn, event_id, user_id))
+    }
+
+    fn delete_participant(&self, event_id: i64, user_id: u64) -> StoreResult<()> {
+        self.with_connection(|conn| delete_participant(conn, event_id, user_id))
+    }
+
+    fn get_participants(&self, event_id: i64) -> StoreResult<Vec<u64>> {
+        self.with_connection(|conn| get_participants(conn, event_id))
+    }
+
+    fn save_template(&self, server_id: u64, template: Template) -> StoreResult<()> {
+        self.with_connection(|conn| save_template(conn, server_id, &template))
+    }
+
+    fn get_template(&self, server_id: u64, name: &str) -> StoreResult<Template> {
+        self.with_connection(|conn| get_template(conn, server_id, name))
+    }
+
+    fn get_templates(&self, server_id: u64) -> StoreResult<Vec<Template>> {
+        self.with_connection(|conn| get_templates(conn, server_id))
+    }
+
+    fn delete_template(&self, server_id: u64, name: &str) -> StoreResult<usize> {
+        self.with_connection(|conn| delete_template(conn, server_id, name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_transitions() {
+        use EventStatus::*;
+        assert!(Draft.can_become(Published));
+        assert!(Published.can_become(Completed));
+        assert!(Published.can_become(Cancelled));
+        assert!(!Published.can_become(Draft));
+        assert!(!Cancelled.can_become(Published));
+        assert!(!Completed.can_become(Cancelled));
+        assert!(!Draft.can_become(Completed));
+    }
+
+    #[test]
+    fn corrupt_events_are_errors() {
+        let pool = DatabasePool::in_memory().unwrap();
+        pool.create_tables().unwrap();
+        let id = pool
+            .insert_event(crate::store::tests::sample_event(1, 100))
+            .unwrap();
+        pool.insert_event(crate::store::tests::sample_event(1, 101))
+            .unwrap();
+        pool.with_connection(|conn| {
+            conn.execute(r#"UPDATE EVENTS SET MODE='unknown' WHERE ID=?1"#, params![id])
+        })
+        .unwrap();
+
+        // Lookups must not just leave a corrupt event out
+        assert!(pool.get_events_by_guild(1).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use poise::serenity_prelude::ChannelType;
+
+/// Channels Discord lets a category hold
+pub const MAX_CATEGORY_CHANNELS: usize = 50;
+
+/// The kinds of channel an event can be provisioned with
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelKind {
+    #[name = "text"]
+    Text,
+    #[name = "voice"]
+    Voice,
+    #[name = "stage"]
+    Stage,
+    #[name = "forum"]
+    Forum,
+    /// A thread of the closest text channel before it in the layout
+    #[name = "thread"]
+    Thread,
+}
+
+impl ChannelKind {
+    fn parse(kind: &str) -> Option<Self> {
+        match kind.trim().to_lowercase().as_str() {
+            "text" => Some(ChannelKind::Text),
+            "voice" => Some(ChannelKind::Voice),
+            "stage" => Some(ChannelKind::Stage),
+            "forum" => Some(ChannelKind::Forum),
+            "thread" => Some(ChannelKind::Thread),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChannelKind::Text => "text",
+            ChannelKind::Voice => "voice",
+            ChannelKind::Stage => "stage",
+            ChannelKind::Forum => "forum",
+            ChannelKind::Thread => "thread",
+        }
+    }
+
+    pub fn channel_type(self) -> ChannelType {
+        match self {
+            ChannelKind::Text => ChannelType::Text,
+            ChannelKind::Voice => ChannelType::Voice,
+            ChannelKind::Stage => ChannelType::Stage,
+            ChannelKind::Forum => ChannelType::Forum,
+            ChannelKind::Thread => ChannelType::PublicThread,
+        }
+    }
+}
+
+/// A channel created inside every new event's category
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChannelSpec {
+    pub name: String,
+    pub kind: ChannelKind,
+}
+
+/// A named channel layout saved by a server, picked with /event create template:<name>
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    pub name: String,
+    pub layout: String,
+}
+
+/// Parses a layout such as `general, lobby:voice, dice-rolls:thread`.
+/// Channels are text channels unless a kind is given after a colon.
+/// A layout needs at least one text channel, event commands are ran from there.
+/// Threads are opened in the closest text channel before them
+pub fn parse_layout(layout: &str) -> Result<Vec<ChannelSpec>, String> {
+    let channels = layout
+        .split(',')
+        .map(|entry| {
+            let (name, kind) = match entry.split_once(':') {
+                Some((name, kind)) => (
+                    name,
+                    ChannelKind::parse(kind).ok_or_else(|| {
+                        format!(
+                            "unknown channel kind `{}` for `{}` (expected text, voice, stage, forum or thread)",
+                            kind.trim(),
+                            name.trim()
+                        )
+                    })?,
+                ),
+                None => (entry, ChannelKind::Text),
+            };
+
+            let name = name.trim();
+            if name.is_empty() || name.chars().count() > 100 {
+                return Err(format!(
+                    "channel names must be between 1 and 100 characters, got `{}`",
+                    name
+                ));
+            }
+
+            Ok(ChannelSpec {
+                name: name.to_string(),
+                kind,
+            })
+        })
+        .collect::<Result<Vec<ChannelSpec>, String>>()?;
+
+    if !channels.iter().any(|c| c.kind == ChannelKind::Text) {
+        return Err(String::from("a layout needs at least one text channel"));
+    }
+    if let Some(thread) = channels
+        .iter()
+        .take_while(|c| c.kind != ChannelKind::Text)
+        .find(|c| c.kind == ChannelKind::Thread)
+    {
+        return Err(format!(
+            "the thread `{}` must come after a text channel",
+            thread.name
+        ));
+    }
+
+    Ok(channels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_kinds_and_trims_names() {
+        assert_eq!(
+            parse_layout(" general , lobby : Voice").unwrap(),
+            vec![
+                ChannelSpec {
+                    name: String::from("general"),
+                    kind: ChannelKind::Text
+                },
+                ChannelSpec {
+                    name: String::from("lobby"),
+                    kind: ChannelKind::Voice
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_layouts() {
+        assert!(parse_layout("lobby:voice").is_err());
+        assert!(parse_layout("general, :voice").is_err());
+        assert!(parse_layout("general,").is_err());
+        assert!(parse_layout("general:category").is_err());
+        assert!(parse_layout("dice:thread, general").is_err());
+    }
+
+    #[test]
+    fn parses_templates() {
+        let kinds = parse_layout("general, voice:voice, logistics, stage:stage, dice:thread")
+            .unwrap()
+            .into_iter()
+            .map(|c| c.kind)
+            .collect::<Vec<ChannelKind>>();
+        assert_eq!(
+            kinds,
+            vec![
+                ChannelKind::Text,
+                ChannelKind::Voice,
+                ChannelKind::Text,
+                ChannelKind::Stage,
+                ChannelKind::Thread
+            ]
+        );
+    }
+}
@@ -1,52 +1,122 @@
+mod approval;
+mod archive;
 mod bacchus;
 mod events;
 mod bacchus_handler;
+mod bracket;
+mod bracket_image;
+mod checkin;
+mod composition;
+mod config;
+mod health;
+mod layout;
+mod manifest;
+mod memory_store;
+mod migrations;
+mod notifications;
+mod participation;
+mod polls;
+#[cfg(feature = "postgres")]
+mod postgres_store;
+mod reliability;
+mod repository;
+mod scheduler;
+mod settings;
+mod store;
+mod teams;
+mod transcript;
 
-use crate::bacchus::{event, init, Data};
-use crate::events::{create_tables, DatabasePool};
+use crate::bacchus::{dm_notifications, event, eventban, init, settings, template, Data};
 use dotenv::dotenv;
 use poise::serenity_prelude as serenity;
 use poise::serenity_prelude::GatewayIntents;
 use crate::bacchus_handler::{BacchusHandler, DBWrapper};
+use crate::config::Config;
+use crate::repository::Repository;
+use crate::store::open_store;
+use std::sync::Arc;
+use tracing::error;
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
 
-    let token = std::env::var("DISCORD_TOKEN").expect("missing DISCORD_TOKEN");
+    let config = match Config::load() {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            std::process::exit(2);
+        }
+    };
+
+    tracing_subscriber::fmt()
+        .with_max_level(config.log_level)
+        .init();
+
+    // Voice states tell who to disconnect when timed voice channels close
     let intents = GatewayIntents::GUILDS
         | GatewayIntents::GUILD_MESSAGE_REACTIONS
+        | GatewayIntents::GUILD_VOICE_STATES
         | GatewayIntents::MESSAGE_CONTENT
         | GatewayIntents::MESSAGE_CONTENT;
 
-    let conn = DatabasePool::new(&std::env::args().nth(1).expect("Specify a database path"))
-        .expect("Failed to open db");
-    create_tables(&conn.get().unwrap()).expect("Couldn't initialize tables");
+    let store = open_store(&config.database_url).expect("Failed to open db");
 
-    let conn2 = conn.clone();
+    let db = Repository::new(store);
+    db.create_tables()
+        .await
+        .expect("Couldn't initialize tables");
+    let db2 = db.clone();
+    let config2 = config.clone();
+
+    if let Some(port) = config.http_port {
+        tokio::spawn(async move {
+            if let Err(e) = health::serve(port).await {
+                error!("Health check endpoint stopped: {}", e);
+            }
+        });
+    }
 
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
-            commands: vec![event(), init()],
+            commands: vec![
+                event(),
+                eventban(),
+                init(),
+                dm_notifications(),
+                settings(),
+                template(),
+            ],
             ..Default::default()
         })
         .setup(|ctx, _ready, framework| {
             Box::pin(async move {
-                poise::builtins::register_globally(ctx, &framework.options().commands).await?;
-                Ok(Data { conn: conn2 }) //Share the db with the command handlers
+                match config2.dev_guild {
+                    Some(guild_id) => {
+                        poise::builtins::register_in_guild(ctx, &framework.options().commands, guild_id).await?
+                    }
+                    None => poise::builtins::register_globally(ctx, &framework.options().commands).await?,
+                }
+                Ok(Data { db: db2, config: config2 }) //Share the db with the command handlers
             })
         })
         .build();
 
-    let mut client = serenity::ClientBuilder::new(token, intents)
+    let mut client = serenity::ClientBuilder::new(&config.token, intents)
         .framework(framework)
         .event_handler(BacchusHandler)
         .await
         .expect("Error creating client");
 
+    tokio::spawn(scheduler::run(
+        client.http.clone(),
+        client.cache.clone(),
+        db.clone(),
+    ));
+
     // Share the DB with the event handlers
     let mut data = client.data.write().await;
-    data.insert::<DBWrapper>(DBWrapper{pool: conn});
+    data.insert::<DBWrapper>(DBWrapper { db });
     drop(data);
 
     client.start().await.unwrap();
@@ -0,0 +1,316 @@
+use crate::bacchus::Error;
+use crate::events::{EventData, EventStatus};
+use crate::scheduler::format_time;
+use poise::serenity_prelude::{
+    ChannelId, ChannelType, Colour, CreateEmbed, CreateEmbedFooter, CreateMessage, EditMessage,
+    Embed, GuildChannel, Http, Message, MessageId, ReactionType, UserId,
+};
+use tracing::{info, warn};
+
+const STARTS: &str = "Starts";
+const ENDS: &str = "Ends";
+const COMPOSITION: &str = "Composition";
+
+/// The embed announcing [event]
+pub fn manifest_embed(event: &EventData, creator: &str) -> CreateEmbed {
+    let mut embed = CreateEmbed::new()
+        .description(event.description.clone().unwrap_or_default())
+        .field("Creator", creator, true);
+
+    if let Some(pic) = &event.picture {
+        embed = embed.image(pic);
+    }
+
+    if let Some(thumb) = &event.thumbnail {
+        embed = embed.thumbnail(thumb)
+    }
+
+    if let Some(decr) = &event.short_description {
+        embed = embed.field("Summary", decr, false);
+    }
+
+    restyle(with_times(embed, event), event)
+}
+
+fn with_times(mut embed: CreateEmbed, event: &EventData) -> CreateEmbed {
+    if let Some(start) = event.starts_at {
+        embed = embed.field(STARTS, format_time(start), true);
+    }
+
+    if let Some(end) = event.ends_at {
+        embed = embed.field(ENDS, format_time(end), true);
+    }
+
+    embed
+}
+
+/// A posted manifest's embed, updated with the event's current times and status.
+/// The composition stays last
+pub fn rebuild(mut embed: Embed, event: &EventData) -> CreateEmbed {
+    let composition = embed
+        .fields
+        .iter()
+        .find(|field| field.name == COMPOSITION)
+        .map(|field| field.value.clone());
+    embed.fields.retain(|field| {
+        field.name != STARTS && field.name != ENDS && field.name != COMPOSITION
+    });
+
+    let embed = with_times(CreateEmbed::from(embed), event);
+    let embed = match composition {
+        Some(composition) => embed.field(COMPOSITION, composition, false),
+        None => embed,
+    };
+    restyle(embed, event)
+}
+
+/// A posted manifest's embed showing [composition] instead of its previous one, or none
+pub fn recompose(mut embed: Embed, composition: Option<String>) -> CreateEmbed {
+    embed.fields.retain(|field| field.name != COMPOSITION);
+    let embed = CreateEmbed::from(embed);
+    match composition {
+        Some(composition) => embed.field(COMPOSITION, composition, false),
+        None => embed,
+    }
+}
+
+/// Sets the parts of a manifest's embed that follow the event's status
+pub fn restyle(embed: CreateEmbed, event: &EventData) -> CreateEmbed {
+    let (title, footer, colour) = match event.status {
+        EventStatus::Draft => (
+            format!("[DRAFT] {}", event.name),
+            String::from("Not announced yet, see /event publish"),
+            Colour::LIGHT_GREY,
+        ),
+        EventStatus::Published => (
+            event.name.clone(),
+            match event.requests_channel_id {
+                Some(_) => format!(
+                    "React with {} to ask the managers to let you in",
+                    event.rsvp_emoji
+                ),
+                None => format!("React with {} to join the event", event.rsvp_emoji),
+            },
+            Colour::BLURPLE,
+        ),
+        EventStatus::Cancelled => (
+            format!("~~{}~~", event.name),
+            String::from("This event was cancelled"),
+            Colour::RED,
+        ),
+        EventStatus::Completed => (
+            format!("{} (completed)", event.name),
+            String::from("This event is over, thanks for coming !"),
+            Colour::DARK_GREY,
+        ),
+    };
+
+    embed
+        .title(title)
+        .footer(CreateEmbedFooter::new(footer))
+        .colour(colour)
+}
+
+/// The text above the embed: the server's announcement while the event is live, its state otherwise
+pub fn banner(status: EventStatus, announcement_text: &str) -> String {
+    match status {
+        EventStatus::Draft => String::from(
+            ":pencil: DRAFT, only the event's members can see this. Announce it with /event publish",
+        ),
+        EventStatus::Published => announcement_text.to_string(),
+        EventStatus::Cancelled => String::from(":no_entry: CANCELLED :no_entry:"),
+        EventStatus::Completed => String::from(":checkered_flag: This event is over"),
+    }
+}
+
+/// Posts a manifest in [channel]. Announcement channels share it with the servers following them,
+/// and published events get the reaction people click to join
+pub async fn post(
+    http: &Http,
+    channel: &GuildChannel,
+    event: &EventData,
+    message: CreateMessage,
+) -> Result<Message, Error> {
+    let answer = channel.id.send_message(http, message).await?;
+
+    info!(
+        "Posted embed regarding event {} on server {}",
+        event.name, channel.guild_id
+    );
+
+    if event.status != EventStatus::Published {
+        return Ok(answer);
+    }
+
+    if channel.kind == ChannelType::News {
+        if let Err(e) = answer.crosspost(http).await {
+            warn!(
+                "Couldn't crosspost the embed of event {} on server {}: {}",
+                event.name, channel.guild_id, e
+            );
+        }
+    }
+
+    answer
+        .react(http, ReactionType::try_from(event.rsvp_emoji.as_str())?)
+        .await?;
+
+    info!(
+        "Reacted to embed regarding event {} on server {}",
+        event.name, channel.guild_id
+    );
+
+    Ok(answer)
+}
+
+/// Removes [user_id]'s reaction to the manifest of [event], for people it turned away
+pub async fn unreact(http: &Http, event: &EventData, user_id: UserId) {
+    let emoji = match ReactionType::try_from(event.rsvp_emoji.as_str()) {
+        Ok(emoji) => emoji,
+        Err(_) => return,
+    };
+    if let Err(e) = ChannelId::new(event.manifest_channel_id)
+        .delete_reaction(http, MessageId::new(event.manifest_id), Some(user_id), emoji)
+        .await
+    {
+        warn!(
+            "Couldn't remove the reaction of {} on event {}: {}",
+            user_id, event.name, e
+        );
+    }
+}
+
+/// Updates the posted manifest of [event] after it changed.
+/// Only published events keep the menus people join with
+pub async fn refresh(http: &Http, event: &EventData, announcement_text: &str) -> Result<(), Error> {
+    let channel_id = ChannelId::new(event.manifest_channel_id);
+    let manifest_id = MessageId::new(event.manifest_id);
+    let message = channel_id.message(http, manifest_id).await?;
+
+    let embeds: Vec<CreateEmbed> = message
+        .embeds
+        .into_iter()
+        .map(|embed| rebuild(embed, event))
+        .collect();
+    let mut edit = EditMessage::new()
+        .content(banner(event.status, announcement_text))
+        .embeds(embeds);
+    if event.status != EventStatus::Published {
+        edit = edit.components(vec![]);
+    }
+    channel_id.edit_message(http, manifest_id, edit).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventMode;
+
+    fn event(status: EventStatus) -> EventData {
+        EventData {
+            name: String::from("LAN"),
+            short_description: Some(String::from("Bring your computer")),
+            description: None,
+            thumbnail: None,
+            picture: None,
+            max_participants: None,
+            server_id: 1,
+            manager_role_id: 2,
+            participant_role_id: 3,
+            manifest_id: 4,
+            manifest_channel_id: 5,
+            category_id: 6,
+            rsvp_emoji: String::from("🎉"),
+            starts_at: Some(1_700_000_000),
+            ends_at: None,
+            timed_voice: false,
+            voice_open: true,
+            mode: EventMode::Category,
+            archived_at: None,
+            status,
+            requests_channel_id: None,
+            transcript_exported_at: None,
+        }
+    }
+
+    fn json(embed: CreateEmbed) -> serde_json::Value {
+        serde_json::to_value(embed).unwrap()
+    }
+
+    #[test]
+    fn styles_follow_status() {
+        let published = json(manifest_embed(&event(EventStatus::Published), "alice"));
+        assert_eq!(published["title"], "LAN");
+        assert_eq!(
+            published["footer"]["text"],
+            "React with 🎉 to join the event"
+        );
+        assert_eq!(published["fields"][2]["value"], "<t:1700000000:F>");
+
+        let cancelled = json(manifest_embed(&event(EventStatus::Cancelled), "alice"));
+        assert_eq!(cancelled["title"], "~~LAN~~");
+        assert_eq!(cancelled["color"], Colour::RED.0);
+
+        let draft = json(manifest_embed(&event(EventStatus::Draft), "alice"));
+        assert_eq!(draft["title"], "[DRAFT] LAN");
+
+        let mut invite_only = event(EventStatus::Published);
+        invite_only.requests_channel_id = Some(7);
+        assert_eq!(
+            json(manifest_embed(&invite_only, "alice"))["footer"]["text"],
+            "React with 🎉 to ask the managers to let you in"
+        );
+    }
+
+    #[test]
+    fn rebuilds_times() {
+        let posted: Embed = serde_json::from_value(json(manifest_embed(
+            &event(EventStatus::Published),
+            "alice",
+        )))
+        .unwrap();
+
+        let mut moved = event(EventStatus::Published);
+        moved.starts_at = Some(1_800_000_000);
+        moved.ends_at = Some(1_800_003_600);
+        let rebuilt = json(rebuild(posted, &moved));
+        let fields = rebuilt["fields"].as_array().unwrap();
+        assert_eq!(fields.len(), 4);
+        assert_eq!(fields[0]["name"], "Creator");
+        assert_eq!(fields[1]["name"], "Summary");
+        assert_eq!(fields[2]["value"], "<t:1800000000:F>");
+        assert_eq!(fields[3]["value"], "<t:1800003600:F>");
+    }
+
+    #[test]
+    fn composition_stays_last() {
+        let posted: Embed = serde_json::from_value(json(manifest_embed(
+            &event(EventStatus::Published),
+            "alice",
+        )))
+        .unwrap();
+        let composed: Embed = serde_json::from_value(json(recompose(
+            posted,
+            Some(String::from("**Tank** 0/2")),
+        )))
+        .unwrap();
+
+        let mut moved = event(EventStatus::Published);
+        moved.ends_at = Some(1_700_003_600);
+        let rebuilt: Embed = serde_json::from_value(json(rebuild(composed, &moved))).unwrap();
+        let names: Vec<&str> = rebuilt.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["Creator", "Summary", STARTS, ENDS, COMPOSITION]);
+        assert_eq!(rebuilt.fields[4].value, "**Tank** 0/2");
+
+        let cleared = json(recompose(rebuilt, None));
+        assert_eq!(cleared["fields"].as_array().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn banners() {
+        assert_eq!(banner(EventStatus::Published, "NEW EVENT"), "NEW EVENT");
+        assert!(banner(EventStatus::Cancelled, "NEW EVENT").contains("CANCELLED"));
+    }
+}
@@ -0,0 +1,825 @@
+use crate::events::{Ban, DatePoll, Eligibility, EventChange, EventData, EventStatus, Team, TeamSlot};
+use crate::events::{Bracket, CheckIn, MatchReport, Outcome, Reliability};
+use crate::layout::Template;
+use crate::settings::GuildSettings;
+use crate::store::{EventStore, StoreError, StoreResult};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Mutex, MutexGuard};
+
+/// A store that lives and dies with the process.
+/// Meant for tests and for trying the bot out without a database file
+#[derive(Default)]
+pub struct MemoryStore {
+    tables: Mutex<Tables>,
+}
+
+#[derive(Default)]
+struct Tables {
+    last_event_id: i64,
+    events: BTreeMap<i64, EventData>,
+    /// (event id, channel id)
+    channels: Vec<(i64, u64)>,
+    /// server id -> (event creator role id, settings)
+    servers: HashMap<u64, (u64, GuildSettings)>,
+    /// (event id, user id), in joining order
+    participants: Vec<(i64, u64)>,
+    /// (server id, name) -> layout
+    templates: BTreeMap<(u64, String), String>,
+    changes: Vec<(i64, EventChange)>,
+    dm_subscribers: BTreeSet<u64>,
+    /// event id -> poll
+    polls: BTreeMap<i64, DatePoll>,
+    /// (event id, slot, user id)
+    poll_votes: BTreeSet<(i64, i64, u64)>,
+    /// (event id, user id) -> request message id
+    join_requests: BTreeMap<(i64, u64), u64>,
+    eligibility: BTreeMap<i64, Eligibility>,
+    /// (event id, user id) -> ban
+    event_bans: BTreeMap<(i64, u64), Ban>,
+    /// (server id, user id) -> ban
+    server_bans: BTreeMap<(u64, u64), Ban>,
+    /// (event id, slot), in the order they were added
+    slots: Vec<(i64, TeamSlot)>,
+    /// (event id, user id, slot name), in the order the slots were taken
+    slot_members: Vec<(i64, u64, String)>,
+    /// (event id, user id) -> skill
+    skills: BTreeMap<(i64, u64), i64>,
+    /// (event id, team number) -> team
+    teams: BTreeMap<(i64, u32), Team>,
+    /// event id -> bracket
+    brackets: BTreeMap<i64, Bracket>,
+    /// (event id, match id) -> report
+    results: BTreeMap<(i64, String), MatchReport>,
+    /// event id -> check-in window
+    checkins: BTreeMap<i64, CheckIn>,
+    /// (event id, user id) -> check-in time
+    attendance: BTreeMap<(i64, u64), i64>,
+    /// (server id, user id, event id, outcome) -> when it was recorded. Outlives the events
+    history: BTreeMap<(u64, u64, i64, &'static str), i64>,
+}
+
+/// Oldest ban first, like the SQL stores
+fn sorted_bans<'a>(bans: impl Iterator<Item = &'a Ban>) -> Vec<Ban> {
+    let mut bans: Vec<Ban> = bans.cloned().collect();
+    bans.sort_by_key(|ban| (ban.banned_at, ban.user_id));
+    bans
+}
+
+impl MemoryStore {
+    fn tables(&self) -> MutexGuard<'_, Tables> {
+        // A panic while holding the lock can't leave the tables half-written, keep going
+        self.tables.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl EventStore for MemoryStore {
+    fn create_tables(&self) -> StoreResult<()> {
+        Ok(())
+    }
+
+    fn insert_event(&self, data: EventData) -> StoreResult<i64> {
+        let mut tables = self.tables();
+        tables.last_event_id += 1;
+        let id = tables.last_event_id;
+        tables.events.insert(id, data);
+        Ok(id)
+    }
+
+    fn delete_event(&self, event_id: i64) -> StoreResult<usize> {
+        let mut tables = self.tables();
+        if tables.events.remove(&event_id).is_none() {
+            return Ok(0);
+        }
+        tables.channels.retain(|(event, _)| *event != event_id);
+        tables.participants.retain(|(event, _)| *event != event_id);
+        tables.changes.retain(|(event, _)| *event != event_id);
+        tables.polls.remove(&event_id);
+        tables.poll_votes.retain(|(event, _, _)| *event != event_id);
+        tables.join_requests.retain(|(event, _), _| *event != event_id);
+        tables.eligibility.remove(&event_id);
+        tables.event_bans.retain(|(event, _), _| *event != event_id);
+        tables.slots.retain(|(event, _)| *event != event_id);
+        tables.slot_members.retain(|(event, _, _)| *event != event_id);
+        tables.skills.retain(|(event, _), _| *event != event_id);
+        tables.teams.retain(|(event, _), _| *event != event_id);
+        tables.brackets.remove(&event_id);
+        tables.results.retain(|(event, _), _| *event != event_id);
+        tables.checkins.remove(&event_id);
+        tables.attendance.retain(|(event, _), _| *event != event_id);
+        Ok(1)
+    }
+
+    fn get_event_by_channel(&self, channel_id: u64) -> StoreResult<(i64, EventData)> {
+        let tables = self.tables();
+        tables
+            .channels
+            .iter()
+            .find(|(_, channel)| *channel == channel_id)
+            .and_then(|(id, _)| tables.events.get(id).map(|event| (*id, event.clone())))
+            .ok_or(StoreError::NotFound)
+    }
+
+    fn get_event_by_manifest(&self, manifest_id: u64) -> StoreResult<(i64, EventData)> {
+        self.tables()
+            .events
+            .iter()
+            .find(|(_, event)| event.manifest_id == manifest_id)
+            .map(|(id, event)| (*id, event.clone()))
+            .ok_or(StoreError::NotFound)
+    }
+
+    fn get_event_by_id(&self, event_id: i64) -> StoreResult<(i64, EventData)> {
+        self.tables()
+            .events
+            .get(&event_id)
+            .map(|event| (event_id, event.clone()))
+            .ok_or(StoreError::NotFound)
+    }
+
+    fn get_event_by_category(&self, category_id: u64) -> StoreResult<(i64, EventData)> {
+        self.tables()
+            .events
+            .iter()
+            .find(|(_, event)| event.category_id == category_id)
+            .map(|(id, event)| (*id, event.clone()))
+            .ok_or(StoreError::NotFound)
+    }
+
+    fn get_events_by_guild(&self, server_id: u64) -> StoreResult<Vec<(i64, EventData)>> {
+        Ok(self
+            .tables()
+            .events
+            .iter()
+            .filter(|(_, event)| event.server_id == server_id)
+            .map(|(id, event)| (*id, event.clone()))
+            .collect())
+    }
+
+    fn get_timed_voice_events(&self) -> StoreResult<Vec<(i64, EventData)>> {
+        Ok(self
+            .tables()
+            .events
+            .iter()
+            .filter(|(_, event)| {
+                event.timed_voice
+                    && event.archived_at.is_none()
+                    && matches!(event.status, EventStatus::Draft | EventStatus::Published)
+            })
+            .map(|(id, event)| (*id, event.clone()))
+            .collect())
+    }
+
+    fn set_voice_open(&self, event_id: i64, open: bool) -> StoreResult<()> {
+        match self.tables().events.get_mut(&event_id) {
+            Some(event) => {
+                event.voice_open = open;
+                Ok(())
+            }
+            None => Err(StoreError::NotFound),
+        }
+    }
+
+    fn archive_event(&self, event_id: i64, archived_at: i64) -> StoreResult<()> {
+        match self.tables().events.get_mut(&event_id) {
+            Some(event) => {
+                event.archived_at = Some(archived_at);
+                Ok(())
+            }
+            None => Err(StoreError::NotFound),
+        }
+    }
+
+    fn set_transcript_exported(&self, event_id: i64, exported_at: i64) -> StoreResult<()> {
+        match self.tables().events.get_mut(&event_id) {
+            Some(event) => {
+                event.transcript_exported_at = Some(exported_at);
+                Ok(())
+            }
+            None => Err(StoreError::NotFound),
+        }
+    }
+
+    fn set_event_times(
+        &self,
+        event_id: i64,
+        starts_at: Option<i64>,
+        ends_at: Option<i64>,
+    ) -> StoreResult<()> {
+        match self.tables().events.get_mut(&event_id) {
+            Some(event) => {
+                event.starts_at = starts_at;
+                event.ends_at = ends_at;
+                Ok(())
+            }
+            None => Err(StoreError::NotFound),
+        }
+    }
+
+    fn insert_event_change(&self, event_id: i64, change: EventChange) -> StoreResult<()> {
+        self.tables().changes.push((event_id, change));
+        Ok(())
+    }
+
+    fn get_event_changes(&self, event_id: i64) -> StoreResult<Vec<EventChange>> {
+        Ok(self
+            .tables()
+            .changes
+            .iter()
+            .filter(|(event, _)| *event == event_id)
+            .map(|(_, change)| change.clone())
+            .collect())
+    }
+
+    fn set_status(&self, event_id: i64, status: EventStatus) -> StoreResult<()> {
+        match self.tables().events.get_mut(&event_id) {
+            Some(event) => {
+                event.status = status;
+                Ok(())
+            }
+            None => Err(StoreError::NotFound),
+        }
+    }
+
+    fn set_manifest(&self, event_id: i64, channel_id: u64, manifest_id: u64) -> StoreResult<()> {
+        match self.tables().events.get_mut(&event_id) {
+            Some(event) => {
+                event.manifest_channel_id = channel_id;
+                event.manifest_id = manifest_id;
+                Ok(())
+            }
+            None => Err(StoreError::NotFound),
+        }
+    }
+
+    fn get_archived_events(&self) -> StoreResult<Vec<(i64, EventData)>> {
+        let mut events: Vec<(i64, EventData)> = self
+            .tables()
+            .events
+            .iter()
+            .filter(|(_, event)| event.archived_at.is_some())
+            .map(|(id, event)| (*id, event.clone()))
+            .collect();
+        events.sort_by_key(|(id, event)| (event.archived_at, *id));
+        Ok(events)
+    }
+
+    fn insert_channels(&self, event_id: i64, channels: Vec<u64>) -> StoreResult<()> {
+        let mut tables = self.tables();
+        tables
+            .channels
+            .extend(channels.into_iter().map(|channel| (event_id, channel)));
+        Ok(())
+    }
+
+    fn get_channels_by_event_id(&self, event_id: i64) -> StoreResult<Vec<u64>> {
+        Ok(self
+            .tables()
+            .channels
+            .iter()
+            .filter(|(event, _)| *event == event_id)
+            .map(|(_, channel)| *channel)
+            .collect())
+    }
+
+    fn delete_channel(&self, event_id: i64, channel_id: u64) -> StoreResult<usize> {
+        let mut tables = self.tables();
+        let before = tables.channels.len();
+        tables.channels.retain(|row| *row != (event_id, channel_id));
+        Ok(before - tables.channels.len())
+    }
+
+    fn insert_server_manager_role(&self, server_id: u64, role_id: u64) -> StoreResult<()> {
+        self.tables()
+            .servers
+            .entry(server_id)
+            .or_default()
+            .0 = role_id;
+        Ok(())
+    }
+
+    fn get_server_manager_role_id(&self, server_id: u64) -> StoreResult<u64> {
+        self.tables()
+            .servers
+            .get(&server_id)
+            .map(|(role_id, _)| *role_id)
+            .ok_or(StoreError::NotFound)
+    }
+
+    fn get_guild_settings(&self, server_id: u64) -> StoreResult<GuildSettings> {
+        self.tables()
+            .servers
+            .get(&server_id)
+            .map(|(_, settings)| settings.clone())
+            .ok_or(StoreError::NotFound)
+    }
+
+    fn set_guild_settings(&self, server_id: u64, settings: GuildSettings) -> StoreResult<()> {
+        match self.tables().servers.get_mut(&server_id) {
+            Some((_, stored)) => {
+                *stored = settings;
+                Ok(())
+            }
+            None => Err(StoreError::NotFound),
+        }
+    }
+
+    fn insert_participant(&self, event_id: i64, user_id: u64) -> StoreResult<()> {
+        let mut tables = self.tables();
+        if !tables.participants.contains(&(event_id, user_id)) {
+            tables.participants.push((event_id, user_id));
+        }
+        Ok(())
+    }
+
+    fn delete_participant(&self, event_id: i64, user_id: u64) -> StoreResult<()> {
+        self.tables()
+            .participants
+            .retain(|participant| *participant != (event_id, user_id));
+        Ok(())
+    }
+
+    fn get_participants(&self, event_id: i64) -> StoreResult<Vec<u64>> {
+        Ok(self
+            .tables()
+            .participants
+            .iter()
+            .filter(|(event, _)| *event == event_id)
+            .map(|(_, user)| *user)
+            .collect())
+    }
+
+    fn set_dm_notifications(&self, user_id: u64, enabled: bool) -> StoreResult<()> {
+        let mut tables = self.tables();
+        if enabled {
+            tables.dm_subscribers.insert(user_id);
+        } else {
+            tables.dm_subscribers.remove(&user_id);
+        }
+        Ok(())
+    }
+
+    fn get_dm_subscribers(&self, event_id: i64) -> StoreResult<Vec<u64>> {
+        let tables = self.tables();
+        Ok(tables
+            .participants
+            .iter()
+            .filter(|(event, user)| *event == event_id && tables.dm_subscribers.contains(user))
+            .map(|(_, user)| *user)
+            .collect())
+    }
+
+    fn create_poll(&self, event_id: i64, mut poll: DatePoll) -> StoreResult<()> {
+        let mut tables = self.tables();
+        poll.slots.sort_unstable();
+        poll.slots.dedup();
+        tables.polls.insert(event_id, poll);
+        tables.poll_votes.retain(|(event, _, _)| *event != event_id);
+        Ok(())
+    }
+
+    fn get_poll(&self, event_id: i64) -> StoreResult<DatePoll> {
+        self.tables()
+            .polls
+            .get(&event_id)
+            .cloned()
+            .ok_or(StoreError::NotFound)
+    }
+
+    fn set_poll_votes(&self, event_id: i64, user_id: u64, slots: Vec<i64>) -> StoreResult<()> {
+        let mut tables = self.tables();
+        let offered = match tables.polls.get(&event_id) {
+            Some(poll) => poll.slots.clone(),
+            None => return Ok(()),
+        };
+        tables
+            .poll_votes
+            .retain(|(event, _, user)| *event != event_id || *user != user_id);
+        for slot in slots.into_iter().filter(|slot| offered.contains(slot)) {
+            tables.poll_votes.insert((event_id, slot, user_id));
+        }
+        Ok(())
+    }
+
+    fn get_poll_votes(&self, event_id: i64) -> StoreResult<Vec<(i64, u64)>> {
+        Ok(self
+            .tables()
+            .poll_votes
+            .iter()
+            .filter(|(event, _, _)| *event == event_id)
+            .map(|(_, slot, user)| (*slot, *user))
+            .collect())
+    }
+
+    fn delete_poll(&self, event_id: i64) -> StoreResult<usize> {
+        let mut tables = self.tables();
+        tables.poll_votes.retain(|(event, _, _)| *event != event_id);
+        Ok(tables.polls.remove(&event_id).map_or(0, |_| 1))
+    }
+
+    fn insert_join_request(&self, event_id: i64, user_id: u64, message_id: u64) -> StoreResult<()> {
+        self.tables()
+            .join_requests
+            .insert((event_id, user_id), message_id);
+        Ok(())
+    }
+
+    fn get_join_request(&self, event_id: i64, user_id: u64) -> StoreResult<u64> {
+        self.tables()
+            .join_requests
+            .get(&(event_id, user_id))
+            .copied()
+            .ok_or(StoreError::NotFound)
+    }
+
+    fn delete_join_request(&self, event_id: i64, user_id: u64) -> StoreResult<usize> {
+        Ok(self
+            .tables()
+            .join_requests
+            .remove(&(event_id, user_id))
+            .map_or(0, |_| 1))
+    }
+
+    fn get_eligibility(&self, event_id: i64) -> StoreResult<Eligibility> {
+        Ok(self
+            .tables()
+            .eligibility
+            .get(&event_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn set_eligibility(&self, event_id: i64, mut rules: Eligibility) -> StoreResult<()> {
+        rules.required_roles.sort_unstable();
+        rules.required_roles.dedup();
+        rules.forbidden_roles.sort_unstable();
+        rules.forbidden_roles.dedup();
+        self.tables().eligibility.insert(event_id, rules);
+        Ok(())
+    }
+
+    fn insert_event_ban(&self, event_id: i64, ban: Ban) -> StoreResult<()> {
+        self.tables()
+            .event_bans
+            .insert((event_id, ban.user_id), ban);
+        Ok(())
+    }
+
+    fn delete_event_ban(&self, event_id: i64, user_id: u64) -> StoreResult<usize> {
+        Ok(self
+            .tables()
+            .event_bans
+            .remove(&(event_id, user_id))
+            .map_or(0, |_| 1))
+    }
+
+    fn get_event_bans(&self, event_id: i64) -> StoreResult<Vec<Ban>> {
+        let tables = self.tables();
+        Ok(sorted_bans(
+            tables
+                .event_bans
+                .iter()
+                .filter(|((event, _), _)| *event == event_id)
+                .map(|(_, ban)| ban),
+        ))
+    }
+
+    fn insert_server_ban(&self, server_id: u64, ban: Ban) -> StoreResult<()> {
+        self.tables()
+            .server_bans
+            .insert((server_id, ban.user_id), ban);
+        Ok(())
+    }
+
+    fn delete_server_ban(&self, server_id: u64, user_id: u64) -> StoreResult<usize> {
+        Ok(self
+            .tables()
+            .server_bans
+            .remove(&(server_id, user_id))
+            .map_or(0, |_| 1))
+    }
+
+    fn get_server_bans(&self, server_id: u64) -> StoreResult<Vec<Ban>> {
+        let tables = self.tables();
+        Ok(sorted_bans(
+            tables
+                .server_bans
+                .iter()
+                .filter(|((server, _), _)| *server == server_id)
+                .map(|(_, ban)| ban),
+        ))
+    }
+
+    fn get_ban(&self, event_id: i64, user_id: u64) -> StoreResult<Ban> {
+        let tables = self.tables();
+        if let Some(ban) = tables.event_bans.get(&(event_id, user_id)) {
+            return Ok(ban.clone());
+        }
+        tables
+            .events
+            .get(&event_id)
+            .and_then(|event| tables.server_bans.get(&(event.server_id, user_id)))
+            .cloned()
+            .ok_or(StoreError::NotFound)
+    }
+
+    fn insert_slot(&self, event_id: i64, slot: TeamSlot) -> StoreResult<()> {
+        let mut tables = self.tables();
+        match tables
+            .slots
+            .iter_mut()
+            .find(|(event, existing)| *event == event_id && existing.name == slot.name)
+        {
+            Some((_, existing)) => *existing = slot,
+            None => tables.slots.push((event_id, slot)),
+        }
+        Ok(())
+    }
+
+    fn delete_slot(&self, event_id: i64, name: &str) -> StoreResult<usize> {
+        let mut tables = self.tables();
+        let before = tables.slots.len();
+        tables
+            .slots
+            .retain(|(event, slot)| *event != event_id || slot.name != name);
+        tables
+            .slot_members
+            .retain(|(event, _, slot)| *event != event_id || slot != name);
+        Ok(before - tables.slots.len())
+    }
+
+    fn get_slots(&self, event_id: i64) -> StoreResult<Vec<TeamSlot>> {
+        Ok(self
+            .tables()
+            .slots
+            .iter()
+            .filter(|(event, _)| *event == event_id)
+            .map(|(_, slot)| slot.clone())
+            .collect())
+    }
+
+    fn take_slot(&self, event_id: i64, user_id: u64, name: &str) -> StoreResult<bool> {
+        let mut tables = self.tables();
+        let capacity = match tables
+            .slots
+            .iter()
+            .find(|(event, slot)| *event == event_id && slot.name == name)
+        {
+            Some((_, slot)) => slot.capacity as usize,
+            None => return Ok(false),
+        };
+        let taken = tables
+            .slot_members
+            .iter()
+            .filter(|(event, user, slot)| *event == event_id && *user != user_id && slot == name)
+            .count();
+        if taken >= capacity {
+            return Ok(false);
+        }
+        tables
+            .slot_members
+            .retain(|(event, user, _)| *event != event_id || *user != user_id);
+        tables
+            .slot_members
+            .push((event_id, user_id, name.to_string()));
+        Ok(true)
+    }
+
+    fn leave_slot(&self, event_id: i64, user_id: u64) -> StoreResult<usize> {
+        let mut tables = self.tables();
+        let before = tables.slot_members.len();
+        tables
+            .slot_members
+            .retain(|(event, user, _)| *event != event_id || *user != user_id);
+        Ok(before - tables.slot_members.len())
+    }
+
+    fn get_slot_members(&self, event_id: i64) -> StoreResult<Vec<(String, u64)>> {
+        Ok(self
+            .tables()
+            .slot_members
+            .iter()
+            .filter(|(event, _, _)| *event == event_id)
+            .map(|(_, user, slot)| (slot.clone(), *user))
+            .collect())
+    }
+
+    fn set_skill(&self, event_id: i64, user_id: u64, skill: Option<i64>) -> StoreResult<()> {
+        let mut tables = self.tables();
+        match skill {
+            Some(skill) => tables.skills.insert((event_id, user_id), skill),
+            None => tables.skills.remove(&(event_id, user_id)),
+        };
+        Ok(())
+    }
+
+    fn get_skills(&self, event_id: i64) -> StoreResult<Vec<(u64, i64)>> {
+        Ok(self
+            .tables()
+            .skills
+            .iter()
+            .filter(|((event, _), _)| *event == event_id)
+            .map(|((_, user), skill)| (*user, *skill))
+            .collect())
+    }
+
+    fn insert_team(&self, event_id: i64, mut team: Team) -> StoreResult<()> {
+        let mut tables = self.tables();
+        for ((_, _), other) in tables
+            .teams
+            .iter_mut()
+            .filter(|((event, _), _)| *event == event_id)
+        {
+            other.members.retain(|user| !team.members.contains(user));
+        }
+        team.members.sort_unstable();
+        team.members.dedup();
+        tables.teams.insert((event_id, team.number), team);
+        Ok(())
+    }
+
+    fn get_teams(&self, event_id: i64) -> StoreResult<Vec<Team>> {
+        Ok(self
+            .tables()
+            .teams
+            .iter()
+            .filter(|((event, _), _)| *event == event_id)
+            .map(|(_, team)| team.clone())
+            .collect())
+    }
+
+    fn delete_teams(&self, event_id: i64) -> StoreResult<usize> {
+        let mut tables = self.tables();
+        let before = tables.teams.len();
+        tables.teams.retain(|(event, _), _| *event != event_id);
+        Ok(before - tables.teams.len())
+    }
+
+    fn create_bracket(&self, event_id: i64, mut bracket: Bracket) -> StoreResult<()> {
+        let mut tables = self.tables();
+        for entrant in &mut bracket.entrants {
+            entrant.members.sort_unstable();
+            entrant.members.dedup();
+        }
+        tables.brackets.insert(event_id, bracket);
+        tables.results.retain(|(event, _), _| *event != event_id);
+        Ok(())
+    }
+
+    fn get_bracket(&self, event_id: i64) -> StoreResult<Bracket> {
+        self.tables()
+            .brackets
+            .get(&event_id)
+            .cloned()
+            .ok_or(StoreError::NotFound)
+    }
+
+    fn delete_bracket(&self, event_id: i64) -> StoreResult<usize> {
+        let mut tables = self.tables();
+        tables.results.retain(|(event, _), _| *event != event_id);
+        Ok(tables.brackets.remove(&event_id).map_or(0, |_| 1))
+    }
+
+    fn report_result(&self, event_id: i64, report: MatchReport) -> StoreResult<()> {
+        let mut tables = self.tables();
+        if !tables.brackets.contains_key(&event_id) {
+            return Err(StoreError::NotFound);
+        }
+        tables
+            .results
+            .insert((event_id, report.match_id.clone()), report);
+        Ok(())
+    }
+
+    fn confirm_result(&self, event_id: i64, match_id: &str) -> StoreResult<usize> {
+        Ok(
+            match self
+                .tables()
+                .results
+                .get_mut(&(event_id, match_id.to_string()))
+            {
+                Some(report) => {
+                    report.confirmed = true;
+                    1
+                }
+                None => 0,
+            },
+        )
+    }
+
+    fn get_results(&self, event_id: i64) -> StoreResult<Vec<MatchReport>> {
+        Ok(self
+            .tables()
+            .results
+            .iter()
+            .filter(|((event, _), _)| *event == event_id)
+            .map(|(_, report)| report.clone())
+            .collect())
+    }
+
+    fn open_checkin(&self, event_id: i64, checkin: CheckIn) -> StoreResult<()> {
+        self.tables().checkins.insert(event_id, checkin);
+        Ok(())
+    }
+
+    fn get_checkin(&self, event_id: i64) -> StoreResult<CheckIn> {
+        self.tables()
+            .checkins
+            .get(&event_id)
+            .cloned()
+            .ok_or(StoreError::NotFound)
+    }
+
+    fn close_checkin(&self, event_id: i64) -> StoreResult<usize> {
+        Ok(self.tables().checkins.remove(&event_id).map_or(0, |_| 1))
+    }
+
+    fn check_in(&self, event_id: i64, user_id: u64, at: i64) -> StoreResult<bool> {
+        let mut tables = self.tables();
+        if tables.attendance.contains_key(&(event_id, user_id)) {
+            return Ok(false);
+        }
+        tables.attendance.insert((event_id, user_id), at);
+        Ok(true)
+    }
+
+    fn get_attendance(&self, event_id: i64) -> StoreResult<Vec<(u64, i64)>> {
+        let mut attendance: Vec<(u64, i64)> = self
+            .tables()
+            .attendance
+            .iter()
+            .filter(|((event, _), _)| *event == event_id)
+            .map(|((_, user), at)| (*user, *at))
+            .collect();
+        attendance.sort_by_key(|(user, at)| (*at, *user));
+        Ok(attendance)
+    }
+
+    fn record_outcome(
+        &self,
+        server_id: u64,
+        user_id: u64,
+        event_id: i64,
+        outcome: Outcome,
+        at: i64,
+    ) -> StoreResult<bool> {
+        let mut tables = self.tables();
+        let key = (server_id, user_id, event_id, outcome.as_str());
+        if tables.history.contains_key(&key) {
+            return Ok(false);
+        }
+        tables.history.insert(key, at);
+        Ok(true)
+    }
+
+    fn get_reliability(&self, server_id: u64, user_id: u64) -> StoreResult<Reliability> {
+        let mut reliability = Reliability::default();
+        for (_, _, _, outcome) in self.tables().history.keys().filter(|(server, user, _, _)| {
+            *server == server_id && *user == user_id
+        }) {
+            let outcome = outcome
+                .parse()
+                .map_err(|e: String| StoreError::Backend(e.into()))?;
+            reliability.count(outcome, 1);
+        }
+        Ok(reliability)
+    }
+
+    fn save_template(&self, server_id: u64, template: Template) -> StoreResult<()> {
+        self.tables()
+            .templates
+            .insert((server_id, template.name), template.layout);
+        Ok(())
+    }
+
+    fn get_template(&self, server_id: u64, name: &str) -> StoreResult<Template> {
+        self.tables()
+            .templates
+            .get(&(server_id, name.to_string()))
+            .map(|layout| Template {
+                name: name.to_string(),
+                layout: layout.clone(),
+            })
+            .ok_or(StoreError::NotFound)
+    }
+
+    fn get_templates(&self, server_id: u64) -> StoreResult<Vec<Template>> {
+        Ok(self
+            .tables()
+            .templates
+            .iter()
+            .filter(|((server, _), _)| *server == server_id)
+            .map(|((_, name), layout)| Template {
+                name: name.clone(),
+                layout: layout.clone(),
+            })
+            .collect())
+    }
+
+    fn delete_template(&self, server_id: u64, name: &str) -> StoreResult<usize> {
+        Ok(self
+            .tables()
+            .templates
+            .remove(&(server_id, name.to_string()))
+            .map_or(0, |_| 1))
+    }
+}
@@ -0,0 +1,639 @@
+/// A schema change, written once per SQL dialect.
+/// Both backends apply the same versions in the same order,
+/// and record them in SCHEMA_MIGRATIONS so each one runs exactly once
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub sqlite: &'static str,
+    #[cfg_attr(not(feature = "postgres"), allow(dead_code))]
+    pub postgres: &'static str,
+}
+
+/// Every migration, oldest first. Never edit a released migration, append a new one instead
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "events, channels, servers and participants",
+        // IF NOT EXISTS: databases created before migrations existed already have these tables
+        sqlite: r#"
+            CREATE TABLE IF NOT EXISTS EVENTS(
+                ID INTEGER PRIMARY KEY AUTOINCREMENT,
+                NAME TEXT NOT NULL,
+                SHORT_DESCRIPTION TEXT,
+                DESCRIPTION TEXT,
+                THUMBNAIL TEXT,
+                PICTURE TEXT,
+                MAX_PARTICIPANTS INTEGER,
+                SERVER_ID INTEGER NOT NULL,
+                MANAGER_ROLE_ID INTEGER NOT NULL,
+                PARTICIPANT_ROLE_ID INTEGER NOT NULL,
+                MANIFEST_ID INTEGER NOT NULL,
+                MANIFEST_CHANNEL_ID INTEGER NOT NULL,
+                CATEGORY_ID INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS CHANNELS (
+                EVENT_ID INTEGER,
+                CHANNEL_ID INTEGER NOT NULL,
+                FOREIGN KEY(EVENT_ID) REFERENCES EVENTS(ID) ON DELETE CASCADE
+            );
+
+            CREATE TABLE IF NOT EXISTS SERVERS (
+                SERVER_ID INTEGER PRIMARY KEY,
+                EVENT_CREATOR_ROLE_ID NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS PARTICIPANTS (
+                EVENT_ID INTEGER NOT NULL,
+                USER_ID INTEGER NOT NULL,
+                PRIMARY KEY(EVENT_ID, USER_ID),
+                FOREIGN KEY(EVENT_ID) REFERENCES EVENTS(ID) ON DELETE CASCADE
+            );
+        "#,
+        // JOIN_ORDER stands in for SQLite's ROWID, which participants are sorted by
+        postgres: r#"
+            CREATE TABLE IF NOT EXISTS EVENTS(
+                ID BIGSERIAL PRIMARY KEY,
+                NAME TEXT NOT NULL,
+                SHORT_DESCRIPTION TEXT,
+                DESCRIPTION TEXT,
+                THUMBNAIL TEXT,
+                PICTURE TEXT,
+                MAX_PARTICIPANTS BIGINT,
+                SERVER_ID BIGINT NOT NULL,
+                MANAGER_ROLE_ID BIGINT NOT NULL,
+                PARTICIPANT_ROLE_ID BIGINT NOT NULL,
+                MANIFEST_ID BIGINT NOT NULL,
+                MANIFEST_CHANNEL_ID BIGINT NOT NULL,
+                CATEGORY_ID BIGINT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS CHANNELS (
+                EVENT_ID BIGINT REFERENCES EVENTS(ID) ON DELETE CASCADE,
+                CHANNEL_ID BIGINT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS SERVERS (
+                SERVER_ID BIGINT PRIMARY KEY,
+                EVENT_CREATOR_ROLE_ID BIGINT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS PARTICIPANTS (
+                EVENT_ID BIGINT NOT NULL REFERENCES EVENTS(ID) ON DELETE CASCADE,
+                USER_ID BIGINT NOT NULL,
+                JOIN_ORDER BIGSERIAL,
+                PRIMARY KEY(EVENT_ID, USER_ID)
+            );
+        "#,
+    },
+    Migration {
+        version: 2,
+        description: "per-server settings, RSVP emoji of each event",
+        sqlite: r#"
+            ALTER TABLE SERVERS ADD COLUMN CREATOR_ROLE_NAME TEXT;
+            ALTER TABLE SERVERS ADD COLUMN MANAGER_ROLE_SUFFIX TEXT;
+            ALTER TABLE SERVERS ADD COLUMN PLAYER_ROLE_SUFFIX TEXT;
+            ALTER TABLE SERVERS ADD COLUMN CHANNEL_LAYOUT TEXT;
+            ALTER TABLE SERVERS ADD COLUMN RSVP_EMOJI TEXT;
+            ALTER TABLE SERVERS ADD COLUMN ANNOUNCEMENT_TEXT TEXT;
+            ALTER TABLE EVENTS ADD COLUMN RSVP_EMOJI TEXT NOT NULL DEFAULT '✅';
+        "#,
+        postgres: r#"
+            ALTER TABLE SERVERS ADD COLUMN CREATOR_ROLE_NAME TEXT;
+            ALTER TABLE SERVERS ADD COLUMN MANAGER_ROLE_SUFFIX TEXT;
+            ALTER TABLE SERVERS ADD COLUMN PLAYER_ROLE_SUFFIX TEXT;
+            ALTER TABLE SERVERS ADD COLUMN CHANNEL_LAYOUT TEXT;
+            ALTER TABLE SERVERS ADD COLUMN RSVP_EMOJI TEXT;
+            ALTER TABLE SERVERS ADD COLUMN ANNOUNCEMENT_TEXT TEXT;
+            ALTER TABLE EVENTS ADD COLUMN RSVP_EMOJI TEXT NOT NULL DEFAULT '✅';
+        "#,
+    },
+    Migration {
+        version: 3,
+        description: "announcement channel of each server",
+        sqlite: r#"
+            ALTER TABLE SERVERS ADD COLUMN ANNOUNCEMENT_CHANNEL_ID INTEGER;
+        "#,
+        postgres: r#"
+            ALTER TABLE SERVERS ADD COLUMN ANNOUNCEMENT_CHANNEL_ID BIGINT;
+        "#,
+    },
+    Migration {
+        version: 4,
+        description: "channel layout templates of each server",
+        sqlite: r#"
+            CREATE TABLE TEMPLATES (
+                SERVER_ID INTEGER NOT NULL,
+                NAME TEXT NOT NULL,
+                LAYOUT TEXT NOT NULL,
+                PRIMARY KEY(SERVER_ID, NAME)
+            );
+        "#,
+        postgres: r#"
+            CREATE TABLE TEMPLATES (
+                SERVER_ID BIGINT NOT NULL,
+                NAME TEXT NOT NULL,
+                LAYOUT TEXT NOT NULL,
+                PRIMARY KEY(SERVER_ID, NAME)
+            );
+        "#,
+    },
+    Migration {
+        version: 5,
+        description: "event window, timed voice channels",
+        sqlite: r#"
+            ALTER TABLE EVENTS ADD COLUMN STARTS_AT INTEGER;
+            ALTER TABLE EVENTS ADD COLUMN ENDS_AT INTEGER;
+            ALTER TABLE EVENTS ADD COLUMN TIMED_VOICE INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE EVENTS ADD COLUMN VOICE_OPEN INTEGER NOT NULL DEFAULT 1;
+        "#,
+        postgres: r#"
+            ALTER TABLE EVENTS ADD COLUMN STARTS_AT BIGINT;
+            ALTER TABLE EVENTS ADD COLUMN ENDS_AT BIGINT;
+            ALTER TABLE EVENTS ADD COLUMN TIMED_VOICE BOOLEAN NOT NULL DEFAULT FALSE;
+            ALTER TABLE EVENTS ADD COLUMN VOICE_OPEN BOOLEAN NOT NULL DEFAULT TRUE;
+        "#,
+    },
+    Migration {
+        version: 6,
+        description: "thread events, thread channel of each server",
+        sqlite: r#"
+            ALTER TABLE EVENTS ADD COLUMN MODE TEXT NOT NULL DEFAULT 'category';
+            ALTER TABLE SERVERS ADD COLUMN THREAD_CHANNEL_ID INTEGER;
+        "#,
+        postgres: r#"
+            ALTER TABLE EVENTS ADD COLUMN MODE TEXT NOT NULL DEFAULT 'category';
+            ALTER TABLE SERVERS ADD COLUMN THREAD_CHANNEL_ID BIGINT;
+        "#,
+    },
+    Migration {
+        version: 7,
+        description: "event archives, archive category and retention of each server",
+        sqlite: r#"
+            ALTER TABLE EVENTS ADD COLUMN ARCHIVED_AT INTEGER;
+            ALTER TABLE SERVERS ADD COLUMN ARCHIVE_CATEGORY_ID INTEGER;
+            ALTER TABLE SERVERS ADD COLUMN ARCHIVE_RETENTION_DAYS INTEGER;
+        "#,
+        postgres: r#"
+            ALTER TABLE EVENTS ADD COLUMN ARCHIVED_AT BIGINT;
+            ALTER TABLE SERVERS ADD COLUMN ARCHIVE_CATEGORY_ID BIGINT;
+            ALTER TABLE SERVERS ADD COLUMN ARCHIVE_RETENTION_DAYS INTEGER;
+        "#,
+    },
+    Migration {
+        version: 8,
+        description: "transcript log channel of each server",
+        sqlite: r#"
+            ALTER TABLE SERVERS ADD COLUMN LOG_CHANNEL_ID INTEGER;
+        "#,
+        postgres: r#"
+            ALTER TABLE SERVERS ADD COLUMN LOG_CHANNEL_ID BIGINT;
+        "#,
+    },
+    Migration {
+        version: 9,
+        description: "event status, events created before it are live",
+        sqlite: r#"
+            ALTER TABLE EVENTS ADD COLUMN STATUS TEXT NOT NULL DEFAULT 'published';
+        "#,
+        postgres: r#"
+            ALTER TABLE EVENTS ADD COLUMN STATUS TEXT NOT NULL DEFAULT 'published';
+        "#,
+    },
+    Migration {
+        version: 10,
+        description: "event change log, participants opted in to DM notifications",
+        sqlite: r#"
+            CREATE TABLE EVENT_CHANGES (
+                ID INTEGER PRIMARY KEY AUTOINCREMENT,
+                EVENT_ID INTEGER NOT NULL,
+                CHANGED_AT INTEGER NOT NULL,
+                AUTHOR_ID INTEGER NOT NULL,
+                FIELD TEXT NOT NULL,
+                OLD_VALUE TEXT,
+                NEW_VALUE TEXT,
+                REASON TEXT,
+                FOREIGN KEY(EVENT_ID) REFERENCES EVENTS(ID) ON DELETE CASCADE
+            );
+
+            CREATE TABLE DM_NOTIFICATIONS (
+                USER_ID INTEGER PRIMARY KEY
+            );
+        "#,
+        postgres: r#"
+            CREATE TABLE EVENT_CHANGES (
+                ID BIGSERIAL PRIMARY KEY,
+                EVENT_ID BIGINT NOT NULL REFERENCES EVENTS(ID) ON DELETE CASCADE,
+                CHANGED_AT BIGINT NOT NULL,
+                AUTHOR_ID BIGINT NOT NULL,
+                FIELD TEXT NOT NULL,
+                OLD_VALUE TEXT,
+                NEW_VALUE TEXT,
+                REASON TEXT
+            );
+
+            CREATE TABLE DM_NOTIFICATIONS (
+                USER_ID BIGINT PRIMARY KEY
+            );
+        "#,
+    },
+    Migration {
+        version: 11,
+        description: "date polls, their candidate slots and votes",
+        sqlite: r#"
+            CREATE TABLE POLLS (
+                EVENT_ID INTEGER PRIMARY KEY,
+                CHANNEL_ID INTEGER NOT NULL,
+                MESSAGE_ID INTEGER NOT NULL,
+                FOREIGN KEY(EVENT_ID) REFERENCES EVENTS(ID) ON DELETE CASCADE
+            );
+
+            CREATE TABLE POLL_SLOTS (
+                EVENT_ID INTEGER NOT NULL,
+                STARTS_AT INTEGER NOT NULL,
+                PRIMARY KEY(EVENT_ID, STARTS_AT),
+                FOREIGN KEY(EVENT_ID) REFERENCES POLLS(EVENT_ID) ON DELETE CASCADE
+            );
+
+            CREATE TABLE POLL_VOTES (
+                EVENT_ID INTEGER NOT NULL,
+                STARTS_AT INTEGER NOT NULL,
+                USER_ID INTEGER NOT NULL,
+                PRIMARY KEY(EVENT_ID, STARTS_AT, USER_ID),
+                FOREIGN KEY(EVENT_ID, STARTS_AT) REFERENCES POLL_SLOTS(EVENT_ID, STARTS_AT) ON DELETE CASCADE
+            );
+        "#,
+        postgres: r#"
+            CREATE TABLE POLLS (
+                EVENT_ID BIGINT PRIMARY KEY REFERENCES EVENTS(ID) ON DELETE CASCADE,
+                CHANNEL_ID BIGINT NOT NULL,
+                MESSAGE_ID BIGINT NOT NULL
+            );
+
+            CREATE TABLE POLL_SLOTS (
+                EVENT_ID BIGINT NOT NULL REFERENCES POLLS(EVENT_ID) ON DELETE CASCADE,
+                STARTS_AT BIGINT NOT NULL,
+                PRIMARY KEY(EVENT_ID, STARTS_AT)
+            );
+
+            CREATE TABLE POLL_VOTES (
+                EVENT_ID BIGINT NOT NULL,
+                STARTS_AT BIGINT NOT NULL,
+                USER_ID BIGINT NOT NULL,
+                PRIMARY KEY(EVENT_ID, STARTS_AT, USER_ID),
+                FOREIGN KEY(EVENT_ID, STARTS_AT) REFERENCES POLL_SLOTS(EVENT_ID, STARTS_AT) ON DELETE CASCADE
+            );
+        "#,
+    },
+    Migration {
+        version: 12,
+        description: "invite-only events and their pending join requests",
+        sqlite: r#"
+            ALTER TABLE EVENTS ADD COLUMN REQUESTS_CHANNEL_ID INTEGER;
+
+            CREATE TABLE JOIN_REQUESTS (
+                EVENT_ID INTEGER NOT NULL,
+                USER_ID INTEGER NOT NULL,
+                MESSAGE_ID INTEGER NOT NULL,
+                PRIMARY KEY(EVENT_ID, USER_ID),
+                FOREIGN KEY(EVENT_ID) REFERENCES EVENTS(ID) ON DELETE CASCADE
+            );
+        "#,
+        postgres: r#"
+            ALTER TABLE EVENTS ADD COLUMN REQUESTS_CHANNEL_ID BIGINT;
+
+            CREATE TABLE JOIN_REQUESTS (
+                EVENT_ID BIGINT NOT NULL REFERENCES EVENTS(ID) ON DELETE CASCADE,
+                USER_ID BIGINT NOT NULL,
+                MESSAGE_ID BIGINT NOT NULL,
+                PRIMARY KEY(EVENT_ID, USER_ID)
+            );
+        "#,
+    },
+    Migration {
+        version: 13,
+        description: "who may join an event: roles and minimum ages",
+        sqlite: r#"
+            CREATE TABLE EVENT_RULES (
+                EVENT_ID INTEGER PRIMARY KEY,
+                MIN_MEMBER_DAYS INTEGER,
+                MIN_ACCOUNT_DAYS INTEGER,
+                FOREIGN KEY(EVENT_ID) REFERENCES EVENTS(ID) ON DELETE CASCADE
+            );
+
+            CREATE TABLE EVENT_RULE_ROLES (
+                EVENT_ID INTEGER NOT NULL,
+                ROLE_ID INTEGER NOT NULL,
+                REQUIRED INTEGER NOT NULL,
+                PRIMARY KEY(EVENT_ID, ROLE_ID),
+                FOREIGN KEY(EVENT_ID) REFERENCES EVENTS(ID) ON DELETE CASCADE
+            );
+        "#,
+        postgres: r#"
+            CREATE TABLE EVENT_RULES (
+                EVENT_ID BIGINT PRIMARY KEY REFERENCES EVENTS(ID) ON DELETE CASCADE,
+                MIN_MEMBER_DAYS INTEGER,
+                MIN_ACCOUNT_DAYS INTEGER
+            );
+
+            CREATE TABLE EVENT_RULE_ROLES (
+                EVENT_ID BIGINT NOT NULL REFERENCES EVENTS(ID) ON DELETE CASCADE,
+                ROLE_ID BIGINT NOT NULL,
+                REQUIRED BOOLEAN NOT NULL,
+                PRIMARY KEY(EVENT_ID, ROLE_ID)
+            );
+        "#,
+    },
+    Migration {
+        version: 14,
+        description: "people banned from an event, or from every event of a server",
+        sqlite: r#"
+            CREATE TABLE EVENT_BANS (
+                EVENT_ID INTEGER NOT NULL,
+                USER_ID INTEGER NOT NULL,
+                BANNED_BY INTEGER NOT NULL,
+                BANNED_AT INTEGER NOT NULL,
+                REASON TEXT,
+                PRIMARY KEY(EVENT_ID, USER_ID),
+                FOREIGN KEY(EVENT_ID) REFERENCES EVENTS(ID) ON DELETE CASCADE
+            );
+
+            CREATE TABLE SERVER_BANS (
+                SERVER_ID INTEGER NOT NULL,
+                USER_ID INTEGER NOT NULL,
+                BANNED_BY INTEGER NOT NULL,
+                BANNED_AT INTEGER NOT NULL,
+                REASON TEXT,
+                PRIMARY KEY(SERVER_ID, USER_ID)
+            );
+        "#,
+        postgres: r#"
+            CREATE TABLE EVENT_BANS (
+                EVENT_ID BIGINT NOT NULL REFERENCES EVENTS(ID) ON DELETE CASCADE,
+                USER_ID BIGINT NOT NULL,
+                BANNED_BY BIGINT NOT NULL,
+                BANNED_AT BIGINT NOT NULL,
+                REASON TEXT,
+                PRIMARY KEY(EVENT_ID, USER_ID)
+            );
+
+            CREATE TABLE SERVER_BANS (
+                SERVER_ID BIGINT NOT NULL,
+                USER_ID BIGINT NOT NULL,
+                BANNED_BY BIGINT NOT NULL,
+                BANNED_AT BIGINT NOT NULL,
+                REASON TEXT,
+                PRIMARY KEY(SERVER_ID, USER_ID)
+            );
+        "#,
+    },
+    Migration {
+        version: 15,
+        description: "composition slots of an event and who took them",
+        sqlite: r#"
+            CREATE TABLE SLOTS (
+                EVENT_ID INTEGER NOT NULL,
+                NAME TEXT NOT NULL,
+                CAPACITY INTEGER NOT NULL,
+                ROLE_ID INTEGER,
+                POSITION INTEGER NOT NULL,
+                PRIMARY KEY(EVENT_ID, NAME),
+                FOREIGN KEY(EVENT_ID) REFERENCES EVENTS(ID) ON DELETE CASCADE
+            );
+
+            CREATE TABLE SLOT_MEMBERS (
+                EVENT_ID INTEGER NOT NULL,
+                USER_ID INTEGER NOT NULL,
+                SLOT_NAME TEXT NOT NULL,
+                PRIMARY KEY(EVENT_ID, USER_ID),
+                FOREIGN KEY(EVENT_ID, SLOT_NAME) REFERENCES SLOTS(EVENT_ID, NAME) ON DELETE CASCADE
+            );
+        "#,
+        postgres: r#"
+            CREATE TABLE SLOTS (
+                EVENT_ID BIGINT NOT NULL REFERENCES EVENTS(ID) ON DELETE CASCADE,
+                NAME TEXT NOT NULL,
+                CAPACITY INTEGER NOT NULL,
+                ROLE_ID BIGINT,
+                POSITION INTEGER NOT NULL,
+                PRIMARY KEY(EVENT_ID, NAME)
+            );
+
+            CREATE TABLE SLOT_MEMBERS (
+                EVENT_ID BIGINT NOT NULL,
+                USER_ID BIGINT NOT NULL,
+                SLOT_NAME TEXT NOT NULL,
+                TAKE_ORDER BIGSERIAL,
+                PRIMARY KEY(EVENT_ID, USER_ID),
+                FOREIGN KEY(EVENT_ID, SLOT_NAME) REFERENCES SLOTS(EVENT_ID, NAME) ON DELETE CASCADE
+            );
+        "#,
+    },
+    Migration {
+        version: 16,
+        description: "skill of participants, generated teams and their members",
+        sqlite: r#"
+            CREATE TABLE SKILLS (
+                EVENT_ID INTEGER NOT NULL,
+                USER_ID INTEGER NOT NULL,
+                SKILL INTEGER NOT NULL,
+                PRIMARY KEY(EVENT_ID, USER_ID),
+                FOREIGN KEY(EVENT_ID) REFERENCES EVENTS(ID) ON DELETE CASCADE
+            );
+
+            CREATE TABLE TEAMS (
+                EVENT_ID INTEGER NOT NULL,
+                NUMBER INTEGER NOT NULL,
+                ROLE_ID INTEGER NOT NULL,
+                TEXT_CHANNEL_ID INTEGER NOT NULL,
+                VOICE_CHANNEL_ID INTEGER NOT NULL,
+                PRIMARY KEY(EVENT_ID, NUMBER),
+                FOREIGN KEY(EVENT_ID) REFERENCES EVENTS(ID) ON DELETE CASCADE
+            );
+
+            CREATE TABLE TEAM_MEMBERS (
+                EVENT_ID INTEGER NOT NULL,
+                NUMBER INTEGER NOT NULL,
+                USER_ID INTEGER NOT NULL,
+                PRIMARY KEY(EVENT_ID, USER_ID),
+                FOREIGN KEY(EVENT_ID, NUMBER) REFERENCES TEAMS(EVENT_ID, NUMBER) ON DELETE CASCADE
+            );
+        "#,
+        postgres: r#"
+            CREATE TABLE SKILLS (
+                EVENT_ID BIGINT NOT NULL REFERENCES EVENTS(ID) ON DELETE CASCADE,
+                USER_ID BIGINT NOT NULL,
+                SKILL BIGINT NOT NULL,
+                PRIMARY KEY(EVENT_ID, USER_ID)
+            );
+
+            CREATE TABLE TEAMS (
+                EVENT_ID BIGINT NOT NULL REFERENCES EVENTS(ID) ON DELETE CASCADE,
+                NUMBER INTEGER NOT NULL,
+                ROLE_ID BIGINT NOT NULL,
+                TEXT_CHANNEL_ID BIGINT NOT NULL,
+                VOICE_CHANNEL_ID BIGINT NOT NULL,
+                PRIMARY KEY(EVENT_ID, NUMBER)
+            );
+
+            CREATE TABLE TEAM_MEMBERS (
+                EVENT_ID BIGINT NOT NULL,
+                NUMBER INTEGER NOT NULL,
+                USER_ID BIGINT NOT NULL,
+                PRIMARY KEY(EVENT_ID, USER_ID),
+                FOREIGN KEY(EVENT_ID, NUMBER) REFERENCES TEAMS(EVENT_ID, NUMBER) ON DELETE CASCADE
+            );
+        "#,
+    },
+    Migration {
+        version: 17,
+        description: "tournament brackets: entrants, their members and match results",
+        sqlite: r#"
+            CREATE TABLE BRACKETS (
+                EVENT_ID INTEGER PRIMARY KEY,
+                FORMAT TEXT NOT NULL,
+                CHANNEL_ID INTEGER NOT NULL,
+                MESSAGE_ID INTEGER NOT NULL,
+                FOREIGN KEY(EVENT_ID) REFERENCES EVENTS(ID) ON DELETE CASCADE
+            );
+
+            CREATE TABLE BRACKET_ENTRANTS (
+                EVENT_ID INTEGER NOT NULL,
+                SEED INTEGER NOT NULL,
+                NAME TEXT NOT NULL,
+                PRIMARY KEY(EVENT_ID, SEED),
+                FOREIGN KEY(EVENT_ID) REFERENCES BRACKETS(EVENT_ID) ON DELETE CASCADE
+            );
+
+            CREATE TABLE BRACKET_MEMBERS (
+                EVENT_ID INTEGER NOT NULL,
+                SEED INTEGER NOT NULL,
+                USER_ID INTEGER NOT NULL,
+                PRIMARY KEY(EVENT_ID, SEED, USER_ID),
+                FOREIGN KEY(EVENT_ID, SEED) REFERENCES BRACKET_ENTRANTS(EVENT_ID, SEED) ON DELETE CASCADE
+            );
+
+            CREATE TABLE BRACKET_RESULTS (
+                EVENT_ID INTEGER NOT NULL,
+                MATCH_ID TEXT NOT NULL,
+                WINNER INTEGER NOT NULL,
+                REPORTED_BY INTEGER NOT NULL,
+                CONFIRMED INTEGER NOT NULL,
+                PRIMARY KEY(EVENT_ID, MATCH_ID),
+                FOREIGN KEY(EVENT_ID) REFERENCES BRACKETS(EVENT_ID) ON DELETE CASCADE
+            );
+        "#,
+        postgres: r#"
+            CREATE TABLE BRACKETS (
+                EVENT_ID BIGINT PRIMARY KEY REFERENCES EVENTS(ID) ON DELETE CASCADE,
+                FORMAT TEXT NOT NULL,
+                CHANNEL_ID BIGINT NOT NULL,
+                MESSAGE_ID BIGINT NOT NULL
+            );
+
+            CREATE TABLE BRACKET_ENTRANTS (
+                EVENT_ID BIGINT NOT NULL REFERENCES BRACKETS(EVENT_ID) ON DELETE CASCADE,
+                SEED INTEGER NOT NULL,
+                NAME TEXT NOT NULL,
+                PRIMARY KEY(EVENT_ID, SEED)
+            );
+
+            CREATE TABLE BRACKET_MEMBERS (
+                EVENT_ID BIGINT NOT NULL,
+                SEED INTEGER NOT NULL,
+                USER_ID BIGINT NOT NULL,
+                PRIMARY KEY(EVENT_ID, SEED, USER_ID),
+                FOREIGN KEY(EVENT_ID, SEED) REFERENCES BRACKET_ENTRANTS(EVENT_ID, SEED) ON DELETE CASCADE
+            );
+
+            CREATE TABLE BRACKET_RESULTS (
+                EVENT_ID BIGINT NOT NULL REFERENCES BRACKETS(EVENT_ID) ON DELETE CASCADE,
+                MATCH_ID TEXT NOT NULL,
+                WINNER INTEGER NOT NULL,
+                REPORTED_BY BIGINT NOT NULL,
+                CONFIRMED BOOLEAN NOT NULL,
+                PRIMARY KEY(EVENT_ID, MATCH_ID)
+            );
+        "#,
+    },
+    Migration {
+        version: 18,
+        description: "check-in windows and attendance",
+        sqlite: r#"
+            CREATE TABLE CHECKINS (
+                EVENT_ID INTEGER PRIMARY KEY,
+                CHANNEL_ID INTEGER NOT NULL,
+                MESSAGE_ID INTEGER NOT NULL,
+                CODE TEXT,
+                OPENED_AT INTEGER NOT NULL,
+                CLOSES_AT INTEGER NOT NULL,
+                FOREIGN KEY(EVENT_ID) REFERENCES EVENTS(ID) ON DELETE CASCADE
+            );
+
+            CREATE TABLE ATTENDANCE (
+                EVENT_ID INTEGER NOT NULL,
+                USER_ID INTEGER NOT NULL,
+                CHECKED_IN_AT INTEGER NOT NULL,
+                PRIMARY KEY(EVENT_ID, USER_ID),
+                FOREIGN KEY(EVENT_ID) REFERENCES EVENTS(ID) ON DELETE CASCADE
+            );
+        "#,
+        postgres: r#"
+            CREATE TABLE CHECKINS (
+                EVENT_ID BIGINT PRIMARY KEY REFERENCES EVENTS(ID) ON DELETE CASCADE,
+                CHANNEL_ID BIGINT NOT NULL,
+                MESSAGE_ID BIGINT NOT NULL,
+                CODE TEXT,
+                OPENED_AT BIGINT NOT NULL,
+                CLOSES_AT BIGINT NOT NULL
+            );
+
+            CREATE TABLE ATTENDANCE (
+                EVENT_ID BIGINT NOT NULL REFERENCES EVENTS(ID) ON DELETE CASCADE,
+                USER_ID BIGINT NOT NULL,
+                CHECKED_IN_AT BIGINT NOT NULL,
+                PRIMARY KEY(EVENT_ID, USER_ID)
+            );
+        "#,
+    },
+    Migration {
+        version: 19,
+        description: "members' history across events, kept after the events are deleted",
+        sqlite: r#"
+            CREATE TABLE MEMBER_HISTORY (
+                SERVER_ID INTEGER NOT NULL,
+                USER_ID INTEGER NOT NULL,
+                EVENT_ID INTEGER NOT NULL,
+                OUTCOME TEXT NOT NULL,
+                RECORDED_AT INTEGER NOT NULL,
+                PRIMARY KEY(SERVER_ID, USER_ID, EVENT_ID, OUTCOME)
+            );
+        "#,
+        postgres: r#"
+            CREATE TABLE MEMBER_HISTORY (
+                SERVER_ID BIGINT NOT NULL,
+                USER_ID BIGINT NOT NULL,
+                EVENT_ID BIGINT NOT NULL,
+                OUTCOME TEXT NOT NULL,
+                RECORDED_AT BIGINT NOT NULL,
+                PRIMARY KEY(SERVER_ID, USER_ID, EVENT_ID, OUTCOME)
+            );
+        "#,
+    },
+    Migration {
+        version: 20,
+        description: "when the transcript of each event was exported",
+        sqlite: r#"
+            ALTER TABLE EVENTS ADD COLUMN TRANSCRIPT_EXPORTED_AT INTEGER;
+        "#,
+        postgres: r#"
+            ALTER TABLE EVENTS ADD COLUMN TRANSCRIPT_EXPORTED_AT BIGINT;
+        "#,
+    },
+];
+
+/// Bookkeeping table, valid in both dialects
+pub const CREATE_MIGRATIONS_TABLE: &str = r#"CREATE TABLE IF NOT EXISTS SCHEMA_MIGRATIONS (
+    VERSION BIGINT PRIMARY KEY,
+    DESCRIPTION TEXT NOT NULL
+)"#;
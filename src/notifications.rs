@@ -0,0 +1,59 @@
+use crate::bacchus::Error;
+use crate::events::EventData;
+use crate::repository::Repository;
+use poise::serenity_prelude::{
+    ChannelId, CreateAllowedMentions, CreateMessage, Http, RoleId, UserId,
+};
+use tracing::{info, warn};
+
+/// Sends [text] to [user_id]'s DMs
+pub async fn dm(http: &Http, user_id: UserId, text: &str) -> Result<(), Error> {
+    user_id
+        .create_dm_channel(http)
+        .await?
+        .send_message(http, CreateMessage::new().content(text))
+        .await?;
+    Ok(())
+}
+
+/// Tells the participants of an event about a change: their role is pinged in [channel],
+/// and those who opted in with /notifications get a DM
+pub async fn notify(
+    http: &Http,
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+    channel: ChannelId,
+    text: &str,
+) -> Result<(), Error> {
+    let participants = RoleId::new(event.participant_role_id);
+    channel
+        .send_message(
+            http,
+            CreateMessage::new()
+                .content(format!("<@&{}> {}", participants, text))
+                .allowed_mentions(CreateAllowedMentions::new().roles(vec![participants])),
+        )
+        .await?;
+
+    // Someone who closed their DMs must not keep the others from being told
+    let subscribers = db.get_dm_subscribers(event_id).await?;
+    for user_id in &subscribers {
+        let user_id = UserId::new(*user_id);
+        if let Err(e) = dm(http, user_id, &format!("**{}**: {}", event.name, text)).await {
+            warn!(
+                "Couldn't DM {} about event {}(id {}): {}",
+                user_id, event.name, event_id, e
+            );
+        }
+    }
+
+    info!(
+        "Notified the participants of event {}(id {}), {} by DM",
+        event.name,
+        event_id,
+        subscribers.len()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,295 @@
+use crate::approval;
+use crate::bacchus::Error;
+use crate::composition;
+use crate::events::{Eligibility, EventData, EventMode, EventStatus, Outcome};
+use crate::manifest;
+use crate::reliability;
+use crate::repository::Repository;
+use crate::scheduler;
+use crate::store::StoreError;
+use poise::serenity_prelude::{Cache, ChannelId, GuildId, Http, RoleId, UserId};
+use std::fmt;
+
+const DAY: i64 = 24 * 60 * 60;
+
+/// Why a member may not join an event
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Ineligible {
+    /// Banned from the event or from every event of the server, with the ban's reason
+    Banned(Option<String>),
+    /// Name of a role the member lacks
+    MissingRole(String),
+    /// Name of a role the member has and that keeps them out
+    ForbiddenRole(String),
+    /// Days the member must have been on the server for
+    NewMember(u32),
+    /// Days the member's account must have existed for
+    NewAccount(u32),
+}
+
+impl fmt::Display for Ineligible {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ineligible::Banned(None) => write!(f, "you're banned from it"),
+            Ineligible::Banned(Some(reason)) => write!(f, "you're banned from it ({})", reason),
+            Ineligible::MissingRole(role) => write!(f, "it requires the {} role", role),
+            Ineligible::ForbiddenRole(role) => write!(f, "it isn't open to the {} role", role),
+            Ineligible::NewMember(days) => write!(
+                f,
+                "you must have been on the server for at least {} days",
+                days
+            ),
+            Ineligible::NewAccount(days) => {
+                write!(f, "your Discord account must be at least {} days old", days)
+            }
+        }
+    }
+}
+
+/// Whether a member may join an event under [rules].
+/// [role_name] names the roles given in the answer, [joined_at] and [created_at] are unix seconds
+pub fn check(
+    rules: &Eligibility,
+    member_roles: &[u64],
+    joined_at: Option<i64>,
+    created_at: i64,
+    now: i64,
+    role_name: impl Fn(u64) -> String,
+) -> Result<(), Ineligible> {
+    if let Some(role) = rules
+        .required_roles
+        .iter()
+        .find(|role| !member_roles.contains(role))
+    {
+        return Err(Ineligible::MissingRole(role_name(*role)));
+    }
+    if let Some(role) = rules
+        .forbidden_roles
+        .iter()
+        .find(|role| member_roles.contains(role))
+    {
+        return Err(Ineligible::ForbiddenRole(role_name(*role)));
+    }
+    if let Some(days) = rules.min_member_days {
+        // Discord leaves joined_at out in some payloads, don't let unknowns in
+        if joined_at.is_none_or(|joined| now - joined < i64::from(days) * DAY) {
+            return Err(Ineligible::NewMember(days));
+        }
+    }
+    if let Some(days) = rules.min_account_days {
+        if now - created_at < i64::from(days) * DAY {
+            return Err(Ineligible::NewAccount(days));
+        }
+    }
+    Ok(())
+}
+
+/// Checks [user_id] against the bans and rules of an event, every way of joining goes through it
+pub async fn check_eligibility(
+    http: &Http,
+    db: &Repository,
+    guild_id: GuildId,
+    event_id: i64,
+    user_id: UserId,
+) -> Result<Result<(), Ineligible>, Error> {
+    match db.get_ban(event_id, u64::from(user_id)).await {
+        Ok(ban) => return Ok(Err(Ineligible::Banned(ban.reason))),
+        Err(StoreError::NotFound) => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    let rules = db.get_eligibility(event_id).await?;
+    if rules == Eligibility::default() {
+        return Ok(Ok(()));
+    }
+
+    let member = guild_id.member(http, user_id).await?;
+    let roles = guild_id.roles(http).await?;
+    let member_roles: Vec<u64> = member.roles.iter().map(|role| u64::from(*role)).collect();
+
+    Ok(check(
+        &rules,
+        &member_roles,
+        member.joined_at.map(|joined| joined.unix_timestamp()),
+        user_id.created_at().unix_timestamp(),
+        scheduler::now(),
+        |role| {
+            roles
+                .get(&RoleId::new(role))
+                .map(|role| role.name.clone())
+                .unwrap_or_else(|| String::from("deleted"))
+        },
+    ))
+}
+
+/// The participants of an event who are people. Bots never join anymore,
+/// but ours could be recorded through its reaction to the manifest
+pub async fn players(cache: &Cache, db: &Repository, event_id: i64) -> Result<Vec<u64>, Error> {
+    let bot_id = u64::from(cache.current_user().id);
+    Ok(db
+        .get_participants(event_id)
+        .await?
+        .into_iter()
+        .filter(|user_id| {
+            *user_id != bot_id && !cache.user(UserId::new(*user_id)).is_some_and(|user| user.bot)
+        })
+        .collect())
+}
+
+/// Lets [user_id] into an event: gives them its participant role,
+/// adds them to its thread for thread events, and records them as a participant.
+/// Joining counts in their history on the server
+pub async fn join(
+    http: &Http,
+    db: &Repository,
+    guild_id: GuildId,
+    event_id: i64,
+    event: &EventData,
+    user_id: UserId,
+) -> Result<(), Error> {
+    http.add_member_role(
+        guild_id,
+        user_id,
+        RoleId::from(event.participant_role_id),
+        None,
+    )
+    .await?;
+    if event.mode == EventMode::Thread {
+        ChannelId::new(event.category_id)
+            .add_thread_member(http, user_id)
+            .await?;
+    }
+    db.insert_participant(event_id, u64::from(user_id)).await?;
+    reliability::record(db, event_id, event, u64::from(user_id), Outcome::Joined).await?;
+    Ok(())
+}
+
+/// Takes [user_id] out of an event they were banned from, however far they got:
+/// their participation, their pending join request and their reaction to the manifest
+pub async fn expel(
+    http: &Http,
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+    user_id: UserId,
+) -> Result<(), Error> {
+    let guild_id = GuildId::new(event.server_id);
+    if db
+        .get_participants(event_id)
+        .await?
+        .contains(&u64::from(user_id))
+    {
+        leave(http, db, guild_id, event_id, event, user_id).await?;
+    }
+    approval::withdraw(http, db, event_id, event, user_id).await?;
+    manifest::unreact(http, event, user_id).await;
+    Ok(())
+}
+
+/// Undoes [join], and frees the slot of [user_id]
+pub async fn leave(
+    http: &Http,
+    db: &Repository,
+    guild_id: GuildId,
+    event_id: i64,
+    event: &EventData,
+    user_id: UserId,
+) -> Result<(), Error> {
+    http.remove_member_role(
+        guild_id,
+        user_id,
+        RoleId::from(event.participant_role_id),
+        None,
+    )
+    .await?;
+    if event.mode == EventMode::Thread {
+        ChannelId::new(event.category_id)
+            .remove_thread_member(http, user_id)
+            .await?;
+    }
+    composition::release(http, db, event_id, event, user_id).await?;
+    db.delete_participant(event_id, u64::from(user_id)).await?;
+    Ok(())
+}
+
+/// [leave], when [user_id] decides to. Dropping out close to the start counts against them
+pub async fn drop_out(
+    http: &Http,
+    db: &Repository,
+    guild_id: GuildId,
+    event_id: i64,
+    event: &EventData,
+    user_id: UserId,
+) -> Result<(), Error> {
+    leave(http, db, guild_id, event_id, event, user_id).await?;
+    if event.status == EventStatus::Published
+        && reliability::is_late_cancel(event.starts_at, scheduler::now())
+    {
+        reliability::record(db, event_id, event, u64::from(user_id), Outcome::LateCancel).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(role: u64) -> String {
+        format!("role{}", role)
+    }
+
+    #[test]
+    fn roles() {
+        let rules = Eligibility {
+            required_roles: vec![1, 2],
+            forbidden_roles: vec![3],
+            ..Eligibility::default()
+        };
+        assert_eq!(check(&rules, &[1, 2, 4], None, 0, 0, name), Ok(()));
+        assert_eq!(
+            check(&rules, &[1], None, 0, 0, name),
+            Err(Ineligible::MissingRole(String::from("role2")))
+        );
+        assert_eq!(
+            check(&rules, &[1, 2, 3], None, 0, 0, name),
+            Err(Ineligible::ForbiddenRole(String::from("role3")))
+        );
+        assert_eq!(
+            check(&Eligibility::default(), &[3], None, 0, 0, name),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn ages() {
+        let rules = Eligibility {
+            min_member_days: Some(7),
+            min_account_days: Some(30),
+            ..Eligibility::default()
+        };
+        let now = 100 * DAY;
+        assert_eq!(
+            check(&rules, &[], Some(now - 7 * DAY), now - 30 * DAY, now, name),
+            Ok(())
+        );
+        assert_eq!(
+            check(
+                &rules,
+                &[],
+                Some(now - 7 * DAY + 1),
+                now - 30 * DAY,
+                now,
+                name
+            ),
+            Err(Ineligible::NewMember(7))
+        );
+        assert_eq!(
+            check(&rules, &[], None, now - 30 * DAY, now, name),
+            Err(Ineligible::NewMember(7))
+        );
+        assert_eq!(
+            check(&rules, &[], Some(0), now - DAY, now, name),
+            Err(Ineligible::NewAccount(30))
+        );
+    }
+}
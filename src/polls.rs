@@ -0,0 +1,292 @@
+use crate::bacchus::Error;
+use crate::events::{DatePoll, EventData, EventStatus};
+use crate::repository::Repository;
+use crate::scheduler::{format_time, parse_time};
+use chrono::DateTime;
+use poise::serenity_prelude::{
+    CacheHttp, Colour, ComponentInteraction, ComponentInteractionDataKind, CreateActionRow,
+    CreateEmbed, CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseFollowup,
+    CreateInteractionResponseMessage, CreateSelectMenu, CreateSelectMenuKind,
+    CreateSelectMenuOption,
+};
+use tracing::info;
+
+/// Custom id of a poll's select menu, followed by the event's id
+pub const POLL_PREFIX: &str = "date_poll:";
+/// Options a Discord select menu can hold
+const MAX_SLOTS: usize = 25;
+const BAR_WIDTH: usize = 10;
+
+/// Reads the candidate times given to /event poll_dates, separated by commas.
+/// Returns them earliest first, without duplicates
+pub fn parse_slots(value: &str) -> Result<Vec<i64>, String> {
+    let mut slots = value
+        .split(',')
+        .filter(|slot| !slot.trim().is_empty())
+        .map(parse_time)
+        .collect::<Result<Vec<i64>, String>>()?;
+    slots.sort_unstable();
+    slots.dedup();
+
+    match slots.len() {
+        0 | 1 => Err(String::from("a poll needs at least 2 different times")),
+        n if n > MAX_SLOTS => Err(format!("a poll can't offer more than {} times", MAX_SLOTS)),
+        _ => Ok(slots),
+    }
+}
+
+/// How many people are available at each slot, in the order of [slots]
+pub fn tally(slots: &[i64], votes: &[(i64, u64)]) -> Vec<(i64, usize)> {
+    slots
+        .iter()
+        .map(|slot| (*slot, votes.iter().filter(|(s, _)| s == slot).count()))
+        .collect()
+}
+
+/// The slot most people are available at, the earliest one on ties.
+/// None until somebody voted
+pub fn winner(tally: &[(i64, usize)]) -> Option<i64> {
+    tally
+        .iter()
+        .filter(|(_, count)| *count > 0)
+        // max_by_key keeps the last maximum, the earliest slot has to win
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|(slot, _)| *slot)
+}
+
+/// One line per slot, with a bar as long as its share of the most voted slot
+pub fn heat_map(tally: &[(i64, usize)]) -> String {
+    let best = tally.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    let leader = winner(tally);
+
+    tally
+        .iter()
+        .enumerate()
+        .map(|(i, (slot, count))| {
+            let filled = if best == 0 {
+                0
+            } else {
+                (count * BAR_WIDTH).div_ceil(best)
+            };
+            format!(
+                "**{}.** {}\n`{}{}` {}{}",
+                i + 1,
+                format_time(*slot),
+                "█".repeat(filled),
+                "░".repeat(BAR_WIDTH - filled),
+                count,
+                if leader == Some(*slot) { " ⭐" } else { "" }
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// A slot as plain text, select menus don't render Discord timestamps
+fn slot_label(slot: i64) -> String {
+    DateTime::from_timestamp(slot, 0)
+        .map(|date| date.format("%a %d %b %Y, %H:%M UTC").to_string())
+        .unwrap_or_else(|| slot.to_string())
+}
+
+/// The embed of a poll, with its current votes. A locked poll shows the picked time
+pub fn poll_embed(
+    event: &EventData,
+    poll: &DatePoll,
+    votes: &[(i64, u64)],
+    locked: Option<i64>,
+) -> CreateEmbed {
+    let tally = tally(&poll.slots, votes);
+    let voters = {
+        let mut users: Vec<u64> = votes.iter().map(|(_, user)| *user).collect();
+        users.sort_unstable();
+        users.dedup();
+        users.len()
+    };
+
+    let (description, footer) = match locked {
+        Some(slot) => (
+            format!("Picked: {}\n\n{}", format_time(slot), heat_map(&tally)),
+            String::from("This poll is closed"),
+        ),
+        None => (
+            heat_map(&tally),
+            format!(
+                "{} voted. Pick every time you're available, managers close the poll with /event poll_lock",
+                voters
+            ),
+        ),
+    };
+
+    CreateEmbed::new()
+        .title(format!("When should {} take place ?", event.name))
+        .description(description)
+        .footer(CreateEmbedFooter::new(footer))
+        .colour(if locked.is_some() {
+            Colour::DARK_GREY
+        } else {
+            Colour::BLURPLE
+        })
+}
+
+/// The menu people pick the times they're available at with
+pub fn poll_menu(event_id: i64, slots: &[i64]) -> CreateActionRow {
+    let options = slots
+        .iter()
+        .map(|slot| CreateSelectMenuOption::new(slot_label(*slot), slot.to_string()))
+        .collect();
+
+    CreateActionRow::SelectMenu(
+        CreateSelectMenu::new(
+            format!("{}{}", POLL_PREFIX, event_id),
+            CreateSelectMenuKind::String { options },
+        )
+        .placeholder("When are you available ?")
+        .min_values(0)
+        .max_values(slots.len() as u8),
+    )
+}
+
+/// Saves the answer of whoever used a poll's menu, and updates the poll's votes
+pub async fn vote(
+    cache_http: impl CacheHttp,
+    db: &Repository,
+    interaction: &ComponentInteraction,
+) -> Result<(), Error> {
+    let http = cache_http.http();
+    let event_id = match interaction
+        .data
+        .custom_id
+        .strip_prefix(POLL_PREFIX)
+        .and_then(|id| id.parse::<i64>().ok())
+    {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+
+    let closed = |text: &str| {
+        CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(text)
+                .ephemeral(true),
+        )
+    };
+
+    let (_, event) = match db.get_event_by_id(event_id).await {
+        Ok(found) => found,
+        Err(_) => {
+            interaction
+                .create_response(http, closed("This event doesn't exist anymore"))
+                .await?;
+            return Ok(());
+        }
+    };
+    let poll = match db.get_poll(event_id).await {
+        Ok(poll) if poll.message_id == u64::from(interaction.message.id) => poll,
+        _ => {
+            interaction
+                .create_response(http, closed("This poll is closed"))
+                .await?;
+            return Ok(());
+        }
+    };
+    if event.archived_at.is_some()
+        || !matches!(event.status, EventStatus::Draft | EventStatus::Published)
+    {
+        interaction
+            .create_response(
+                http,
+                closed(&format!("{} can't be moved anymore", event.name)),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    let slots: Vec<i64> = match &interaction.data.kind {
+        ComponentInteractionDataKind::StringSelect { values } => {
+            values.iter().filter_map(|v| v.parse().ok()).collect()
+        }
+        _ => return Ok(()),
+    };
+    let user_id = u64::from(interaction.user.id);
+    db.set_poll_votes(event_id, user_id, slots.clone()).await?;
+    let votes = db.get_poll_votes(event_id).await?;
+
+    interaction
+        .create_response(
+            http,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embed(poll_embed(&event, &poll, &votes, None)),
+            ),
+        )
+        .await?;
+
+    let answer = if slots.is_empty() {
+        String::from("You're not available at any of these times")
+    } else {
+        format!(
+            "You're available {}",
+            slots
+                .iter()
+                .map(|slot| format_time(*slot))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    };
+    interaction
+        .create_followup(
+            http,
+            CreateInteractionResponseFollowup::new()
+                .content(answer)
+                .ephemeral(true),
+        )
+        .await?;
+
+    info!(
+        "{} voted for {} slots of the poll of event {}(id {})",
+        user_id,
+        slots.len(),
+        event.name,
+        event_id
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_slots() {
+        assert_eq!(
+            parse_slots("2024-12-31 20:00, <t:1700000000:F>,1700000000"),
+            Ok(vec![1_700_000_000, 1_735_675_200])
+        );
+        assert!(parse_slots("1700000000").is_err());
+        assert!(parse_slots("1700000000, tomorrow").is_err());
+        let many = (0..26).map(|i| i.to_string()).collect::<Vec<_>>().join(",");
+        assert!(parse_slots(&many).is_err());
+    }
+
+    #[test]
+    fn picks_the_winner() {
+        let votes = [(1, 10), (2, 10), (2, 11), (3, 10), (3, 12)];
+        let counts = tally(&[1, 2, 3, 4], &votes);
+        assert_eq!(counts, vec![(1, 1), (2, 2), (3, 2), (4, 0)]);
+        assert_eq!(winner(&counts), Some(2));
+        assert_eq!(winner(&tally(&[1, 2], &[])), None);
+    }
+
+    #[test]
+    fn draws_the_heat_map() {
+        let map = heat_map(&tally(&[1, 2, 3], &[(1, 10), (2, 10), (2, 11)]));
+        let lines: Vec<&str> = map.lines().collect();
+        assert_eq!(lines[0], "**1.** <t:1:F>");
+        assert_eq!(lines[1], "`█████░░░░░` 1");
+        assert_eq!(lines[3], "`██████████` 2 ⭐");
+        assert_eq!(lines[5], "`░░░░░░░░░░` 0");
+    }
+}
@@ -0,0 +1,123 @@
+use crate::bacchus::Error;
+use crate::events::{EventData, Outcome, Reliability};
+use crate::participation;
+use crate::repository::Repository;
+use crate::scheduler;
+use poise::serenity_prelude::Cache;
+use tracing::info;
+
+/// Leaving an event less than this long before it starts, or after, is a late cancellation
+pub const LATE_CANCEL: i64 = 24 * 60 * 60;
+
+/// Whether leaving at [now] an event starting at [starts_at] is a late cancellation.
+/// Events without a start time can be left anytime
+pub fn is_late_cancel(starts_at: Option<i64>, now: i64) -> bool {
+    starts_at.is_some_and(|start| now >= start - LATE_CANCEL)
+}
+
+/// Share of the tracked events a member was expected at that they came to, in percent.
+/// None until one was tracked
+pub fn score(reliability: &Reliability) -> Option<u32> {
+    let expected = reliability.attended + reliability.late_cancels + reliability.no_shows;
+    (expected > 0).then(|| reliability.attended * 100 / expected)
+}
+
+/// Whether a member already let an event down, worth telling managers about
+pub fn has_misses(reliability: &Reliability) -> bool {
+    reliability.late_cancels + reliability.no_shows > 0
+}
+
+/// One line summing up a member's record
+pub fn describe(reliability: &Reliability) -> String {
+    format!(
+        "joined {}, attended {}, cancelled late {}, no-show {} ({})",
+        reliability.joined,
+        reliability.attended,
+        reliability.late_cancels,
+        reliability.no_shows,
+        match score(reliability) {
+            Some(score) => format!("{}% reliable", score),
+            None => String::from("no attendance taken yet"),
+        }
+    )
+}
+
+/// Adds [outcome] to the history of [user_id] on the event's server
+pub async fn record(
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+    user_id: u64,
+    outcome: Outcome,
+) -> Result<(), Error> {
+    db.record_outcome(
+        event.server_id,
+        user_id,
+        event_id,
+        outcome,
+        scheduler::now(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Marks the participants of a completed event who never checked in as no-shows.
+/// Events that didn't take attendance count against nobody. Returns the number of no-shows
+pub async fn settle(
+    cache: &Cache,
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+) -> Result<usize, Error> {
+    let attendance = db.get_attendance(event_id).await?;
+    if attendance.is_empty() {
+        return Ok(0);
+    }
+
+    let mut no_shows = 0;
+    for user_id in participation::players(cache, db, event_id).await? {
+        if !attendance.iter().any(|(user, _)| *user == user_id) {
+            record(db, event_id, event, user_id, Outcome::NoShow).await?;
+            no_shows += 1;
+        }
+    }
+    info!(
+        "{} participants of event {}(id {}) didn't show up",
+        no_shows, event.name, event_id
+    );
+    Ok(no_shows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spots_late_cancels() {
+        let start = 10 * LATE_CANCEL;
+        assert!(!is_late_cancel(None, start));
+        assert!(!is_late_cancel(Some(start), start - LATE_CANCEL - 1));
+        assert!(is_late_cancel(Some(start), start - LATE_CANCEL));
+        assert!(is_late_cancel(Some(start), start + 1));
+    }
+
+    #[test]
+    fn scores_members() {
+        let mut reliability = Reliability {
+            joined: 5,
+            ..Reliability::default()
+        };
+        assert_eq!(score(&reliability), None);
+        assert!(!has_misses(&reliability));
+
+        reliability.attended = 2;
+        reliability.late_cancels = 1;
+        reliability.no_shows = 1;
+        assert_eq!(score(&reliability), Some(50));
+        assert!(has_misses(&reliability));
+        assert_eq!(
+            describe(&reliability),
+            "joined 5, attended 2, cancelled late 1, no-show 1 (50% reliable)"
+        );
+    }
+}
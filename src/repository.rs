@@ -0,0 +1,431 @@
+use crate::events::{Ban, DatePoll, Eligibility, EventChange, EventData, EventStatus, Team, TeamSlot};
+use crate::events::{Bracket, CheckIn, MatchReport, Outcome, Reliability};
+use crate::layout::Template;
+use crate::settings::GuildSettings;
+use crate::store::{EventStore, StoreError, StoreResult};
+use std::sync::Arc;
+
+/// Async access to the database.
+/// Every query runs on tokio's blocking thread pool, so disk I/O never stalls the gateway
+#[derive(Clone)]
+pub struct Repository {
+    store: Arc<dyn EventStore>,
+}
+
+impl Repository {
+    pub fn new(store: Arc<dyn EventStore>) -> Self {
+        Repository { store }
+    }
+
+    /// Runs [query] against the store, on a thread where blocking is allowed
+    pub async fn run<T, F>(&self, query: F) -> StoreResult<T>
+    where
+        F: FnOnce(&dyn EventStore) -> StoreResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let store = self.store.clone();
+        tokio::task::spawn_blocking(move || query(store.as_ref()))
+            .await
+            .map_err(|e| StoreError::Backend(Box::new(e)))?
+    }
+
+    pub async fn create_tables(&self) -> StoreResult<()> {
+        self.run(|store| store.create_tables()).await
+    }
+
+    /// Inserts a new event, returns its UID
+    pub async fn insert_event(&self, data: EventData) -> StoreResult<i64> {
+        self.run(move |store| store.insert_event(data)).await
+    }
+
+    pub async fn insert_channels(&self, event_id: i64, channels: Vec<u64>) -> StoreResult<()> {
+        self.run(move |store| store.insert_channels(event_id, channels))
+            .await
+    }
+
+    pub async fn get_channels_by_event_id(&self, event_id: i64) -> StoreResult<Vec<u64>> {
+        self.run(move |store| store.get_channels_by_event_id(event_id))
+            .await
+    }
+
+    pub async fn delete_channel(&self, event_id: i64, channel_id: u64) -> StoreResult<usize> {
+        self.run(move |store| store.delete_channel(event_id, channel_id))
+            .await
+    }
+
+    pub async fn delete_event(&self, event_id: i64) -> StoreResult<usize> {
+        self.run(move |store| store.delete_event(event_id)).await
+    }
+
+    pub async fn get_event_by_channel(&self, channel_id: u64) -> StoreResult<(i64, EventData)> {
+        self.run(move |store| store.get_event_by_channel(channel_id))
+            .await
+    }
+
+    pub async fn get_event_by_manifest(&self, manifest_id: u64) -> StoreResult<(i64, EventData)> {
+        self.run(move |store| store.get_event_by_manifest(manifest_id))
+            .await
+    }
+
+    pub async fn get_event_by_id(&self, event_id: i64) -> StoreResult<(i64, EventData)> {
+        self.run(move |store| store.get_event_by_id(event_id)).await
+    }
+
+    pub async fn get_event_by_category(&self, category_id: u64) -> StoreResult<(i64, EventData)> {
+        self.run(move |store| store.get_event_by_category(category_id))
+            .await
+    }
+
+    pub async fn get_events_by_guild(&self, server_id: u64) -> StoreResult<Vec<(i64, EventData)>> {
+        self.run(move |store| store.get_events_by_guild(server_id))
+            .await
+    }
+
+    pub async fn get_timed_voice_events(&self) -> StoreResult<Vec<(i64, EventData)>> {
+        self.run(|store| store.get_timed_voice_events()).await
+    }
+
+    pub async fn set_voice_open(&self, event_id: i64, open: bool) -> StoreResult<()> {
+        self.run(move |store| store.set_voice_open(event_id, open))
+            .await
+    }
+
+    pub async fn archive_event(&self, event_id: i64, archived_at: i64) -> StoreResult<()> {
+        self.run(move |store| store.archive_event(event_id, archived_at))
+            .await
+    }
+
+    pub async fn get_archived_events(&self) -> StoreResult<Vec<(i64, EventData)>> {
+        self.run(|store| store.get_archived_events()).await
+    }
+
+    pub async fn set_transcript_exported(&self, event_id: i64, exported_at: i64) -> StoreResult<()> {
+        self.run(move |store| store.set_transcript_exported(event_id, exported_at))
+            .await
+    }
+
+    pub async fn set_event_times(
+        &self,
+        event_id: i64,
+        starts_at: Option<i64>,
+        ends_at: Option<i64>,
+    ) -> StoreResult<()> {
+        self.run(move |store| store.set_event_times(event_id, starts_at, ends_at))
+            .await
+    }
+
+    pub async fn insert_event_change(&self, event_id: i64, change: EventChange) -> StoreResult<()> {
+        self.run(move |store| store.insert_event_change(event_id, change))
+            .await
+    }
+
+    pub async fn get_event_changes(&self, event_id: i64) -> StoreResult<Vec<EventChange>> {
+        self.run(move |store| store.get_event_changes(event_id))
+            .await
+    }
+
+    pub async fn set_dm_notifications(&self, user_id: u64, enabled: bool) -> StoreResult<()> {
+        self.run(move |store| store.set_dm_notifications(user_id, enabled))
+            .await
+    }
+
+    pub async fn get_dm_subscribers(&self, event_id: i64) -> StoreResult<Vec<u64>> {
+        self.run(move |store| store.get_dm_subscribers(event_id))
+            .await
+    }
+
+    pub async fn create_poll(&self, event_id: i64, poll: DatePoll) -> StoreResult<()> {
+        self.run(move |store| store.create_poll(event_id, poll)).await
+    }
+
+    pub async fn get_poll(&self, event_id: i64) -> StoreResult<DatePoll> {
+        self.run(move |store| store.get_poll(event_id)).await
+    }
+
+    pub async fn set_poll_votes(
+        &self,
+        event_id: i64,
+        user_id: u64,
+        slots: Vec<i64>,
+    ) -> StoreResult<()> {
+        self.run(move |store| store.set_poll_votes(event_id, user_id, slots))
+            .await
+    }
+
+    pub async fn get_poll_votes(&self, event_id: i64) -> StoreResult<Vec<(i64, u64)>> {
+        self.run(move |store| store.get_poll_votes(event_id))
+            .await
+    }
+
+    pub async fn delete_poll(&self, event_id: i64) -> StoreResult<usize> {
+        self.run(move |store| store.delete_poll(event_id)).await
+    }
+
+    pub async fn insert_join_request(
+        &self,
+        event_id: i64,
+        user_id: u64,
+        message_id: u64,
+    ) -> StoreResult<()> {
+        self.run(move |store| store.insert_join_request(event_id, user_id, message_id))
+            .await
+    }
+
+    pub async fn get_join_request(&self, event_id: i64, user_id: u64) -> StoreResult<u64> {
+        self.run(move |store| store.get_join_request(event_id, user_id))
+            .await
+    }
+
+    pub async fn delete_join_request(&self, event_id: i64, user_id: u64) -> StoreResult<usize> {
+        self.run(move |store| store.delete_join_request(event_id, user_id))
+            .await
+    }
+
+    pub async fn get_eligibility(&self, event_id: i64) -> StoreResult<Eligibility> {
+        self.run(move |store| store.get_eligibility(event_id)).await
+    }
+
+    pub async fn set_eligibility(&self, event_id: i64, rules: Eligibility) -> StoreResult<()> {
+        self.run(move |store| store.set_eligibility(event_id, rules))
+            .await
+    }
+
+    pub async fn insert_event_ban(&self, event_id: i64, ban: Ban) -> StoreResult<()> {
+        self.run(move |store| store.insert_event_ban(event_id, ban))
+            .await
+    }
+
+    pub async fn delete_event_ban(&self, event_id: i64, user_id: u64) -> StoreResult<usize> {
+        self.run(move |store| store.delete_event_ban(event_id, user_id))
+            .await
+    }
+
+    pub async fn get_event_bans(&self, event_id: i64) -> StoreResult<Vec<Ban>> {
+        self.run(move |store| store.get_event_bans(event_id)).await
+    }
+
+    pub async fn insert_server_ban(&self, server_id: u64, ban: Ban) -> StoreResult<()> {
+        self.run(move |store| store.insert_server_ban(server_id, ban))
+            .await
+    }
+
+    pub async fn delete_server_ban(&self, server_id: u64, user_id: u64) -> StoreResult<usize> {
+        self.run(move |store| store.delete_server_ban(server_id, user_id))
+            .await
+    }
+
+    pub async fn get_server_bans(&self, server_id: u64) -> StoreResult<Vec<Ban>> {
+        self.run(move |store| store.get_server_bans(server_id))
+            .await
+    }
+
+    pub async fn get_ban(&self, event_id: i64, user_id: u64) -> StoreResult<Ban> {
+        self.run(move |store| store.get_ban(event_id, user_id))
+            .await
+    }
+
+    pub async fn insert_slot(&self, event_id: i64, slot: TeamSlot) -> StoreResult<()> {
+        self.run(move |store| store.insert_slot(event_id, slot))
+            .await
+    }
+
+    pub async fn delete_slot(&self, event_id: i64, name: String) -> StoreResult<usize> {
+        self.run(move |store| store.delete_slot(event_id, &name))
+            .await
+    }
+
+    pub async fn get_slots(&self, event_id: i64) -> StoreResult<Vec<TeamSlot>> {
+        self.run(move |store| store.get_slots(event_id))
+            .await
+    }
+
+    pub async fn take_slot(&self, event_id: i64, user_id: u64, name: String) -> StoreResult<bool> {
+        self.run(move |store| store.take_slot(event_id, user_id, &name))
+            .await
+    }
+
+    pub async fn leave_slot(&self, event_id: i64, user_id: u64) -> StoreResult<usize> {
+        self.run(move |store| store.leave_slot(event_id, user_id))
+            .await
+    }
+
+    pub async fn get_slot_members(&self, event_id: i64) -> StoreResult<Vec<(String, u64)>> {
+        self.run(move |store| store.get_slot_members(event_id))
+            .await
+    }
+
+    pub async fn set_skill(&self, event_id: i64, user_id: u64, skill: Option<i64>) -> StoreResult<()> {
+        self.run(move |store| store.set_skill(event_id, user_id, skill))
+            .await
+    }
+
+    pub async fn get_skills(&self, event_id: i64) -> StoreResult<Vec<(u64, i64)>> {
+        self.run(move |store| store.get_skills(event_id))
+            .await
+    }
+
+    pub async fn insert_team(&self, event_id: i64, team: Team) -> StoreResult<()> {
+        self.run(move |store| store.insert_team(event_id, team))
+            .await
+    }
+
+    pub async fn get_teams(&self, event_id: i64) -> StoreResult<Vec<Team>> {
+        self.run(move |store| store.get_teams(event_id))
+            .await
+    }
+
+    pub async fn delete_teams(&self, event_id: i64) -> StoreResult<usize> {
+        self.run(move |store| store.delete_teams(event_id))
+            .await
+    }
+
+    pub async fn create_bracket(&self, event_id: i64, bracket: Bracket) -> StoreResult<()> {
+        self.run(move |store| store.create_bracket(event_id, bracket))
+            .await
+    }
+
+    pub async fn get_bracket(&self, event_id: i64) -> StoreResult<Bracket> {
+        self.run(move |store| store.get_bracket(event_id))
+            .await
+    }
+
+    pub async fn delete_bracket(&self, event_id: i64) -> StoreResult<usize> {
+        self.run(move |store| store.delete_bracket(event_id))
+            .await
+    }
+
+    pub async fn report_result(&self, event_id: i64, report: MatchReport) -> StoreResult<()> {
+        self.run(move |store| store.report_result(event_id, report))
+            .await
+    }
+
+    pub async fn confirm_result(&self, event_id: i64, match_id: String) -> StoreResult<usize> {
+        self.run(move |store| store.confirm_result(event_id, &match_id))
+            .await
+    }
+
+    pub async fn get_results(&self, event_id: i64) -> StoreResult<Vec<MatchReport>> {
+        self.run(move |store| store.get_results(event_id))
+            .await
+    }
+
+    pub async fn open_checkin(&self, event_id: i64, checkin: CheckIn) -> StoreResult<()> {
+        self.run(move |store| store.open_checkin(event_id, checkin))
+            .await
+    }
+
+    pub async fn get_checkin(&self, event_id: i64) -> StoreResult<CheckIn> {
+        self.run(move |store| store.get_checkin(event_id))
+            .await
+    }
+
+    pub async fn close_checkin(&self, event_id: i64) -> StoreResult<usize> {
+        self.run(move |store| store.close_checkin(event_id))
+            .await
+    }
+
+    pub async fn check_in(&self, event_id: i64, user_id: u64, at: i64) -> StoreResult<bool> {
+        self.run(move |store| store.check_in(event_id, user_id, at))
+            .await
+    }
+
+    pub async fn get_attendance(&self, event_id: i64) -> StoreResult<Vec<(u64, i64)>> {
+        self.run(move |store| store.get_attendance(event_id))
+            .await
+    }
+
+    pub async fn record_outcome(
+        &self,
+        server_id: u64,
+        user_id: u64,
+        event_id: i64,
+        outcome: Outcome,
+        at: i64,
+    ) -> StoreResult<bool> {
+        self.run(move |store| store.record_outcome(server_id, user_id, event_id, outcome, at))
+            .await
+    }
+
+    pub async fn get_reliability(&self, server_id: u64, user_id: u64) -> StoreResult<Reliability> {
+        self.run(move |store| store.get_reliability(server_id, user_id))
+            .await
+    }
+
+    pub async fn set_status(&self, event_id: i64, status: EventStatus) -> StoreResult<()> {
+        self.run(move |store| store.set_status(event_id, status))
+            .await
+    }
+
+    pub async fn set_manifest(
+        &self,
+        event_id: i64,
+        channel_id: u64,
+        manifest_id: u64,
+    ) -> StoreResult<()> {
+        self.run(move |store| store.set_manifest(event_id, channel_id, manifest_id))
+            .await
+    }
+
+    pub async fn insert_server_manager_role(
+        &self,
+        server_id: u64,
+        role_id: u64,
+    ) -> StoreResult<()> {
+        self.run(move |store| store.insert_server_manager_role(server_id, role_id))
+            .await
+    }
+
+    pub async fn get_server_manager_role_id(&self, server_id: u64) -> StoreResult<u64> {
+        self.run(move |store| store.get_server_manager_role_id(server_id))
+            .await
+    }
+
+    pub async fn get_guild_settings(&self, server_id: u64) -> StoreResult<GuildSettings> {
+        self.run(move |store| store.get_guild_settings(server_id))
+            .await
+    }
+
+    pub async fn set_guild_settings(
+        &self,
+        server_id: u64,
+        settings: GuildSettings,
+    ) -> StoreResult<()> {
+        self.run(move |store| store.set_guild_settings(server_id, settings))
+            .await
+    }
+
+    pub async fn insert_participant(&self, event_id: i64, user_id: u64) -> StoreResult<()> {
+        self.run(move |store| store.insert_participant(event_id, user_id))
+            .await
+    }
+
+    pub async fn delete_participant(&self, event_id: i64, user_id: u64) -> StoreResult<()> {
+        self.run(move |store| store.delete_participant(event_id, user_id))
+            .await
+    }
+
+    pub async fn get_participants(&self, event_id: i64) -> StoreResult<Vec<u64>> {
+        self.run(move |store| store.get_participants(event_id))
+            .await
+    }
+
+    pub async fn save_template(&self, server_id: u64, template: Template) -> StoreResult<()> {
+        self.run(move |store| store.save_template(server_id, template))
+            .await
+    }
+
+    pub async fn get_template(&self, server_id: u64, name: String) -> StoreResult<Template> {
+        self.run(move |store| store.get_template(server_id, &name))
+            .await
+    }
+
+    pub async fn get_templates(&self, server_id: u64) -> StoreResult<Vec<Template>> {
+        self.run(move |store| store.get_templates(server_id))
+            .await
+    }
+
+    pub async fn delete_template(&self, server_id: u64, name: String) -> StoreResult<usize> {
+        self.run(move |store| store.delete_template(server_id, &name))
+            .await
+    }
+}
@@ -0,0 +1,220 @@
+use crate::archive;
+use crate::bacchus::Error;
+use crate::events::EventData;
+use crate::repository::Repository;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use poise::serenity_prelude::{
+    Cache, ChannelId, ChannelType, GuildId, Http, PermissionOverwrite, PermissionOverwriteType,
+    Permissions, RoleId, UserId,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// How often the scheduler looks for voice channels to open or close, and archives to delete
+const TICK: Duration = Duration::from_secs(30);
+
+/// Reads a date given to a command: a Discord timestamp like <t:1700000000:F>,
+/// unix seconds, RFC 3339, or "YYYY-MM-DD HH:MM" in UTC
+pub fn parse_time(value: &str) -> Result<i64, String> {
+    let value = value.trim();
+    let discord = value
+        .strip_prefix("<t:")
+        .and_then(|v| v.strip_suffix('>'))
+        .map(|v| v.split(':').next().unwrap_or(v));
+
+    if let Ok(timestamp) = discord.unwrap_or(value).parse::<i64>() {
+        return Ok(timestamp);
+    }
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date.timestamp());
+    }
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M")
+        .map(|date| date.and_utc().timestamp())
+        .map_err(|_| {
+            format!(
+                "couldn't read the date `{}`, expected e.g. 2024-12-31 20:00 (UTC) or a Discord timestamp",
+                value
+            )
+        })
+}
+
+/// A Discord timestamp, shown to each reader in their own timezone
+pub fn format_time(timestamp: i64) -> String {
+    format!("<t:{}:F>", timestamp)
+}
+
+pub fn now() -> i64 {
+    Utc::now().timestamp()
+}
+
+/// Whether participants may connect to the voice channels of an event at [now].
+/// A missing bound leaves the window open on that side
+pub fn voice_open_at(starts_at: Option<i64>, ends_at: Option<i64>, now: i64) -> bool {
+    starts_at.is_none_or(|start| now >= start) && ends_at.is_none_or(|end| now < end)
+}
+
+/// The participants' overwrite on an event's voice and stage channels
+pub fn voice_overwrite(participant_role: RoleId, open: bool) -> PermissionOverwrite {
+    let access = Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES;
+    PermissionOverwrite {
+        allow: if open {
+            access | Permissions::CONNECT
+        } else {
+            access
+        },
+        deny: if open {
+            Permissions::empty()
+        } else {
+            Permissions::CONNECT
+        },
+        kind: PermissionOverwriteType::Role(participant_role),
+    }
+}
+
+/// Opens and closes timed voice channels as their events start and end,
+/// and deletes expired archives. Never returns
+pub async fn run(http: Arc<Http>, cache: Arc<Cache>, db: Repository) {
+    let mut interval = tokio::time::interval(TICK);
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = archive::purge_expired(&http, &db).await {
+            warn!("Couldn't delete expired archives: {}", e);
+        }
+
+        let events = match db.get_timed_voice_events().await {
+            Ok(events) => events,
+            Err(e) => {
+                warn!("Couldn't read timed voice channels: {}", e);
+                continue;
+            }
+        };
+
+        let now = now();
+        for (id, event) in events {
+            let open = voice_open_at(event.starts_at, event.ends_at, now);
+            if open != event.voice_open {
+                if let Err(e) = set_voice_access(&http, &cache, &db, id, &event, open).await {
+                    warn!(
+                        "Couldn't {} the voice channels of event {}(id {}): {}",
+                        if open { "open" } else { "close" },
+                        event.name,
+                        id,
+                        e
+                    );
+                }
+            }
+        }
+    }
+}
+
+async fn set_voice_access(
+    http: &Http,
+    cache: &Cache,
+    db: &Repository,
+    id: i64,
+    event: &EventData,
+    open: bool,
+) -> Result<(), Error> {
+    let overwrite = voice_overwrite(RoleId::new(event.participant_role_id), open);
+
+    for channel_id in db.get_channels_by_event_id(id).await? {
+        // A channel deleted by hand must not block the others
+        let channel = match ChannelId::new(channel_id).to_channel(http).await {
+            Ok(channel) => channel,
+            Err(e) => {
+                warn!(
+                    "Couldn't fetch channel {} of event {}: {}",
+                    channel_id, id, e
+                );
+                continue;
+            }
+        };
+        if let Some(channel) = channel.guild() {
+            if matches!(channel.kind, ChannelType::Voice | ChannelType::Stage) {
+                channel.create_permission(http, overwrite.clone()).await?;
+                if !open {
+                    disconnect_participants(http, cache, event, channel.guild_id, channel.id).await;
+                }
+            }
+        }
+    }
+
+    db.set_voice_open(id, open).await?;
+    info!(
+        "{} the voice channels of event {}(id {}) on server {}",
+        if open { "Opened" } else { "Closed" },
+        event.name,
+        id,
+        event.server_id
+    );
+
+    Ok(())
+}
+
+/// Takes the participants still in [channel_id] out of it, the permission only keeps new ones out.
+/// Managers keep their access and stay, and so does anyone whose roles aren't cached
+async fn disconnect_participants(
+    http: &Http,
+    cache: &Cache,
+    event: &EventData,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) {
+    let manager_role = RoleId::new(event.manager_role_id);
+    let connected: Vec<UserId> = match cache.guild(guild_id) {
+        Some(guild) => guild
+            .voice_states
+            .values()
+            .filter(|state| state.channel_id == Some(channel_id))
+            // Voice states don't always carry the member, and a member whose roles
+            // can't be found may be a manager: they stay
+            .filter(|state| {
+                state
+                    .member
+                    .as_ref()
+                    .or_else(|| guild.members.get(&state.user_id))
+                    .is_some_and(|member| !member.roles.contains(&manager_role))
+            })
+            .map(|state| state.user_id)
+            .collect(),
+        None => return,
+    };
+
+    for user_id in connected {
+        if let Err(e) = guild_id.disconnect_member(http, user_id).await {
+            warn!(
+                "Couldn't disconnect {} from channel {} of event {}: {}",
+                user_id, channel_id, event.name, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_times() {
+        assert_eq!(parse_time("1700000000").unwrap(), 1_700_000_000);
+        assert_eq!(parse_time("<t:1700000000:F>").unwrap(), 1_700_000_000);
+        assert_eq!(parse_time("<t:1700000000>").unwrap(), 1_700_000_000);
+        assert_eq!(parse_time("2023-11-14 22:13").unwrap(), 1_699_999_980);
+        assert_eq!(
+            parse_time("2023-11-14T23:13:20+01:00").unwrap(),
+            1_700_000_000
+        );
+        assert!(parse_time("next friday").is_err());
+    }
+
+    #[test]
+    fn voice_window() {
+        assert!(!voice_open_at(Some(100), Some(200), 99));
+        assert!(voice_open_at(Some(100), Some(200), 100));
+        assert!(!voice_open_at(Some(100), Some(200), 200));
+        assert!(voice_open_at(None, Some(200), 0));
+        assert!(voice_open_at(Some(100), None, 1000));
+    }
+}
@@ -0,0 +1,339 @@
+use crate::config::Config;
+use crate::layout::{parse_layout, ChannelSpec};
+use poise::serenity_prelude::ReactionType;
+
+const DEFAULT_MANAGER_ROLE_SUFFIX: &str = "-manager";
+const DEFAULT_PLAYER_ROLE_SUFFIX: &str = "-player";
+const DEFAULT_CREATOR_ROLE_NAME: &str = "Menad";
+const DEFAULT_ANNOUNCEMENT_TEXT: &str =
+    ":trumpet: :trumpet: :trumpet: NEW EVENT :trumpet: :trumpet: :trumpet:";
+
+/// A server's overrides, stored in SERVERS.
+/// Unset values fall back to the bot's configuration or to the built-in defaults
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GuildSettings {
+    pub creator_role_name: Option<String>,
+    pub manager_role_suffix: Option<String>,
+    pub player_role_suffix: Option<String>,
+    pub channel_layout: Option<String>,
+    pub rsvp_emoji: Option<String>,
+    pub announcement_text: Option<String>,
+    /// Where manifests are posted, instead of the channel /event create is ran in
+    pub announcement_channel_id: Option<u64>,
+    /// Where thread events are opened, a text or forum channel
+    pub thread_channel_id: Option<u64>,
+    /// The category archived events are moved to, created on the first /event archive
+    pub archive_category_id: Option<u64>,
+    /// How long archived events are kept before being deleted, forever if unset
+    pub archive_retention_days: Option<u32>,
+    /// Where transcripts of deleted events are posted, instead of the DMs of whoever deleted them
+    pub log_channel_id: Option<u64>,
+}
+
+/// The settings /config can change
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SettingKey {
+    #[name = "creator_role_name"]
+    CreatorRoleName,
+    #[name = "manager_role_suffix"]
+    ManagerRoleSuffix,
+    #[name = "player_role_suffix"]
+    PlayerRoleSuffix,
+    #[name = "channel_layout"]
+    ChannelLayout,
+    #[name = "rsvp_emoji"]
+    RsvpEmoji,
+    #[name = "announcement_text"]
+    AnnouncementText,
+    #[name = "announcement_channel"]
+    AnnouncementChannel,
+    #[name = "thread_channel"]
+    ThreadChannel,
+    #[name = "archive_category"]
+    ArchiveCategory,
+    #[name = "archive_retention_days"]
+    ArchiveRetentionDays,
+    #[name = "log_channel"]
+    LogChannel,
+}
+
+impl SettingKey {
+    pub const ALL: [SettingKey; 11] = [
+        SettingKey::CreatorRoleName,
+        SettingKey::ManagerRoleSuffix,
+        SettingKey::PlayerRoleSuffix,
+        SettingKey::ChannelLayout,
+        SettingKey::RsvpEmoji,
+        SettingKey::AnnouncementText,
+        SettingKey::AnnouncementChannel,
+        SettingKey::ThreadChannel,
+        SettingKey::ArchiveCategory,
+        SettingKey::ArchiveRetentionDays,
+        SettingKey::LogChannel,
+    ];
+
+    /// Checks a new value before it is stored, returns it cleaned up
+    pub fn validate(self, value: &str) -> Result<String, String> {
+        let value = value.trim();
+        match self {
+            SettingKey::CreatorRoleName
+            | SettingKey::ManagerRoleSuffix
+            | SettingKey::PlayerRoleSuffix => {
+                if value.is_empty() || value.chars().count() > 50 {
+                    return Err(String::from("must be between 1 and 50 characters"));
+                }
+            }
+            SettingKey::ChannelLayout => {
+                parse_layout(value)?;
+            }
+            SettingKey::RsvpEmoji => {
+                if value.is_empty() || ReactionType::try_from(value).is_err() {
+                    return Err(String::from(
+                        "must be a unicode emoji or a custom one like <:name:id>",
+                    ));
+                }
+            }
+            SettingKey::AnnouncementText => {
+                if value.is_empty() || value.chars().count() > 2000 {
+                    return Err(String::from("must be between 1 and 2000 characters"));
+                }
+            }
+            SettingKey::AnnouncementChannel
+            | SettingKey::ThreadChannel
+            | SettingKey::ArchiveCategory
+            | SettingKey::LogChannel => {
+                return parse_channel(value)
+                    .map(|id| id.to_string())
+                    .ok_or_else(|| String::from("must be a channel, like #announcements"));
+            }
+            SettingKey::ArchiveRetentionDays => {
+                if !value.parse::<u32>().is_ok_and(|days| days > 0) {
+                    return Err(String::from("must be a number of days, at least 1"));
+                }
+            }
+        }
+        Ok(value.to_string())
+    }
+}
+
+/// Reads a channel mention like <#123>, or a bare channel id
+fn parse_channel(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let id = value
+        .strip_prefix("<#")
+        .and_then(|v| v.strip_suffix('>'))
+        .unwrap_or(value);
+    id.parse::<u64>().ok().filter(|id| *id != 0)
+}
+
+impl GuildSettings {
+    /// The server's own value for [key], if any
+    pub fn get(&self, key: SettingKey) -> Option<String> {
+        match key {
+            SettingKey::CreatorRoleName => self.creator_role_name.clone(),
+            SettingKey::ManagerRoleSuffix => self.manager_role_suffix.clone(),
+            SettingKey::PlayerRoleSuffix => self.player_role_suffix.clone(),
+            SettingKey::ChannelLayout => self.channel_layout.clone(),
+            SettingKey::RsvpEmoji => self.rsvp_emoji.clone(),
+            SettingKey::AnnouncementText => self.announcement_text.clone(),
+            SettingKey::AnnouncementChannel => {
+                self.announcement_channel_id.map(|id| format!("<#{}>", id))
+            }
+            SettingKey::ThreadChannel => self.thread_channel_id.map(|id| format!("<#{}>", id)),
+            SettingKey::ArchiveCategory => {
+                self.archive_category_id.map(|id| format!("<#{}>", id))
+            }
+            SettingKey::ArchiveRetentionDays => {
+                self.archive_retention_days.map(|days| days.to_string())
+            }
+            SettingKey::LogChannel => self.log_channel_id.map(|id| format!("<#{}>", id)),
+        }
+    }
+
+    /// Replaces a setting with a value checked by [SettingKey::validate], [None] restores its default
+    pub fn set(&mut self, key: SettingKey, value: Option<String>) {
+        match key {
+            SettingKey::CreatorRoleName => self.creator_role_name = value,
+            SettingKey::ManagerRoleSuffix => self.manager_role_suffix = value,
+            SettingKey::PlayerRoleSuffix => self.player_role_suffix = value,
+            SettingKey::ChannelLayout => self.channel_layout = value,
+            SettingKey::RsvpEmoji => self.rsvp_emoji = value,
+            SettingKey::AnnouncementText => self.announcement_text = value,
+            SettingKey::AnnouncementChannel => {
+                self.announcement_channel_id = value.as_deref().and_then(parse_channel)
+            }
+            SettingKey::ThreadChannel => {
+                self.thread_channel_id = value.as_deref().and_then(parse_channel)
+            }
+            SettingKey::ArchiveCategory => {
+                self.archive_category_id = value.as_deref().and_then(parse_channel)
+            }
+            SettingKey::ArchiveRetentionDays => {
+                self.archive_retention_days = value.and_then(|days| days.parse().ok())
+            }
+            SettingKey::LogChannel => {
+                self.log_channel_id = value.as_deref().and_then(parse_channel)
+            }
+        }
+    }
+
+    /// The value in effect for [key], and whether it is the server's own
+    pub fn effective(&self, key: SettingKey, config: &Config) -> (String, bool) {
+        match self.get(key) {
+            Some(value) => (value, true),
+            None => (
+                match key {
+                    SettingKey::CreatorRoleName => String::from(DEFAULT_CREATOR_ROLE_NAME),
+                    SettingKey::ManagerRoleSuffix => String::from(DEFAULT_MANAGER_ROLE_SUFFIX),
+                    SettingKey::PlayerRoleSuffix => String::from(DEFAULT_PLAYER_ROLE_SUFFIX),
+                    SettingKey::ChannelLayout => config
+                        .channel_layout
+                        .iter()
+                        .map(|spec| format!("{}:{}", spec.name, spec.kind.name()))
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                    SettingKey::RsvpEmoji => config.rsvp_emoji.clone(),
+                    SettingKey::AnnouncementText => String::from(DEFAULT_ANNOUNCEMENT_TEXT),
+                    SettingKey::AnnouncementChannel | SettingKey::ThreadChannel => {
+                        String::from("the channel /event create is ran in")
+                    }
+                    SettingKey::ArchiveCategory => {
+                        String::from("an Archive category, created when first needed")
+                    }
+                    SettingKey::ArchiveRetentionDays => String::from("forever"),
+                    SettingKey::LogChannel => {
+                        String::from("the DMs of whoever deletes the event")
+                    }
+                },
+                false,
+            ),
+        }
+    }
+
+    pub fn creator_role_name(&self) -> String {
+        self.creator_role_name
+            .clone()
+            .unwrap_or_else(|| String::from(DEFAULT_CREATOR_ROLE_NAME))
+    }
+
+    pub fn manager_role_name(&self, event_name: &str) -> String {
+        format!(
+            "{}{}",
+            event_name,
+            self.manager_role_suffix
+                .as_deref()
+                .unwrap_or(DEFAULT_MANAGER_ROLE_SUFFIX)
+        )
+    }
+
+    pub fn player_role_name(&self, event_name: &str) -> String {
+        format!(
+            "{}{}",
+            event_name,
+            self.player_role_suffix
+                .as_deref()
+                .unwrap_or(DEFAULT_PLAYER_ROLE_SUFFIX)
+        )
+    }
+
+    pub fn channel_layout(&self, config: &Config) -> Vec<ChannelSpec> {
+        self.channel_layout
+            .as_deref()
+            .and_then(|layout| parse_layout(layout).ok())
+            .unwrap_or_else(|| config.channel_layout.clone())
+    }
+
+    pub fn rsvp_emoji(&self, config: &Config) -> String {
+        self.rsvp_emoji
+            .clone()
+            .unwrap_or_else(|| config.rsvp_emoji.clone())
+    }
+
+    pub fn announcement_text(&self) -> String {
+        self.announcement_text
+            .clone()
+            .unwrap_or_else(|| String::from(DEFAULT_ANNOUNCEMENT_TEXT))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Cli, FileConfig};
+
+    fn config() -> Config {
+        let cli = Cli {
+            token: Some(String::from("token")),
+            rsvp_emoji: Some(String::from("🎉")),
+            ..Default::default()
+        };
+        Config::resolve(cli, FileConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn falls_back_to_defaults() {
+        let settings = GuildSettings::default();
+        let config = config();
+        assert_eq!(settings.manager_role_name("LAN"), "LAN-manager");
+        assert_eq!(settings.player_role_name("LAN"), "LAN-player");
+        assert_eq!(settings.rsvp_emoji(&config), "🎉");
+        assert_eq!(settings.channel_layout(&config), config.channel_layout);
+        assert_eq!(
+            settings.effective(SettingKey::ChannelLayout, &config),
+            (String::from("general:text"), false)
+        );
+    }
+
+    #[test]
+    fn overrides_and_resets() {
+        let mut settings = GuildSettings::default();
+        let config = config();
+        settings.set(
+            SettingKey::PlayerRoleSuffix,
+            Some(String::from(" (player)")),
+        );
+        settings.set(
+            SettingKey::ChannelLayout,
+            Some(String::from("chat, lobby:voice")),
+        );
+        assert_eq!(settings.player_role_name("LAN"), "LAN (player)");
+        assert_eq!(settings.channel_layout(&config).len(), 2);
+        assert_eq!(
+            settings.effective(SettingKey::PlayerRoleSuffix, &config),
+            (String::from(" (player)"), true)
+        );
+
+        settings.set(SettingKey::AnnouncementChannel, Some(String::from("42")));
+        assert_eq!(settings.announcement_channel_id, Some(42));
+        assert_eq!(
+            settings.effective(SettingKey::AnnouncementChannel, &config),
+            (String::from("<#42>"), true)
+        );
+
+        settings.set(SettingKey::PlayerRoleSuffix, None);
+        assert_eq!(settings.player_role_name("LAN"), "LAN-player");
+    }
+
+    #[test]
+    fn validates_values() {
+        assert!(SettingKey::ChannelLayout.validate("lobby:voice").is_err());
+        assert!(SettingKey::RsvpEmoji.validate("").is_err());
+        assert!(SettingKey::ManagerRoleSuffix.validate("   ").is_err());
+        assert!(SettingKey::AnnouncementChannel.validate("#news").is_err());
+        assert_eq!(
+            SettingKey::AnnouncementChannel.validate("<#42>").unwrap(),
+            "42"
+        );
+        assert_eq!(SettingKey::ThreadChannel.validate("42").unwrap(), "42");
+        assert!(SettingKey::ArchiveRetentionDays.validate("0").is_err());
+        assert!(SettingKey::ArchiveRetentionDays.validate("a week").is_err());
+        assert_eq!(
+            SettingKey::ArchiveRetentionDays.validate(" 30 ").unwrap(),
+            "30"
+        );
+        assert_eq!(
+            SettingKey::CreatorRoleName.validate(" Organizer ").unwrap(),
+            "Organizer"
+        );
+    }
+}
@@ -0,0 +1,343 @@
+use crate::bacchus::Error;
+use crate::events::{EventData, Team};
+use crate::layout::MAX_CATEGORY_CHANNELS;
+use crate::repository::Repository;
+use poise::serenity_prelude::{
+    ChannelId, ChannelType, CreateChannel, EditRole, GuildId, Http, PermissionOverwrite,
+    PermissionOverwriteType, Permissions, RoleId, UserId,
+};
+use rand::seq::SliceRandom;
+use tracing::{info, warn};
+
+/// Two channels per team, a category holds 50. The event's own channels may leave room for fewer, see [room]
+pub const MAX_TEAMS: usize = 20;
+/// The text and voice channels of a team
+const TEAM_CHANNELS: [ChannelType; 2] = [ChannelType::Text, ChannelType::Voice];
+
+/// How /event teams generate splits the participants
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TeamSplit {
+    /// Teams of the same size, whoever ends up together
+    #[default]
+    #[name = "random"]
+    Random,
+    /// Teams whose total skill is as close as possible, see /event teams skill
+    #[name = "skill"]
+    Skill,
+}
+
+/// Deals [players] to [count] teams like cards, the first teams get one more on uneven splits
+pub fn deal(players: &[u64], count: usize) -> Vec<Vec<u64>> {
+    let mut teams = vec![vec![]; count];
+    for (i, player) in players.iter().enumerate() {
+        teams[i % count].push(*player);
+    }
+    teams
+}
+
+/// Splits [players] into [count] teams of the same size, give or take one, with close total skills.
+/// The strongest players are placed first, each in the smallest team with the lowest total.
+/// Players of the same skill keep their order
+pub fn balance(players: &[(u64, i64)], count: usize) -> Vec<Vec<u64>> {
+    let mut players = players.to_vec();
+    players.sort_by_key(|(_, skill)| std::cmp::Reverse(*skill));
+
+    let mut teams: Vec<(Vec<u64>, i64)> = vec![(vec![], 0); count];
+    for (player, skill) in players {
+        let (members, total) = teams
+            .iter_mut()
+            .min_by_key(|(members, total)| (members.len(), *total))
+            .expect("at least one team");
+        members.push(player);
+        *total += skill;
+    }
+    teams.into_iter().map(|(members, _)| members).collect()
+}
+
+/// Total skill of [members], people without a skill count as 0
+pub fn total_skill(members: &[u64], skills: &[(u64, i64)]) -> i64 {
+    members
+        .iter()
+        .filter_map(|member| skills.iter().find(|(user, _)| user == member))
+        .map(|(_, skill)| skill)
+        .sum()
+}
+
+/// Splits the participants of an event into [count] teams.
+/// They are shuffled first, so that regenerating gives other teams
+pub fn split(
+    participants: &[u64],
+    skills: &[(u64, i64)],
+    count: usize,
+    by: TeamSplit,
+) -> Vec<Vec<u64>> {
+    let mut players = participants.to_vec();
+    players.shuffle(&mut rand::thread_rng());
+
+    match by {
+        TeamSplit::Random => deal(&players, count),
+        TeamSplit::Skill => {
+            let players: Vec<(u64, i64)> = players
+                .iter()
+                .map(|player| (*player, total_skill(&[*player], skills)))
+                .collect();
+            balance(&players, count)
+        }
+    }
+}
+
+/// One line per team: its role, total skill when known, and members
+pub fn describe(teams: &[Team], skills: &[(u64, i64)]) -> String {
+    teams
+        .iter()
+        .map(|team| {
+            let mut line = format!("**Team {}** <@&{}>", team.number, team.role_id);
+            if !skills.is_empty() {
+                line += &format!(" (skill {})", total_skill(&team.members, skills));
+            }
+            line += &format!(
+                ": {}",
+                team.members
+                    .iter()
+                    .map(|member| format!("<@{}>", member))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+            line
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// How many teams the event's category has room for, once its current teams are cleared
+pub async fn room(
+    http: &Http,
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+) -> Result<usize, Error> {
+    let category_id = ChannelId::new(event.category_id);
+    let team_channels: Vec<u64> = db
+        .get_teams(event_id)
+        .await?
+        .iter()
+        .flat_map(|team| [team.text_channel_id, team.voice_channel_id])
+        .collect();
+    let used = GuildId::new(event.server_id)
+        .channels(http)
+        .await?
+        .values()
+        .filter(|c| c.parent_id == Some(category_id) && !team_channels.contains(&u64::from(c.id)))
+        .count();
+    Ok(MAX_CATEGORY_CHANNELS.saturating_sub(used) / TEAM_CHANNELS.len())
+}
+
+/// Deletes what was made of a team that couldn't be created
+async fn discard(
+    http: &Http,
+    db: &Repository,
+    event_id: i64,
+    guild_id: GuildId,
+    role_id: RoleId,
+    channels: &[u64],
+) {
+    for channel_id in channels {
+        if let Err(e) = ChannelId::new(*channel_id).delete(http).await {
+            warn!("Couldn't delete channel {} of a failed team: {}", channel_id, e);
+        }
+        if let Err(e) = db.delete_channel(event_id, *channel_id).await {
+            warn!("Couldn't forget channel {} of a failed team: {}", channel_id, e);
+        }
+    }
+    if let Err(e) = guild_id.delete_role(http, role_id).await {
+        warn!("Couldn't delete role {} of a failed team: {}", role_id, e);
+    }
+}
+
+/// Creates the role and the private text and voice channels of team [number] in the event's category,
+/// and gives the role to [members]. The channels are recorded as the event's.
+/// Nothing is left behind when a step fails
+pub async fn create_team(
+    http: &Http,
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+    number: u32,
+    mut members: Vec<u64>,
+    bot: UserId,
+) -> Result<Team, Error> {
+    members.sort_unstable();
+    let guild_id = GuildId::new(event.server_id);
+    let name = format!("{} Team {}", event.name, number);
+    let role = guild_id
+        .create_role(http, EditRole::new().name(&name))
+        .await?;
+
+    let permissions = vec![
+        PermissionOverwrite {
+            allow: Permissions::empty(),
+            deny: Permissions::VIEW_CHANNEL,
+            kind: PermissionOverwriteType::Role(guild_id.everyone_role()),
+        },
+        PermissionOverwrite {
+            allow: Permissions::VIEW_CHANNEL
+                | Permissions::SEND_MESSAGES
+                | Permissions::CONNECT
+                | Permissions::SPEAK,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Role(role.id),
+        },
+        PermissionOverwrite {
+            allow: Permissions::VIEW_CHANNEL
+                | Permissions::SEND_MESSAGES
+                | Permissions::CONNECT
+                | Permissions::SPEAK,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Role(RoleId::new(event.manager_role_id)),
+        },
+        PermissionOverwrite {
+            allow: Permissions::VIEW_CHANNEL
+                | Permissions::MANAGE_CHANNELS
+                | Permissions::SEND_MESSAGES
+                | Permissions::CONNECT,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Member(bot),
+        },
+    ];
+    let mut channels = vec![];
+    for kind in TEAM_CHANNELS {
+        let created = guild_id
+            .create_channel(
+                http,
+                CreateChannel::new(format!("team-{}", number))
+                    .kind(kind)
+                    .permissions(permissions.clone())
+                    .category(ChannelId::new(event.category_id)),
+            )
+            .await;
+        match created {
+            Ok(channel) => channels.push(u64::from(channel.id)),
+            Err(e) => {
+                discard(http, db, event_id, guild_id, role.id, &channels).await;
+                return Err(e.into());
+            }
+        }
+    }
+    if let Err(e) = db.insert_channels(event_id, channels.clone()).await {
+        discard(http, db, event_id, guild_id, role.id, &channels).await;
+        return Err(e.into());
+    }
+
+    for member in &members {
+        if let Err(e) = http
+            .add_member_role(guild_id, UserId::new(*member), role.id, None)
+            .await
+        {
+            warn!("Couldn't give the role of {} to {}: {}", name, member, e);
+        }
+    }
+
+    let team = Team {
+        number,
+        role_id: u64::from(role.id),
+        text_channel_id: channels[0],
+        voice_channel_id: channels[1],
+        members,
+    };
+    if let Err(e) = db.insert_team(event_id, team.clone()).await {
+        discard(http, db, event_id, guild_id, role.id, &channels).await;
+        return Err(e.into());
+    }
+    info!(
+        "Created team {} of event {}(id {}) with {} members",
+        number,
+        event.name,
+        event_id,
+        team.members.len()
+    );
+
+    Ok(team)
+}
+
+/// Deletes the roles and channels of an event's teams, then forgets them.
+/// Returns how many teams there were
+pub async fn clear(
+    http: &Http,
+    db: &Repository,
+    event_id: i64,
+    event: &EventData,
+) -> Result<usize, Error> {
+    let guild_id = GuildId::new(event.server_id);
+    let teams = db.get_teams(event_id).await?;
+
+    // People may have deleted a team's role or channels by hand
+    for team in &teams {
+        if let Err(e) = guild_id.delete_role(http, RoleId::new(team.role_id)).await {
+            warn!(
+                "Couldn't delete the role of team {} of event {}: {}",
+                team.number, event.name, e
+            );
+        }
+        for channel_id in [team.text_channel_id, team.voice_channel_id] {
+            if let Err(e) = ChannelId::new(channel_id).delete(http).await {
+                warn!(
+                    "Couldn't delete channel {} of team {} of event {}: {}",
+                    channel_id, team.number, event.name, e
+                );
+            }
+            db.delete_channel(event_id, channel_id).await?;
+        }
+    }
+    db.delete_teams(event_id).await?;
+
+    info!(
+        "Cleared the {} teams of event {}(id {})",
+        teams.len(),
+        event.name,
+        event_id
+    );
+    Ok(teams.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deals_even_teams() {
+        let teams = deal(&[1, 2, 3, 4, 5, 6, 7], 3);
+        assert_eq!(teams, vec![vec![1, 4, 7], vec![2, 5], vec![3, 6]]);
+        assert_eq!(deal(&[1], 2), vec![vec![1], vec![]]);
+    }
+
+    #[test]
+    fn balances_skills() {
+        let players = [(1, 10), (2, 9), (3, 8), (4, 7), (5, 1), (6, 1)];
+        let teams = balance(&players, 2);
+        let totals: Vec<i64> = teams
+            .iter()
+            .map(|team| total_skill(team, &players))
+            .collect();
+        assert_eq!(teams.iter().map(Vec::len).collect::<Vec<_>>(), vec![3, 3]);
+        assert!((totals[0] - totals[1]).abs() <= 2, "{:?}", totals);
+
+        // Sizes never differ by more than one, whatever the skills
+        let lopsided = [(1, 1000), (2, 1), (3, 1), (4, 1), (5, 1)];
+        let sizes: Vec<usize> = balance(&lopsided, 2).iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![2, 3]);
+    }
+
+    #[test]
+    fn splits_everyone() {
+        let participants: Vec<u64> = (1..=10).collect();
+        let skills = [(1, 5), (2, 3)];
+        for by in [TeamSplit::Random, TeamSplit::Skill] {
+            let mut everyone: Vec<u64> = split(&participants, &skills, 3, by)
+                .into_iter()
+                .flatten()
+                .collect();
+            everyone.sort_unstable();
+            assert_eq!(everyone, participants);
+        }
+    }
+}
@@ -0,0 +1,337 @@
+use crate::bacchus::Error;
+use crate::events::EventData;
+use crate::scheduler;
+use futures::TryStreamExt;
+use poise::serenity_prelude::{
+    ChannelId, CreateAttachment, CreateMessage, Embed, Http, Message, UserId,
+};
+use serde::Serialize;
+use tracing::{info, warn};
+
+/// The conversations of an event, saved before its channels are deleted
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Transcript {
+    pub event: String,
+    /// Unix seconds
+    pub exported_at: i64,
+    pub channels: Vec<ChannelTranscript>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ChannelTranscript {
+    pub id: u64,
+    pub name: String,
+    /// Oldest first
+    pub messages: Vec<TranscriptMessage>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TranscriptMessage {
+    pub id: u64,
+    pub author_id: u64,
+    pub author: String,
+    /// RFC 3339
+    pub timestamp: String,
+    pub content: String,
+    pub attachments: Vec<TranscriptAttachment>,
+    pub embeds: Vec<TranscriptEmbed>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TranscriptAttachment {
+    pub filename: String,
+    pub url: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TranscriptEmbed {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub url: Option<String>,
+    pub fields: Vec<(String, String)>,
+}
+
+impl From<&Embed> for TranscriptEmbed {
+    fn from(embed: &Embed) -> Self {
+        TranscriptEmbed {
+            title: embed.title.clone(),
+            description: embed.description.clone(),
+            url: embed.url.clone(),
+            fields: embed
+                .fields
+                .iter()
+                .map(|field| (field.name.clone(), field.value.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl From<&Message> for TranscriptMessage {
+    fn from(message: &Message) -> Self {
+        TranscriptMessage {
+            id: u64::from(message.id),
+            author_id: u64::from(message.author.id),
+            author: message.author.name.clone(),
+            timestamp: message.timestamp.to_string(),
+            content: message.content.clone(),
+            attachments: message
+                .attachments
+                .iter()
+                .map(|attachment| TranscriptAttachment {
+                    filename: attachment.filename.clone(),
+                    url: attachment.url.clone(),
+                })
+                .collect(),
+            embeds: message.embeds.iter().map(TranscriptEmbed::from).collect(),
+        }
+    }
+}
+
+impl Transcript {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Transcripts are always serializable")
+    }
+
+    /// A standalone page, readable in any browser
+    pub fn to_html(&self) -> String {
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n\
+             <style>body{{font-family:sans-serif;max-width:60em;margin:auto}}\
+             .author{{font-weight:bold}}.time{{color:#777;font-size:small}}\
+             blockquote{{border-left:4px solid #5865f2;margin-left:0;padding-left:1em}}</style>\n\
+             </head>\n<body>\n<h1>{0}</h1>\n",
+            escape(&self.event)
+        );
+
+        for channel in &self.channels {
+            html += &format!("<h2>#{}</h2>\n", escape(&channel.name));
+            if channel.messages.is_empty() {
+                html += "<p><em>No messages</em></p>\n";
+            }
+            for message in &channel.messages {
+                html += &format!(
+                    "<div class=\"message\" id=\"{}\">\n<span class=\"author\">{}</span> <span class=\"time\">{}</span>\n",
+                    message.id,
+                    escape(&message.author),
+                    escape(&message.timestamp)
+                );
+                if !message.content.is_empty() {
+                    html += &format!(
+                        "<p>{}</p>\n",
+                        escape(&message.content).replace('\n', "<br>")
+                    );
+                }
+                for attachment in &message.attachments {
+                    html += &format!(
+                        "<p><a href=\"{}\">{}</a></p>\n",
+                        escape(&attachment.url),
+                        escape(&attachment.filename)
+                    );
+                }
+                for embed in &message.embeds {
+                    html += "<blockquote>\n";
+                    match (&embed.title, &embed.url) {
+                        (Some(title), Some(url)) => {
+                            html += &format!(
+                                "<strong><a href=\"{}\">{}</a></strong>\n",
+                                escape(url),
+                                escape(title)
+                            )
+                        }
+                        (Some(title), None) => {
+                            html += &format!("<strong>{}</strong>\n", escape(title))
+                        }
+                        _ => {}
+                    }
+                    if let Some(description) = &embed.description {
+                        html += &format!("<p>{}</p>\n", escape(description).replace('\n', "<br>"));
+                    }
+                    for (name, value) in &embed.fields {
+                        html += &format!("<p><em>{}</em>: {}</p>\n", escape(name), escape(value));
+                    }
+                    html += "</blockquote>\n";
+                }
+                html += "</div>\n";
+            }
+        }
+
+        html + "</body>\n</html>\n"
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// A name safe to use in a file name
+fn file_stem(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    match stem.trim_matches('-') {
+        "" => String::from("event"),
+        stem => stem.to_string(),
+    }
+}
+
+/// Reads every message of [channel_ids]. A channel without messages to read,
+/// like a forum or one already deleted, is left out
+pub async fn collect(
+    http: &Http,
+    event: &EventData,
+    channel_ids: &[u64],
+) -> Transcript {
+    let mut channels = Vec::new();
+
+    for channel_id in channel_ids {
+        let channel_id = ChannelId::new(*channel_id);
+        let name = match channel_id.to_channel(http).await {
+            Ok(channel) => channel.guild().map(|c| c.name).unwrap_or_default(),
+            Err(e) => {
+                warn!(
+                    "Couldn't fetch channel {} of event {} for its transcript: {}",
+                    channel_id, event.name, e
+                );
+                continue;
+            }
+        };
+
+        let messages: Vec<Message> = match channel_id.messages_iter(http).try_collect().await {
+            Ok(messages) => messages,
+            Err(e) => {
+                warn!(
+                    "Couldn't read the messages of channel {} of event {}: {}",
+                    channel_id, event.name, e
+                );
+                continue;
+            }
+        };
+
+        channels.push(ChannelTranscript {
+            id: u64::from(channel_id),
+            name,
+            // Discord pages from the newest message
+            messages: messages.iter().rev().map(TranscriptMessage::from).collect(),
+        });
+    }
+
+    Transcript {
+        event: event.name.clone(),
+        exported_at: scheduler::now(),
+        channels,
+    }
+}
+
+/// Saves the conversations of an event in [log_channel], or in [recipient]'s DMs.
+/// Nothing is exported when there is neither
+pub async fn export(
+    http: &Http,
+    event: &EventData,
+    channel_ids: &[u64],
+    log_channel: Option<u64>,
+    recipient: Option<UserId>,
+) -> Result<(), Error> {
+    let destination = match (log_channel, recipient) {
+        (Some(channel_id), _) => ChannelId::new(channel_id),
+        (None, Some(user_id)) => user_id.create_dm_channel(http).await?.id,
+        (None, None) => {
+            info!(
+                "No log channel on server {}, the transcript of {} isn't saved",
+                event.server_id, event.name
+            );
+            return Ok(());
+        }
+    };
+
+    let transcript = collect(http, event, channel_ids).await;
+    let stem = file_stem(&event.name);
+    destination
+        .send_message(
+            http,
+            CreateMessage::new()
+                .content(format!("Transcript of {}", event.name))
+                .add_file(CreateAttachment::bytes(
+                    transcript.to_html(),
+                    format!("{}-transcript.html", stem),
+                ))
+                .add_file(CreateAttachment::bytes(
+                    transcript.to_json(),
+                    format!("{}-transcript.json", stem),
+                )),
+        )
+        .await?;
+
+    info!(
+        "Exported the transcript of event {} from server {}",
+        event.name, event.server_id
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript() -> Transcript {
+        Transcript {
+            event: String::from("LAN <party>"),
+            exported_at: 1_700_000_000,
+            channels: vec![ChannelTranscript {
+                id: 1,
+                name: String::from("general"),
+                messages: vec![TranscriptMessage {
+                    id: 2,
+                    author_id: 3,
+                    author: String::from("alice"),
+                    timestamp: String::from("2023-11-14T22:13:20Z"),
+                    content: String::from("bring <snacks> & drinks\nplease"),
+                    attachments: vec![TranscriptAttachment {
+                        filename: String::from("map.png"),
+                        url: String::from("https://cdn.example/map.png"),
+                    }],
+                    embeds: vec![TranscriptEmbed {
+                        title: Some(String::from("Rules")),
+                        description: None,
+                        url: None,
+                        fields: vec![(String::from("Start"), String::from("20:00"))],
+                    }],
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn renders_html() {
+        let html = transcript().to_html();
+        assert!(html.contains("<title>LAN &lt;party&gt;</title>"));
+        assert!(html.contains("bring &lt;snacks&gt; &amp; drinks<br>please"));
+        assert!(html.contains("<a href=\"https://cdn.example/map.png\">map.png</a>"));
+        assert!(html.contains("<strong>Rules</strong>"));
+        assert!(!html.contains("<snacks>"));
+    }
+
+    #[test]
+    fn renders_json() {
+        let json: serde_json::Value = serde_json::from_str(&transcript().to_json()).unwrap();
+        let message = &json["channels"][0]["messages"][0];
+        assert_eq!(message["author"], "alice");
+        assert_eq!(message["timestamp"], "2023-11-14T22:13:20Z");
+        assert_eq!(
+            message["attachments"][0]["url"],
+            "https://cdn.example/map.png"
+        );
+        assert_eq!(message["embeds"][0]["fields"][0][1], "20:00");
+    }
+
+    #[test]
+    fn file_names() {
+        assert_eq!(file_stem("LAN party #3"), "LAN-party--3");
+        assert_eq!(file_stem("???"), "event");
+    }
+}